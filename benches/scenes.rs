@@ -0,0 +1,43 @@
+//! Times a small render of every scene in the registry, or of the scenes
+//! named on the command line. Run with `cargo bench --bench scenes`, or
+//! `cargo bench --bench scenes -- cornell-box final-scene` for some of them.
+
+use ray_tracing_in_one_weekend::{camera::CameraBuilder, scenes::Scene};
+use std::time::Instant;
+
+const WIDTH: u64 = 160;
+const SAMPLES: u64 = 16;
+
+fn main() {
+    // cargo passes --bench along
+    let names: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let scenes: Vec<Scene> = match names.is_empty() {
+        true => Scene::ALL.to_vec(),
+        false => names
+            .iter()
+            .map(|name| name.parse().unwrap_or_else(|err| panic!("{err}")))
+            .collect(),
+    };
+
+    println!("{WIDTH} pixels wide, {SAMPLES} samples per pixel");
+    for scene in scenes {
+        fastrand::seed(1);
+        let camera = CameraBuilder::default()
+            .with_image_width(WIDTH)
+            .with_samples_per_pixel(SAMPLES)
+            .with_max_bounces(50);
+        let (elapsed, pixels) = scene.with_world(camera, |world, camera| {
+            let start = Instant::now();
+            let render = camera.build().render_parallel(&world);
+            (start.elapsed().as_secs_f64(), render.beauty.pixels().len())
+        });
+        println!(
+            "{:<18} {elapsed:>7.2} s {:>7.3} Msamples/s",
+            scene.name(),
+            (pixels as u64 * SAMPLES) as f64 / elapsed / 1e6
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Compares tracing rays through a HitList of spheres against a SphereSet.
+//! Run with `cargo bench`, and `cargo bench --features simd` (optionally with
+//! RUSTFLAGS="-C target-feature=+avx") to see the SIMD backends. Add the f32
+//! feature for single precision.
+
+use ray_tracing_in_one_weekend::{
+    color::Color,
+    float::{random, Float},
+    hit::{Hit, HitList},
+    materials::Lambertian,
+    ray::Ray,
+    shapes::{Sphere, SphereSet},
+    vec3::Pos,
+};
+use std::{hint::black_box, time::Instant};
+
+const RAYS: usize = 200_000;
+
+fn main() {
+    fastrand::seed(1);
+    let mat = Lambertian {
+        albedo: Color(0.5, 0.5, 0.5),
+    };
+
+    // the small spheres of the cover scene
+    let centers: Vec<_> = (-11..11)
+        .flat_map(|a| (-11..11).map(move |b| (a as Float, b as Float)))
+        .map(|(a, b)| Pos(a + 0.9 * random(), 0.2, b + 0.9 * random()))
+        .collect();
+
+    let mut list = HitList::default();
+    for center in &centers {
+        list.push(Sphere::new(*center, 0.2, &mat));
+    }
+    let set = SphereSet::new(
+        centers
+            .iter()
+            .map(|center| Sphere::new(*center, 0.2, &mat))
+            .collect(),
+    );
+
+    // from the camera position towards random points on the ground
+    let rays: Vec<_> = (0..RAYS)
+        .map(|_| {
+            let origin = Pos(13., 2., 3.);
+            let target = Pos(random() * 24. - 12., 0.2, random() * 24. - 12.);
+            Ray {
+                origin,
+                dir: target - origin,
+                time: 0.,
+            }
+        })
+        .collect();
+
+    // both have to find the same hits
+    for ray in rays.iter().take(1000) {
+        let t1 = list.hit(ray, 0.0..Float::INFINITY).map(|hit| hit.t);
+        let t2 = set.hit(ray, 0.0..Float::INFINITY).map(|hit| hit.t);
+        assert_eq!(t1, t2);
+    }
+
+    let time = |name: &str, world: &dyn Hit| {
+        let start = Instant::now();
+        let mut hits = 0;
+        for ray in &rays {
+            hits += black_box(world.hit(ray, 0.0..Float::INFINITY)).is_some() as usize;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        println!(
+            "{name:<10} {:>7.2} Mrays/s ({hits} hits)",
+            RAYS as f64 / elapsed / 1e6
+        );
+        elapsed
+    };
+
+    let backend = match (cfg!(feature = "simd"), cfg!(target_feature = "avx")) {
+        (false, _) => "scalar",
+        (true, _) if cfg!(feature = "f32") => "sse f32",
+        (true, false) => "sse2",
+        (true, true) => "avx",
+    };
+    println!(
+        "{} spheres, {RAYS} rays, {backend} backend",
+        set.spheres().len()
+    );
+    let list_time = time("HitList", &list);
+    let set_time = time("SphereSet", &set);
+    println!("speedup    {:>7.2}x", list_time / set_time);
+}
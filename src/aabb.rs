@@ -0,0 +1,92 @@
+use crate::{
+    float::{gamma, Float},
+    ray::Ray,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl Aabb {
+    /// Contains nothing, the identity for `union`
+    pub const EMPTY: Aabb = Aabb {
+        min: Pos(Float::INFINITY, Float::INFINITY, Float::INFINITY),
+        max: Pos(
+            Float::NEG_INFINITY,
+            Float::NEG_INFINITY,
+            Float::NEG_INFINITY,
+        ),
+    };
+
+    /// Box with two opposite corners at a and b
+    pub fn new(a: Pos, b: Pos) -> Self {
+        Self {
+            min: Pos(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())),
+            max: Pos(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())),
+        }
+    }
+
+    /// Smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Pos(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            max: Pos(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        }
+    }
+
+    /// The part of the interval where the ray is inside the box (slab test)
+    pub fn ray_interval(&self, ray: &Ray, interval: Range<Float>) -> Option<Range<Float>> {
+        let mut range = interval;
+        let axes = [
+            (self.min.x(), self.max.x(), ray.origin.x(), ray.dir.x()),
+            (self.min.y(), self.max.y(), ray.origin.y(), ray.dir.y()),
+            (self.min.z(), self.max.z(), ray.origin.z(), ray.dir.z()),
+        ];
+        for (min, max, origin, dir) in axes {
+            let inv = 1. / dir;
+            let (t0, t1) = ((min - origin) * inv, (max - origin) * inv);
+            // max and min ignore the NaN of a ray lying in a slab boundary,
+            // and the far side is pushed out by its rounding error so rays
+            // grazing an edge aren't lost (pbrt 6.8.2)
+            range.start = range.start.max(t0.min(t1));
+            range.end = range.end.min(t0.max(t1) * (1. + 2. * gamma(3)));
+        }
+        // flat boxes, like those around axis aligned triangles, are hit too
+        (range.start <= range.end).then_some(range)
+    }
+
+    pub fn translate(&self, offset: &Vec3) -> Aabb {
+        Aabb {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    /// The eight corners of the box
+    pub fn corners(&self) -> [Pos; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Pos(a.x(), a.y(), a.z()),
+            Pos(b.x(), a.y(), a.z()),
+            Pos(a.x(), b.y(), a.z()),
+            Pos(b.x(), b.y(), a.z()),
+            Pos(a.x(), a.y(), b.z()),
+            Pos(b.x(), a.y(), b.z()),
+            Pos(a.x(), b.y(), b.z()),
+            Pos(b.x(), b.y(), b.z()),
+        ]
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{color::Color, hit::HitInfo, ray::Ray};
+
+/// Arbitrary output variables: extra per-pixel layers of first-hit data
+/// rendered alongside the beauty pass, for compositing and denoising.
+/// Pixels where the camera ray misses everything are black.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aov {
+    /// Base color of the surface
+    Albedo,
+    /// Shading normal, raw [-1, 1] components
+    Normal,
+    /// Distance from the camera along the ray
+    Depth,
+    /// World space position
+    Position,
+    /// Index of the top level object, as a pseudo-random color. Taken from
+    /// the pixel's first sample rather than averaged.
+    ObjectId,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 5] = [
+        Aov::Albedo,
+        Aov::Normal,
+        Aov::Depth,
+        Aov::Position,
+        Aov::ObjectId,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::ObjectId => "object-id",
+        }
+    }
+
+    /// Value of the AOV for the first hit of the camera ray `ray`
+    pub(super) fn value(&self, ray: &Ray, hit_info: Option<&HitInfo>) -> Color {
+        let Some(hit_info) = hit_info else {
+            return Color(0., 0., 0.);
+        };
+
+        match self {
+            Aov::Albedo => hit_info.mat.albedo(hit_info),
+            Aov::Normal => Color::from(hit_info.normal.to_vec()),
+            Aov::Depth => {
+                // camera rays aren't unit length, t counts in their length
+                let distance = hit_info.t * ray.dir.length();
+                Color(distance, distance, distance)
+            }
+            Aov::Position => Color::from(hit_info.pos.to_vec()),
+            Aov::ObjectId => Color::from_id(hit_info.object_id as u64),
+        }
+    }
+}
+
+impl std::str::FromStr for Aov {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Aov::ALL
+            .into_iter()
+            .find(|aov| aov.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Aov::ALL.iter().map(Aov::name).collect();
+                format!("unknown AOV '{s}', expected one of: {}", names.join(", "))
+            })
+    }
+}
@@ -1,21 +1,37 @@
 #![allow(unused)]
 
-use super::Camera;
-use crate::vec3::*;
+use super::{Aov, Camera, Filter, RenderMode};
+use crate::{
+    color::Color,
+    denoise::Denoiser,
+    float::{consts, Float},
+    sampler::SamplerKind,
+    tone_map::ColorPipeline,
+    vec3::*,
+};
 
 // it's a little overkill...
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct CameraBuilder {
-    aspect_ratio: Option<f64>,
+    aspect_ratio: Option<Float>,
     image_width: Option<u64>,
     samples_per_pixel: Option<u64>,
     max_bounces: Option<u64>,
-    vfov: Option<f64>,
+    filter: Option<Filter>,
+    sampler: Option<SamplerKind>,
+    vfov: Option<Float>,
     lookat: Option<Pos>,
     lookfrom: Option<Pos>,
     vup: Option<Vec3>,
-    defocus_angle: Option<f64>,
-    focus_dist: Option<f64>,
+    defocus_angle: Option<Float>,
+    focus_dist: Option<Float>,
+    shutter_open: Option<Float>,
+    shutter_close: Option<Float>,
+    render_mode: Option<RenderMode>,
+    aovs: Option<Vec<Aov>>,
+    denoiser: Option<Denoiser>,
+    color_pipeline: Option<ColorPipeline>,
+    background: Option<Color>,
 }
 
 macro_rules! with_param {
@@ -30,25 +46,42 @@ macro_rules! with_param {
 }
 
 impl CameraBuilder {
-    with_param!(aspect_ratio, f64, with_aspect_ratio);
+    with_param!(aspect_ratio, Float, with_aspect_ratio);
     with_param!(image_width, u64, with_image_width);
     with_param!(samples_per_pixel, u64, with_samples_per_pixel);
     with_param!(max_bounces, u64, with_max_bounces);
-    with_param!(vfov, f64, with_vfov);
+    with_param!(filter, Filter, with_filter);
+    with_param!(sampler, SamplerKind, with_sampler);
+    with_param!(vfov, Float, with_vfov);
     with_param!(lookat, Pos, with_lookat);
     with_param!(lookfrom, Pos, with_lookfrom);
     with_param!(vup, Vec3, with_vup);
-    with_param!(defocus_angle, f64, with_defocus_angle);
-    with_param!(focus_dist, f64, with_focus_dist);
+    with_param!(defocus_angle, Float, with_defocus_angle);
+    with_param!(focus_dist, Float, with_focus_dist);
+    with_param!(shutter_open, Float, with_shutter_open);
+    with_param!(shutter_close, Float, with_shutter_close);
+    with_param!(render_mode, RenderMode, with_render_mode);
+    with_param!(aovs, Vec<Aov>, with_aovs);
+    with_param!(denoiser, Denoiser, with_denoiser);
+    with_param!(color_pipeline, ColorPipeline, with_color_pipeline);
+    with_param!(background, Color, with_background);
 
-    pub fn with_vfov_degrees(self, vfov: f64) -> Self {
+    pub fn with_vfov_degrees(self, vfov: Float) -> Self {
         self.with_vfov(vfov.to_radians())
     }
 
-    pub fn with_defocus_angle_degrees(self, defocus_angle: f64) -> Self {
+    pub fn with_defocus_angle_degrees(self, defocus_angle: Float) -> Self {
         self.with_defocus_angle(defocus_angle.to_radians())
     }
 
+    /// Rays are sent at uniformly random times between open and close,
+    /// blurring objects that move in that interval. Moving objects have their
+    /// own start and end times, 0 and 1 unless set, and hold still outside
+    /// them.
+    pub fn with_shutter(self, open: Float, close: Float) -> Self {
+        self.with_shutter_open(open).with_shutter_close(close)
+    }
+
     /// Preset resolution, aspect ratio, samples, and bounces for a final render.
     /// Image width: 1920
     /// Aspect ratio: 16/9
@@ -81,12 +114,21 @@ impl CameraBuilder {
             self.image_width.unwrap_or(100),
             self.samples_per_pixel.unwrap_or(10),
             self.max_bounces.unwrap_or(10),
-            self.vfov.unwrap_or(std::f64::consts::PI / 2.),
-            self.lookat.unwrap_or(Vec3(0., 0., -1.)),
-            self.lookfrom.unwrap_or(Vec3(0., 0., 0.)),
-            self.vup.unwrap_or(Pos(0., 1., 0.)),
+            self.filter.unwrap_or_default(),
+            self.sampler.unwrap_or_default(),
+            self.vfov.unwrap_or(consts::PI / 2.),
+            self.lookat.unwrap_or(Pos(0., 0., -1.)),
+            self.lookfrom.unwrap_or(Pos::ORIGIN),
+            self.vup.unwrap_or(Vec3(0., 1., 0.)),
             self.defocus_angle.unwrap_or(0.),
             self.focus_dist.unwrap_or(10.),
+            self.shutter_open.unwrap_or(0.),
+            self.shutter_close.unwrap_or(0.),
+            self.render_mode.unwrap_or_default(),
+            self.aovs.unwrap_or_default(),
+            self.denoiser,
+            self.color_pipeline.unwrap_or_default(),
+            self.background,
         )
     }
 }
@@ -0,0 +1,121 @@
+use crate::float::{consts, Float};
+
+/// Pixel reconstruction filter.
+/// Each sample is splatted into every pixel whose center lies within the
+/// filter radius, weighted by the filter at the offset from that center.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Constant over the pixel square, every sample only lands in its own pixel
+    #[default]
+    Box,
+    /// Linear falloff, radius 1
+    Tent,
+    /// Gaussian with a standard deviation of 0.5, radius 1.5
+    Gaussian,
+    /// Mitchell-Netravali cubic with B = C = 1/3, radius 2
+    Mitchell,
+    /// Lanczos windowed sinc with 2 lobes, radius 2
+    Lanczos,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 5] = [
+        Filter::Box,
+        Filter::Tent,
+        Filter::Gaussian,
+        Filter::Mitchell,
+        Filter::Lanczos,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::Box => "box",
+            Filter::Tent => "tent",
+            Filter::Gaussian => "gaussian",
+            Filter::Mitchell => "mitchell",
+            Filter::Lanczos => "lanczos",
+        }
+    }
+
+    /// Distance in pixels from the center at which the filter falls to zero
+    pub fn radius(&self) -> Float {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell | Filter::Lanczos => 2.,
+        }
+    }
+
+    /// How many neighboring pixels in each direction a sample can land in
+    pub(super) fn reach(&self) -> usize {
+        // samples are up to half a pixel away from their own pixel's center
+        (self.radius() + 0.5).ceil() as usize - 1
+    }
+
+    /// Weight of a sample at offset (dx, dy) pixels from a pixel center.
+    /// Mitchell and Lanczos have negative lobes.
+    pub fn weight(&self, dx: Float, dy: Float) -> Float {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    // all the filters are separable
+    fn weight_1d(&self, x: Float) -> Float {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.;
+        }
+
+        match self {
+            Filter::Box => 1.,
+            Filter::Tent => 1. - x,
+            Filter::Gaussian => {
+                // shifted down so it reaches zero at the radius
+                let gaussian = |x: Float| (-x * x / (2. * 0.5 * 0.5)).exp();
+                (gaussian(x) - gaussian(self.radius())).max(0.)
+            }
+            Filter::Mitchell => {
+                const B: Float = 1. / 3.;
+                const C: Float = 1. / 3.;
+                let (x2, x3) = (x * x, x * x * x);
+                let value = if x < 1. {
+                    (12. - 9. * B - 6. * C) * x3 + (-18. + 12. * B + 6. * C) * x2 + (6. - 2. * B)
+                } else {
+                    (-B - 6. * C) * x3
+                        + (6. * B + 30. * C) * x2
+                        + (-12. * B - 48. * C) * x
+                        + (8. * B + 24. * C)
+                };
+                value / 6.
+            }
+            Filter::Lanczos => sinc(x) * sinc(x / self.radius()),
+        }
+    }
+}
+
+fn sinc(x: Float) -> Float {
+    use consts::PI;
+
+    if x < 1e-5 {
+        1.
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Filter::ALL
+            .into_iter()
+            .find(|filter| filter.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Filter::ALL.iter().map(Filter::name).collect();
+                format!(
+                    "unknown filter '{s}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -1,17 +1,54 @@
+mod aov;
 mod camera_builder;
+mod filter;
+mod render_mode;
 
+pub use aov::*;
 pub use camera_builder::*;
+pub use filter::*;
+pub use render_mode::*;
+
+use crate::{
+    color::*,
+    denoise::Denoiser,
+    float::Float,
+    hit::*,
+    image::Image,
+    materials::PathState,
+    ray::*,
+    sampler::{Sampler, SamplerKind},
+    tone_map::ColorPipeline,
+    vec3::*,
+};
+
+/// The framebuffers filled by a render
+pub struct Render {
+    /// Linear radiance, see `color_pipeline` for displaying it
+    pub beauty: Image,
+    pub aovs: Vec<(Aov, Image)>,
+    pub color_pipeline: ColorPipeline,
+}
 
-use crate::{color::*, hit::*, ray::*, vec3::*};
+// what a worker sends back after rendering one line
+struct LineRender {
+    line: usize,
+    // filter weighted color sums and weights, for lines
+    // line - filter reach ..= line + filter reach
+    beauty: Vec<Vec<(Color, Float)>>,
+    // one line of pixels for each aov
+    aovs: Vec<Vec<Color>>,
+}
 
 pub struct Camera {
-    // pub aspect_ratio: f64,
+    // pub aspect_ratio: Float,
     image_width: u64,
     image_height: u64,
-    // vfov: f64,
-    // focal_length: f64,
+    // vfov: Float,
+    // focal_length: Float,
     // viewport_upper_left: Pos,
-    pixel_00_pos: Pos,
+    // relative to the camera center, adding the two far from the origin would
+    // lose the tiny viewport to rounding
+    pixel_00_offset: Vec3,
     // viewport_u: Vec3,
     // viewport_v: Vec3,
     pixel_du: Vec3,
@@ -19,33 +56,55 @@ pub struct Camera {
     center: Pos,
 
     samples_per_pixel: u64,
-    pixel_sample_scale: f64,
+    pixel_sample_scale: Float,
     max_bounces: u64,
+    filter: Filter,
+    sampler: SamplerKind,
 
-    defocus_angle: f64,
+    shutter_open: Float,
+    shutter_close: Float,
+
+    defocus_angle: Float,
     defocus_u: Vec3,
     defocus_v: Vec3,
+    focus_dist: Float,
+
+    render_mode: RenderMode,
+    aovs: Vec<Aov>,
+    denoiser: Option<Denoiser>,
+    color_pipeline: ColorPipeline,
+    // what rays that hit nothing see, a sky gradient when unset
+    background: Option<Color>,
 }
 
 impl Camera {
     #[allow(clippy::too_many_arguments)]
     /// Use CameraBuilder instead.
     pub fn new(
-        aspect_ratio: f64,
+        aspect_ratio: Float,
         image_width: u64,
         samples_per_pixel: u64,
         max_bounces: u64,
-        vfov: f64,
+        filter: Filter,
+        sampler: SamplerKind,
+        vfov: Float,
         lookat: Pos,
         lookfrom: Pos,
         vup: Vec3,
-        defocus_angle: f64,
-        focus_dist: f64,
+        defocus_angle: Float,
+        focus_dist: Float,
+        shutter_open: Float,
+        shutter_close: Float,
+        render_mode: RenderMode,
+        aovs: Vec<Aov>,
+        denoiser: Option<Denoiser>,
+        color_pipeline: ColorPipeline,
+        background: Option<Color>,
     ) -> Self {
-        let pixel_sample_scale = 1.0 / samples_per_pixel as f64;
+        let pixel_sample_scale = 1.0 / samples_per_pixel as Float;
 
         // minimum height of 1
-        let image_height = ((image_width as f64 / aspect_ratio) as u64).max(1);
+        let image_height = ((image_width as Float / aspect_ratio) as u64).max(1);
 
         // camera info
         let camera_position = lookfrom;
@@ -55,25 +114,23 @@ impl Camera {
 
         // camera viewport info
         let viewport_height = 2. * h * focus_dist;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
+        let viewport_width = viewport_height * (image_width as Float / image_height as Float);
 
         // calculate orthonormal basis (u, v, w)
-        let w = (lookfrom - lookat).unit_vec();
-        let u = vup.cross(&w).unit_vec();
-        let v = w.cross(&u);
+        let basis = Mat3::look_at(lookfrom, lookat, vup);
+        let (u, v, w) = (basis.col(0), basis.col(1), basis.col(2));
 
         // vectors along viewport top and left edges
         let viewport_u = viewport_width * u;
         let viewport_v = viewport_height * -v;
 
         // horizontal and vertical vec between in-world pixel centers
-        let pixel_du = viewport_u / image_width as f64;
-        let pixel_dv = viewport_v / image_height as f64;
+        let pixel_du = viewport_u / image_width as Float;
+        let pixel_dv = viewport_v / image_height as Float;
 
         // position of upper left pixel
-        let viewport_upper_left =
-            camera_position - (focus_dist * w) - viewport_u / 2. - viewport_v / 2.;
-        let pixel_00_pos = viewport_upper_left + pixel_du / 2. + pixel_dv / 2.;
+        let viewport_upper_left = -(focus_dist * w) - viewport_u / 2. - viewport_v / 2.;
+        let pixel_00_offset = viewport_upper_left + pixel_du / 2. + pixel_dv / 2.;
 
         let defocus_radius = focus_dist * (defocus_angle / 2.).tan();
         let defocus_u = u * defocus_radius;
@@ -86,7 +143,7 @@ impl Camera {
             // vfov,
             // focal_length,
             // viewport_upper_left,
-            pixel_00_pos,
+            pixel_00_offset,
             // viewport_u,
             // viewport_v,
             pixel_du,
@@ -96,14 +153,26 @@ impl Camera {
             samples_per_pixel,
             pixel_sample_scale,
             max_bounces,
+            filter,
+            sampler,
+
+            shutter_open,
+            shutter_close,
 
             defocus_angle,
             defocus_u,
             defocus_v,
+            focus_dist,
+
+            render_mode,
+            aovs,
+            denoiser,
+            color_pipeline,
+            background,
         }
     }
 
-    pub fn render_parallel(&self, world: &(impl Hit + Sync)) {
+    pub fn render_parallel(&self, world: &(impl Hit + Sync)) -> Render {
         use crossbeam;
         use std::{sync::mpsc, thread};
 
@@ -124,31 +193,72 @@ impl Camera {
         };
 
         // channel for receiving work
-        let (report_tx, report_rx) = mpsc::channel::<(usize, Vec<Color>)>();
+        let (report_tx, report_rx) = mpsc::channel::<LineRender>();
 
         // create "accumulator" thread
         // responsible for  receiving data from threads
         let image_height = self.image_height;
         let image_width = self.image_width;
+        // the denoiser needs albedo and normal guides even if they aren't outputs
+        let mut aovs = self.aovs.clone();
+        if self.denoiser.is_some() {
+            for guide in [Aov::Albedo, Aov::Normal] {
+                if !aovs.contains(&guide) {
+                    aovs.push(guide);
+                }
+            }
+        }
+        let aovs = &aovs;
+
+        let (width, height) = (image_width as usize, image_height as usize);
+        let reach = self.filter.reach();
+        let aov_count = aovs.len();
         let start_time_d = start_time;
         let accumulator = thread::spawn(move || {
             let mut lines_completed = 0;
 
-            let mut image = vec![Color(0., 0., 0.); (image_height * image_width) as usize];
-            while let Ok((line, pixels)) = report_rx.recv() {
-                // gross clone..............agggghghh
-                image[(line * image_width as usize)..((line + 1) * image_width as usize)]
-                    .clone_from_slice(&pixels);
+            // samples can land in neighboring lines, so sum them up
+            // and only divide by the weights at the end
+            let mut beauty = vec![(Color(0., 0., 0.), 0.); width * height];
+            let mut aov_images = vec![Image::new(width, height); aov_count];
+            while let Ok(render) = report_rx.recv() {
+                for (i, splats) in render.beauty.iter().enumerate() {
+                    let Some(y) = (render.line + i).checked_sub(reach) else {
+                        continue;
+                    };
+                    if y >= height {
+                        continue;
+                    }
+
+                    for (sum, splat) in beauty[(y * width)..((y + 1) * width)]
+                        .iter_mut()
+                        .zip(splats)
+                    {
+                        sum.0 += splat.0;
+                        sum.1 += splat.1;
+                    }
+                }
+                for (image, pixels) in aov_images.iter_mut().zip(&render.aovs) {
+                    image.set_line(render.line, pixels);
+                }
                 lines_completed += 1;
 
                 eprint!(
                     "\rRender Progress: {:>6.2} %\tTime: {:.1?}               \r",
-                    lines_completed as f64 / image_height as f64 * 100.,
+                    lines_completed as Float / image_height as Float * 100.,
                     start_time_d.elapsed()
                 );
             }
 
-            image
+            let mut beauty_image = Image::new(width, height);
+            for (pixel, (color, weight)) in beauty_image.pixels_mut().iter_mut().zip(beauty) {
+                // negative lobes can cancel out, leave those pixels black
+                if weight > 0. {
+                    *pixel = color / weight;
+                }
+            }
+
+            (beauty_image, aov_images)
         });
 
         // create "worker" threads
@@ -162,28 +272,36 @@ impl Camera {
                 let assign_rx = assign_rx.clone();
                 s.spawn(move || {
                     while let Ok(line) = assign_rx.recv() {
-                        let pixels = (0..self.image_width)
-                            .map(|x| self.pixel_color(world, x, line))
-                            .collect();
-
-                        report_tx.send((line as usize, pixels)).unwrap();
+                        report_tx.send(self.render_line(world, aovs, line)).unwrap();
                     }
                 });
             }
         });
 
-        // output final image
-        let image = accumulator.join().unwrap();
+        let (beauty, aov_images) = accumulator.join().unwrap();
+        let mut aovs: Vec<(Aov, Image)> = aovs.iter().copied().zip(aov_images).collect();
+
+        let beauty = match self.denoiser {
+            Some(denoiser) => {
+                eprint!("\rDenoising                                            \r");
+                let guide = |aov| &aovs.iter().find(|(a, _)| *a == aov).unwrap().1;
+                denoiser.denoise(&beauty, guide(Aov::Albedo), guide(Aov::Normal))
+            }
+            None => beauty,
+        };
+        // drop the guides nobody asked for
+        aovs.retain(|(aov, _)| self.aovs.contains(aov));
 
-        println!("P3\n{} {}\n255", self.image_width, self.image_height);
-        // write pixel colors to stdout
-        for pixel in image {
-            pixel.write_color();
-        }
         eprintln!(
             "\rFinished rendering in {:.4} seconds                           ",
-            start_time.elapsed().as_millis() as f64 / 1000.0
+            start_time.elapsed().as_millis() as Float / 1000.0
         );
+
+        Render {
+            beauty,
+            aovs,
+            color_pipeline: self.color_pipeline,
+        }
     }
 
     pub fn _render_parallel(&self, world: &(impl Hit + Sync)) {
@@ -213,7 +331,7 @@ impl Camera {
                 lines += i;
                 eprint!(
                     "\rRender Progress: {:>6.2} %\tTime: {:.1?}                 \r",
-                    lines as f64 / image_height as f64 * 100.,
+                    lines as Float / image_height as Float * 100.,
                     start_time.elapsed()
                 )
             }
@@ -234,11 +352,19 @@ impl Camera {
                     let end: u64 = start + lines;
 
                     // rendering
+                    let mut sampler = self.sampler.create(self.samples_per_pixel);
                     let mut pixels = Vec::with_capacity((lines * self.image_width) as usize);
                     for y in start..end {
                         tx.send(1).unwrap();
                         for x in 0..self.image_width {
-                            pixels.push(self.pixel_color(world, x, y));
+                            let color = (0..self.samples_per_pixel)
+                                .map(|index| {
+                                    let (_, ray) = self.camera_sample(&mut *sampler, x, y, index);
+                                    let hit_info = world.hit(&ray, 0.0..Float::INFINITY);
+                                    self.sample_color(&ray, hit_info.as_ref(), world, &mut *sampler)
+                                })
+                                .sum::<Color>();
+                            pixels.push(color * self.pixel_sample_scale);
                         }
                     }
 
@@ -259,15 +385,96 @@ impl Camera {
         }
         eprintln!(
             "\rFinished rendering in {:.4} seconds                           ",
-            start_time.elapsed().as_millis() as f64 / 1000.0
+            start_time.elapsed().as_millis() as Float / 1000.0
         );
     }
 
-    fn pixel_color(&self, world: &impl Hit, x: u64, y: u64) -> Color {
-        (0..self.samples_per_pixel)
-            .map(|_| self.ray_color(&self.get_ray(x, y), world, 0))
-            .sum::<Color>()
-            * self.pixel_sample_scale
+    // renders every sample whose pixel is on line y, splatting the beauty
+    // color into the pixels the filter covers and averaging the aovs
+    fn render_line(&self, world: &impl Hit, aovs: &[Aov], y: u64) -> LineRender {
+        let width = self.image_width as usize;
+        let reach = self.filter.reach() as isize;
+
+        let mut beauty = vec![vec![(Color(0., 0., 0.), 0.); width]; 2 * reach as usize + 1];
+        let mut aov_lines = vec![vec![Color(0., 0., 0.); width]; aovs.len()];
+        let mut sampler = self.sampler.create(self.samples_per_pixel);
+
+        for x in 0..self.image_width {
+            for index in 0..self.samples_per_pixel {
+                let (offset, ray) = self.camera_sample(&mut *sampler, x, y, index);
+                // the first hit is shared by the beauty pass and the aovs
+                let hit_info = world.hit(&ray, 0.0..Float::INFINITY);
+                let color = self.sample_color(&ray, hit_info.as_ref(), world, &mut *sampler);
+
+                for dy in -reach..=reach {
+                    for dx in -reach..=reach {
+                        let px = x as isize + dx;
+                        if !(0..width as isize).contains(&px) {
+                            continue;
+                        }
+
+                        let weight = self
+                            .filter
+                            .weight(offset.0 - dx as Float, offset.1 - dy as Float);
+                        if weight != 0. {
+                            let splat = &mut beauty[(dy + reach) as usize][px as usize];
+                            splat.0 += color * weight;
+                            splat.1 += weight;
+                        }
+                    }
+                }
+
+                for (line, aov) in aov_lines.iter_mut().zip(aovs) {
+                    let value = aov.value(&ray, hit_info.as_ref());
+                    match aov {
+                        // an average of ids is the color of no object, so
+                        // the pixel keeps its first sample's
+                        Aov::ObjectId if index == 0 => line[x as usize] = value,
+                        Aov::ObjectId => {}
+                        _ => line[x as usize] += value * self.pixel_sample_scale,
+                    }
+                }
+            }
+        }
+
+        LineRender {
+            line: y as usize,
+            beauty,
+            aovs: aov_lines,
+        }
+    }
+
+    // color of a single sample with the camera ray's first hit already
+    // found, depending on the render mode
+    fn sample_color(
+        &self,
+        ray: &Ray,
+        hit_info: Option<&HitInfo>,
+        world: &impl Hit,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        match self.render_mode {
+            RenderMode::Beauty => {
+                self.shade(ray, hit_info, world, 0, &mut PathState::default(), sampler)
+            }
+            RenderMode::Bounces => {
+                let mut path = PathState::default();
+                let bounces = match hit_info
+                    .and_then(|hit_info| hit_info.mat.scatter(ray, hit_info, &mut path, sampler))
+                {
+                    Some((ray, _)) => self.ray_bounces(&ray, world, 1, &mut path, sampler),
+                    None => 0,
+                };
+                // ray_color scatters at most max_bounces + 1 times, the last
+                // one cut off
+                let value = bounces as Float / (self.max_bounces + 1) as Float;
+                Color(value, value, value)
+            }
+            mode => match hit_info {
+                Some(hit_info) => mode.first_hit_color(ray, hit_info, self.focus_dist),
+                None => Color(0., 0., 0.),
+            },
+        }
     }
 
     // outputs to stdout rn...
@@ -276,15 +483,18 @@ impl Camera {
 
         println!("P3\n{} {}\n255", self.image_width, self.image_height);
 
+        let mut sampler = self.sampler.create(self.samples_per_pixel);
+
         for y in 0..self.image_height {
             eprint!("\rLines remaining: {:>6}", self.image_height - y);
 
             for x in 0..self.image_width {
                 let mut color = Color(0., 0., 0.);
 
-                for _ in 0..self.samples_per_pixel {
-                    let ray = self.get_ray(x, y);
-                    color += self.ray_color(&ray, world, 0);
+                for index in 0..self.samples_per_pixel {
+                    let (_, ray) = self.camera_sample(&mut *sampler, x, y, index);
+                    let hit_info = world.hit(&ray, 0.0..Float::INFINITY);
+                    color += self.sample_color(&ray, hit_info.as_ref(), world, &mut *sampler);
                 }
 
                 (color * self.pixel_sample_scale).write_color();
@@ -293,17 +503,38 @@ impl Camera {
 
         eprintln!(
             "\rFinished rendering in {:.4} seconds                           ",
-            start_time.elapsed().as_millis() as f64 / 1000.0
+            start_time.elapsed().as_millis() as Float / 1000.0
         );
     }
 
-    fn ray_color(&self, ray: &Ray, world: &impl Hit, bounces: u64) -> Color {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &impl Hit,
+        bounces: u64,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
         if bounces > self.max_bounces {
             return Color(0., 0., 0.);
         }
 
+        let hit_info = world.hit(ray, 0.0..Float::INFINITY);
+        self.shade(ray, hit_info.as_ref(), world, bounces, path, sampler)
+    }
+
+    // ray_color with the ray's hit already found
+    fn shade(
+        &self,
+        ray: &Ray,
+        hit_info: Option<&HitInfo>,
+        world: &impl Hit,
+        bounces: u64,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
         // object intersection
-        if let Some(hit_info) = world.hit(ray, 0.001..f64::INFINITY) {
+        if let Some(hit_info) = hit_info {
             // uniform distribution
             // let next_dir = Vec3::random_on_hemisphere(&hit_info.normal);
             // lambertian distribution
@@ -314,13 +545,22 @@ impl Camera {
             // };
             // return 0.5 * self.ray_color(&next_ray, world, bounces + 1);
 
-            if let Some((ray, attenuation)) = hit_info.mat.scatter(ray, &hit_info) {
-                return attenuation * self.ray_color(&ray, world, bounces + 1);
+            // absorbed on the way if the ray went through colored glass or the like
+            let transmittance = path.media.transmittance(hit_info.t * ray.dir.length());
+            let emitted = hit_info.mat.emitted(hit_info);
+
+            if let Some((ray, attenuation)) = hit_info.mat.scatter(ray, hit_info, path, sampler) {
+                return transmittance
+                    * (emitted
+                        + attenuation * self.ray_color(&ray, world, bounces + 1, path, sampler));
             }
-            return Color(0., 0., 0.);
+            return transmittance * emitted;
         }
 
         // background color
+        if let Some(background) = self.background {
+            return background;
+        }
         let unit_ray = ray.dir.unit_vec();
         let scaled_y = (unit_ray.y() + 1.0) * 0.5;
         let c1 = Color(1., 1., 1.);
@@ -330,30 +570,76 @@ impl Camera {
         // Color(1., 1., 1.)
     }
 
-    fn get_ray(&self, i: u64, j: u64) -> Ray {
-        let origin = if self.defocus_angle <= 0. {
-            self.center
+    // same walk as ray_color, but only counts how many times the ray scattered
+    fn ray_bounces(
+        &self,
+        ray: &Ray,
+        world: &impl Hit,
+        bounces: u64,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> u64 {
+        // the same cutoff as ray_color
+        if bounces > self.max_bounces {
+            return bounces;
+        }
+
+        match world
+            .hit(ray, 0.0..Float::INFINITY)
+            .and_then(|hit_info| hit_info.mat.scatter(ray, &hit_info, path, sampler))
+        {
+            Some((ray, _)) => self.ray_bounces(&ray, world, bounces + 1, path, sampler),
+            None => bounces,
+        }
+    }
+
+    // starts sample `index` of pixel (i, j)
+    // returns where in the pixel the sample landed and its ray
+    fn camera_sample(
+        &self,
+        sampler: &mut dyn Sampler,
+        i: u64,
+        j: u64,
+        index: u64,
+    ) -> ((Float, Float), Ray) {
+        sampler.start_pixel_sample((i, j), index);
+        let offset = sample_square(sampler);
+        (offset, self.get_ray(i, j, offset, sampler))
+    }
+
+    // ray through pixel (i, j), offset from the pixel center by a fraction of a pixel
+    fn get_ray(&self, i: u64, j: u64, offset: (Float, Float), sampler: &mut dyn Sampler) -> Ray {
+        let lens_offset = if self.defocus_angle <= 0. {
+            Vec3(0., 0., 0.)
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(sampler.get_2d())
         };
 
-        let offset = sample_square();
         // let offset = offset.0 * self.pixel_du + offset.1 * self.pixel_dv;
-        let viewport_target = self.pixel_00_pos
-            + self.pixel_du * (offset.0 + i as f64)
-            + self.pixel_dv * (offset.1 + j as f64);
-        let dir = viewport_target - origin;
+        let viewport_target = self.pixel_00_offset
+            + self.pixel_du * (offset.0 + i as Float)
+            + self.pixel_dv * (offset.1 + j as Float);
+        let origin = self.center + lens_offset;
+        let dir = viewport_target - lens_offset;
+
+        let time = if self.shutter_close > self.shutter_open {
+            self.shutter_open + (self.shutter_close - self.shutter_open) * sampler.get_1d()
+        } else {
+            self.shutter_open
+        };
 
-        Ray { origin, dir }
+        Ray { origin, dir, time }
     }
 
-    fn defocus_disk_sample(&self) -> Pos {
-        let p = Vec3::rand_in_unit_disk();
-        self.center + p.0 * self.defocus_u + p.1 * self.defocus_v
+    // offset of a point on the lens from the camera center
+    fn defocus_disk_sample(&self, u: (Float, Float)) -> Vec3 {
+        let p = Vec3::in_unit_disk_from_sample(u);
+        p.0 * self.defocus_u + p.1 * self.defocus_v
     }
 }
 
 // return [-0.5, -0.5] - [0.5, 0.5]
-fn sample_square() -> (f64, f64) {
-    (fastrand::f64() - 0.5, fastrand::f64() - 0.5)
+fn sample_square(sampler: &mut dyn Sampler) -> (Float, Float) {
+    let (u, v) = sampler.get_2d();
+    (u - 0.5, v - 0.5)
 }
@@ -0,0 +1,97 @@
+use crate::{color::Color, float::Float, hit::HitInfo, ray::Ray};
+
+/// What the camera writes for each pixel.
+/// Everything except `Beauty` is a false-color debug view of the first hit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Full path traced radiance
+    #[default]
+    Beauty,
+    /// Shading normal, mapped from [-1, 1] to [0, 1] per channel
+    Normals,
+    /// Hit distance, white at the camera fading to black
+    Depth,
+    /// Green for front faces, red for back faces
+    FrontFace,
+    /// Texture coordinates as red (u) and green (v)
+    Uv,
+    /// A stable pseudo-random color per material
+    MaterialId,
+    /// Number of bounces the path took, black (none) to white (cut off by max
+    /// bounces)
+    Bounces,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 7] = [
+        RenderMode::Beauty,
+        RenderMode::Normals,
+        RenderMode::Depth,
+        RenderMode::FrontFace,
+        RenderMode::Uv,
+        RenderMode::MaterialId,
+        RenderMode::Bounces,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderMode::Beauty => "beauty",
+            RenderMode::Normals => "normals",
+            RenderMode::Depth => "depth",
+            RenderMode::FrontFace => "front-face",
+            RenderMode::Uv => "uv",
+            RenderMode::MaterialId => "material-id",
+            RenderMode::Bounces => "bounces",
+        }
+    }
+
+    /// False color for a first-hit debug mode of the camera ray `ray`.
+    /// `depth_scale` is the distance that maps to 50% gray in depth mode.
+    pub(super) fn first_hit_color(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        depth_scale: Float,
+    ) -> Color {
+        match self {
+            RenderMode::Normals => (Color::from(hit_info.normal.to_vec()) + 1.) * 0.5,
+            RenderMode::Depth => {
+                // camera rays aren't unit length, t counts in their length
+                let distance = hit_info.t * ray.dir.length();
+                let value = depth_scale / (depth_scale + distance);
+                Color(value, value, value)
+            }
+            RenderMode::FrontFace => match hit_info.front_face {
+                true => Color(0., 1., 0.),
+                false => Color(1., 0., 0.),
+            },
+            RenderMode::Uv => Color(hit_info.u, hit_info.v, 0.),
+            RenderMode::MaterialId => material_color(hit_info),
+            RenderMode::Beauty | RenderMode::Bounces => {
+                unreachable!("{} is not a first-hit mode", self.name())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for RenderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RenderMode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = RenderMode::ALL.iter().map(RenderMode::name).collect();
+                format!(
+                    "unknown render mode '{s}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+// materials don't have ids, so use the address of the material instead
+fn material_color(hit_info: &HitInfo) -> Color {
+    Color::from_id(hit_info.mat as *const _ as *const () as usize as u64)
+}
@@ -0,0 +1,119 @@
+use ray_tracing_in_one_weekend::{
+    camera::{Aov, CameraBuilder, Filter, RenderMode},
+    denoise::Denoiser,
+    sampler::SamplerKind,
+    scenes::Scene,
+    tone_map::ColorPipeline,
+};
+
+const USAGE: &str = "\
+usage: ray_tracing_in_one_weekend [options] > image.ppm
+
+options:
+    --scene <name>   random-spheres (default), cornell-box,
+                     checkered-spheres, perlin-spheres, earth,
+                     cornell-smoke or final-scene
+    --mode <mode>    beauty (default), normals, depth, front-face, uv,
+                     material-id or bounces
+    --aov <aovs>     comma separated extra layers to write next to the image:
+                     albedo, normal, depth, position, object-id
+    --aov-prefix <p> path prefix of the aov files, written as
+                     <p>.<aov>.pfm (default: render)
+    --filter <f>     pixel filter: box (default), tent, gaussian, mitchell
+                     or lanczos
+    --sampler <s>    independent (default), stratified, halton or sobol
+    --denoise        filter the beauty image after rendering
+    --exposure <ev>  brighten or darken the image by this many stops
+    --tone-map <op>  clamp (default), reinhard, aces or agx
+    -h, --help       print this message";
+
+/// Options parsed from the command line
+pub struct Options {
+    pub scene: Scene,
+    pub render_mode: RenderMode,
+    pub aovs: Vec<Aov>,
+    pub aov_prefix: String,
+    pub filter: Filter,
+    pub sampler: SamplerKind,
+    pub denoiser: Option<Denoiser>,
+    pub color_pipeline: ColorPipeline,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            scene: Scene::default(),
+            render_mode: RenderMode::default(),
+            aovs: vec![],
+            aov_prefix: "render".to_string(),
+            filter: Filter::default(),
+            sampler: SamplerKind::default(),
+            denoiser: None,
+            color_pipeline: ColorPipeline::default(),
+        }
+    }
+}
+
+impl Options {
+    /// Parses the process arguments, exiting with a usage message on bad input
+    pub fn from_args() -> Self {
+        match Options::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(err) => {
+                eprintln!("{err}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Applies the render settings chosen on the command line
+    pub fn apply(&self, cam: CameraBuilder) -> CameraBuilder {
+        let cam = cam
+            .with_render_mode(self.render_mode)
+            .with_aovs(self.aovs.clone())
+            .with_filter(self.filter)
+            .with_sampler(self.sampler)
+            .with_color_pipeline(self.color_pipeline);
+
+        match self.denoiser {
+            Some(denoiser) => cam.with_denoiser(denoiser),
+            None => cam,
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+
+            match arg.as_str() {
+                "--scene" => options.scene = value()?.parse()?,
+                "--mode" => options.render_mode = value()?.parse()?,
+                "--aov" => {
+                    for aov in value()?.split(',') {
+                        options.aovs.push(aov.parse()?);
+                    }
+                }
+                "--aov-prefix" => options.aov_prefix = value()?,
+                "--filter" => options.filter = value()?.parse()?,
+                "--sampler" => options.sampler = value()?.parse()?,
+                "--denoise" => options.denoiser = Some(Denoiser::default()),
+                "--exposure" => {
+                    let exposure = value()?;
+                    options.color_pipeline.exposure = exposure
+                        .parse()
+                        .map_err(|_| format!("invalid exposure '{exposure}'"))?;
+                }
+                "--tone-map" => options.color_pipeline.tone_map = value()?.parse()?,
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("unknown option '{arg}'")),
+            }
+        }
+
+        Ok(options)
+    }
+}
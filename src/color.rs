@@ -1,46 +1,124 @@
-pub use crate::vec3::Vec3 as Color;
+use crate::{
+    float::{random, Float},
+    tone_map::ColorPipeline,
+    vec3::{impl_vec3_binop, Mat3, Vec3},
+};
+use std::io::{self, Write};
+
+/// Linear RGB radiance or reflectance.
+/// Colors multiply componentwise, unlike vectors.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color(pub Float, pub Float, pub Float);
 
 impl Color {
     pub fn write_color(&self) {
-        let transform = |x: f64| {
-            // linear to gamma 2 space
-            // F(x) = x^2
-            // F-1(x) = sqrt(x)
-            // then scale [0, 1) to [0, 255]
-            (x.max(0.).sqrt().clamp(0., 0.999) * 256.) as usize
-        };
-        println!(
-            "{} {} {}",
-            transform(self.r()),
-            transform(self.g()),
-            transform(self.b())
-        );
+        self.write_color_to(&mut io::stdout(), &ColorPipeline::default())
+            .unwrap();
+    }
+
+    /// Writes the color as a line of an ASCII PPM
+    pub fn write_color_to(&self, out: &mut impl Write, pipeline: &ColorPipeline) -> io::Result<()> {
+        let [r, g, b] = pipeline.encode(*self);
+        writeln!(out, "{r} {g} {b}")
+    }
+
+    /// A stable pseudo-random color for an id, so neighbouring ids look different
+    pub fn from_id(id: u64) -> Self {
+        // splitmix64 finalizer
+        let mut x = id.wrapping_add(0x9e3779b97f4a7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+
+        let channel = |shift: u32| ((x >> shift) & 0xff) as Float / 255.;
+        Color(channel(0), channel(8), channel(16))
     }
 
     pub fn random() -> Self {
-        Color(fastrand::f64(), fastrand::f64(), fastrand::f64())
+        Color(random(), random(), random())
     }
 
-    pub fn random_range(min: f64, max: f64) -> Self {
-        Color(randf64(min, max), randf64(min, max), randf64(min, max))
+    pub fn random_range(min: Float, max: Float) -> Self {
+        Color(
+            rand_float(min, max),
+            rand_float(min, max),
+            rand_float(min, max),
+        )
+    }
+
+    /// Perceived brightness, with the Rec. 709 weights
+    pub fn luminance(&self) -> Float {
+        0.2126 * self.0 + 0.7152 * self.1 + 0.0722 * self.2
+    }
+
+    /// Squared euclidean distance between the colors
+    pub fn distance_squared(&self, other: &Color) -> Float {
+        let d = (self.0 - other.0, self.1 - other.1, self.2 - other.2);
+        d.0 * d.0 + d.1 * d.1 + d.2 * d.2
     }
 
     #[inline(always)]
-    pub fn r(&self) -> f64 {
+    pub fn r(&self) -> Float {
         self.0
     }
 
     #[inline(always)]
-    pub fn g(&self) -> f64 {
+    pub fn g(&self) -> Float {
         self.1
     }
 
     #[inline(always)]
-    pub fn b(&self) -> f64 {
+    pub fn b(&self) -> Float {
         self.2
     }
 }
 
-fn randf64(min: f64, max: f64) -> f64 {
-    fastrand::f64() * (max - min) + min
+fn rand_float(min: Float, max: Float) -> Float {
+    random() * (max - min) + min
+}
+
+// for visualizing vectors, e.g. normals and positions in aovs
+impl From<Vec3> for Color {
+    fn from(v: Vec3) -> Self {
+        Color(v.0, v.1, v.2)
+    }
+}
+
+// color space conversions
+impl std::ops::Mul<Color> for Mat3 {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Self::Output {
+        let m = &self.0;
+        Color(
+            m[0][0] * rhs.0 + m[0][1] * rhs.1 + m[0][2] * rhs.2,
+            m[1][0] * rhs.0 + m[1][1] * rhs.1 + m[1][2] * rhs.2,
+            m[2][0] * rhs.0 + m[2][1] * rhs.1 + m[2][2] * rhs.2,
+        )
+    }
+}
+
+impl std::iter::Sum for Color {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Color(0., 0., 0.), |acc, x| acc + x)
+    }
 }
+
+impl_vec3_binop!(Add, add, Color, Color => Color);
+impl_vec3_binop!(Add, add, Color, Float => Color);
+impl_vec3_binop!(AddAssign, add_assign, Color, Color, assign);
+
+impl_vec3_binop!(Sub, sub, Color, Color => Color);
+impl_vec3_binop!(Sub, sub, Color, Float => Color);
+impl_vec3_binop!(SubAssign, sub_assign, Color, Color, assign);
+
+impl_vec3_binop!(Mul, mul, Color, Color => Color);
+impl_vec3_binop!(Mul, mul, Color, Float => Color);
+impl_vec3_binop!(Mul, mul, Float, Color => Color);
+impl_vec3_binop!(MulAssign, mul_assign, Color, Color, assign);
+impl_vec3_binop!(MulAssign, mul_assign, Color, Float, assign);
+
+impl_vec3_binop!(Div, div, Color, Color => Color);
+impl_vec3_binop!(Div, div, Color, Float => Color);
+impl_vec3_binop!(DivAssign, div_assign, Color, Color, assign);
+impl_vec3_binop!(DivAssign, div_assign, Color, Float, assign);
@@ -0,0 +1,117 @@
+use crate::{color::Color, float::Float, image::Image};
+
+/// Edge-avoiding à-trous wavelet filter (Dammertz et al. 2010).
+///
+/// Repeatedly blurs the image with a 5x5 B3-spline kernel whose taps spread
+/// out by a factor of two each pass. Every tap is weighted by how similar its
+/// color, normal and albedo are to the center pixel, so the blur stops at
+/// geometry and material edges instead of smearing them.
+#[derive(Clone, Copy, Debug)]
+pub struct Denoiser {
+    /// Number of filter passes, the kernel covers 4 * 2^iterations pixels
+    pub iterations: u32,
+    /// Color difference tolerated in the first pass, halved every pass
+    pub color_sigma: Float,
+    /// Normal difference tolerated
+    pub normal_sigma: Float,
+    /// Albedo difference tolerated
+    pub albedo_sigma: Float,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            color_sigma: 0.6,
+            normal_sigma: 0.3,
+            albedo_sigma: 0.1,
+        }
+    }
+}
+
+// B3-spline weights for offsets -2..=2
+const KERNEL: [Float; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+impl Denoiser {
+    /// Filters a beauty image using albedo and normal guide images of the same size
+    pub fn denoise(&self, beauty: &Image, albedo: &Image, normal: &Image) -> Image {
+        // filter the lighting only, so surface color detail isn't blurred
+        let guard = |a: Color| Color(a.r().max(0.01), a.g().max(0.01), a.b().max(0.01));
+        let mut irradiance = beauty.clone();
+        for (i, pixel) in irradiance.pixels_mut().iter_mut().enumerate() {
+            *pixel /= guard(albedo.pixels()[i]);
+        }
+
+        for iteration in 0..self.iterations {
+            let color_sigma = self.color_sigma * Float::powi(0.5, iteration as i32);
+            irradiance = self.pass(&irradiance, albedo, normal, 1 << iteration, color_sigma);
+        }
+
+        for (i, pixel) in irradiance.pixels_mut().iter_mut().enumerate() {
+            *pixel *= guard(albedo.pixels()[i]);
+        }
+        irradiance
+    }
+
+    fn pass(
+        &self,
+        color: &Image,
+        albedo: &Image,
+        normal: &Image,
+        step: usize,
+        color_sigma: Float,
+    ) -> Image {
+        let (width, height) = (color.width(), color.height());
+        let mut out = Image::new(width, height);
+
+        // exp(-d^2 / sigma^2), sigma of 0 turns the term off
+        let edge_weight = |d2: Float, sigma: Float| match sigma > 0. {
+            true => (-d2 / (sigma * sigma)).exp(),
+            false => 1.,
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let (c_p, n_p, a_p) = (color.get(x, y), normal.get(x, y), albedo.get(x, y));
+
+                let mut sum = Color(0., 0., 0.);
+                let mut total_weight = 0.;
+                for (j, ky) in KERNEL.iter().enumerate() {
+                    let qy = y as isize + (j as isize - 2) * step as isize;
+                    if !(0..height as isize).contains(&qy) {
+                        continue;
+                    }
+
+                    for (i, kx) in KERNEL.iter().enumerate() {
+                        let qx = x as isize + (i as isize - 2) * step as isize;
+                        if !(0..width as isize).contains(&qx) {
+                            continue;
+                        }
+                        let (qx, qy) = (qx as usize, qy as usize);
+
+                        let c_q = color.get(qx, qy);
+                        let weight = kx
+                            * ky
+                            * edge_weight(c_p.distance_squared(&c_q), color_sigma)
+                            * edge_weight(
+                                n_p.distance_squared(&normal.get(qx, qy)),
+                                self.normal_sigma,
+                            )
+                            * edge_weight(
+                                a_p.distance_squared(&albedo.get(qx, qy)),
+                                self.albedo_sigma,
+                            );
+
+                        sum += c_q * weight;
+                        total_weight += weight;
+                    }
+                }
+
+                // the center tap always has a weight of at least 9/64
+                out.set(x, y, sum / total_weight);
+            }
+        }
+
+        out
+    }
+}
@@ -0,0 +1,31 @@
+//! The floating point type of all the geometry and shading math.
+//! f64 by default, the `f32` feature switches to f32, which halves the memory
+//! and doubles the lanes per SIMD register.
+
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+/// The largest float below 1, for keeping samples in [0, 1)
+pub const ONE_MINUS_EPSILON: Float = 1. - Float::EPSILON / 2.;
+
+/// Uniform random float in [0, 1)
+pub fn random() -> Float {
+    #[cfg(not(feature = "f32"))]
+    return fastrand::f64();
+    #[cfg(feature = "f32")]
+    return fastrand::f32();
+}
+
+/// Bound on the relative rounding error after n floating point operations,
+/// (1 + e)^n <= 1 + gamma(n) (Higham, via pbrt)
+pub fn gamma(n: u32) -> Float {
+    let ne = n as Float * Float::EPSILON * 0.5;
+    ne / (1. - ne)
+}
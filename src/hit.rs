@@ -1,28 +1,98 @@
-use crate::{materials::Material, ray::*, vec3::*};
+use crate::{aabb::Aabb, color::Color, float::Float, materials::Material, ray::*, vec3::*};
 use std::ops::Range;
 
 // information on ray intersection
+#[derive(Clone, Copy)]
 pub struct HitInfo<'a> {
     // the position of the intersection
     pub pos: Pos,
 
-    // the unit-length surface normal
-    pub normal: Vec3,
+    // bound on the absolute rounding error of pos in each axis
+    pub pos_error: Vec3,
+
+    // the unit-length normal materials shade with, facing the ray
+    pub normal: Normal,
+
+    // the true normal of the surface, also facing the ray. It differs from
+    // the shading normal where a normal or bump map bends that one, and is
+    // the one rays leave the surface along.
+    pub geometric_normal: Normal,
 
     // the parameter to the ray
-    pub t: f64,
+    pub t: Float,
 
     // whether the front or back face was hit
     pub front_face: bool,
 
+    // surface texture coordinates, both in [0, 1]
+    pub u: Float,
+    pub v: Float,
+
+    // how pos changes with u and v, for tangent frames, zero where unknown
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+
+    // interpolated color of mesh vertices, white for everything else
+    pub vertex_color: Color,
+
+    // index of the top level object that was hit, filled in by HitList
+    pub object_id: usize,
+
     // the material of the object that was hit
     pub mat: &'a dyn Material,
 }
 
+impl HitInfo<'_> {
+    /// A ray leaving the surface. Instead of a fixed epsilon the origin is
+    /// pushed along the geometric normal just past the rounding error of pos, so it
+    /// can't hit the surface it starts on at any scene scale (pbrt 6.8.6).
+    pub fn spawn_ray(&self, dir: Vec3, time: Float) -> Ray {
+        let n = self.geometric_normal.to_vec();
+        let err = self.pos_error;
+        // distance along the normal to the edge of the error box
+        let d = n.0.abs() * err.0 + n.1.abs() * err.1 + n.2.abs() * err.2;
+        let offset = match n.dot(&dir) < 0. {
+            true => n * -d,
+            false => n * d,
+        };
+
+        // round away from the surface, the addition could undo a tiny offset
+        let away = |p: Float, o: Float| match o {
+            o if o > 0. => p.next_up(),
+            o if o < 0. => p.next_down(),
+            _ => p,
+        };
+        let p = self.pos + offset;
+        Ray {
+            origin: Pos(
+                away(p.0, offset.0),
+                away(p.1, offset.1),
+                away(p.2, offset.2),
+            ),
+            dir,
+            time,
+        }
+    }
+}
+
 // anything that can be hit by a ray
 pub trait Hit {
     // calculates the hit info
-    fn hit(&self, ray: &Ray, ray_t_interval: Range<f64>) -> Option<HitInfo>;
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>>;
+
+    // box containing the object at every moment between time 0 and 1
+    fn bounding_box(&self) -> Aabb;
+}
+
+// lets one object be shared, e.g. by several transformed instances
+impl<T: Hit + ?Sized> Hit for &T {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        (**self).hit(ray, ray_t_interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (**self).bounding_box()
+    }
 }
 
 #[derive(Default)]
@@ -37,10 +107,23 @@ impl<'a> HitList<'a> {
 }
 
 impl Hit for HitList<'_> {
-    fn hit(&self, ray: &Ray, ray_t_interval: Range<f64>) -> Option<HitInfo> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
         self.objects
             .iter()
-            .filter_map(|object| object.hit(ray, ray_t_interval.clone()))
+            .enumerate()
+            .filter_map(|(object_id, object)| {
+                let hit_info = object.hit(ray, ray_t_interval.clone())?;
+                Some(HitInfo {
+                    object_id,
+                    ..hit_info
+                })
+            })
             .min_by(|info1, info2| info1.t.total_cmp(&info2.t))
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.objects
+            .iter()
+            .fold(Aabb::EMPTY, |acc, object| acc.union(&object.bounding_box()))
+    }
 }
@@ -0,0 +1,77 @@
+use crate::{color::Color, tone_map::ColorPipeline};
+use std::io::{self, Write};
+
+/// A framebuffer of linear colors, stored row by row from the top left
+#[derive(Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    /// A black image
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color(0., 0., 0.); width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Overwrites one row of pixels
+    pub fn set_line(&mut self, y: usize, line: &[Color]) {
+        self.pixels[(y * self.width)..((y + 1) * self.width)].copy_from_slice(line);
+    }
+
+    /// Writes the image as an ASCII PPM, converted to 8 bit sRGB by the pipeline
+    pub fn write_ppm(&self, pipeline: &ColorPipeline, out: &mut impl Write) -> io::Result<()> {
+        let mut out = io::BufWriter::new(out);
+        writeln!(out, "P3\n{} {}\n255", self.width, self.height)?;
+        for pixel in &self.pixels {
+            pixel.write_color_to(&mut out, pipeline)?;
+        }
+        out.flush()
+    }
+
+    /// Writes the image as a little-endian PFM, keeping the raw linear values.
+    /// PFM rows go from bottom to top.
+    pub fn write_pfm(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut out = io::BufWriter::new(out);
+        write!(out, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        for line in self.pixels.chunks(self.width).rev() {
+            for pixel in line {
+                for channel in [pixel.r(), pixel.g(), pixel.b()] {
+                    // already f32 with the f32 feature
+                    #[allow(clippy::unnecessary_cast)]
+                    out.write_all(&(channel as f32).to_le_bytes())?;
+                }
+            }
+        }
+        out.flush()
+    }
+}
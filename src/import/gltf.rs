@@ -0,0 +1,1330 @@
+//! glTF 2.0 scenes, as .gltf files with their buffers and images beside them
+//! or packed into a single .glb file.
+
+use super::{jpeg::*, json::Json, png::*};
+use crate::{
+    aabb::Aabb,
+    camera::CameraBuilder,
+    color::Color,
+    float::{consts, Float},
+    hit::HitList,
+    image::Image,
+    materials::*,
+    shapes::*,
+    texture::*,
+    tone_map::srgb_eotf,
+    vec3::*,
+};
+use std::{fs, io, path::Path, sync::Arc};
+
+fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("gltf: {message}"))
+}
+
+// extensions that are read, at least in part, everything else is ignored
+// with a warning
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "KHR_lights_punctual",
+    "KHR_materials_clearcoat",
+    "KHR_materials_emissive_strength",
+    "KHR_materials_ior",
+    "KHR_materials_sheen",
+    "KHR_materials_specular",
+    "KHR_materials_transmission",
+];
+
+// accessors are read into f64s, so a count from a broken file could ask for
+// any amount of memory
+const MAX_ACCESSOR_VALUES: usize = 1 << 26;
+
+/// A glTF scene with its triangles moved into place by their nodes, its
+/// materials, cameras and lights. `world` puts it together for rendering,
+/// with the lights only approximated.
+///
+/// Textures can be PNG or JPEG, baseline or sequential. Progressive and
+/// arithmetic coded JPEGs are skipped with a warning, and the material goes
+/// without that texture.
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<GltfMaterial>,
+    pub cameras: Vec<GltfCamera>,
+    pub lights: Vec<PunctualLight>,
+    /// Everything skipped or approximated while loading
+    pub warnings: Vec<String>,
+    // point and spot lights as small glowing spheres: center, radius, material
+    light_spheres: Vec<(Pos, Float, DiffuseLight)>,
+}
+
+/// One primitive of a glTF mesh, in world space
+pub struct GltfMesh {
+    pub mesh: TriangleMesh,
+    // index into the scene's materials
+    pub material: usize,
+}
+
+pub struct GltfMaterial {
+    pub surface: Box<dyn Material + Sync>,
+    // holes cut where alpha is low, for the MASK and BLEND alpha modes
+    pub alpha: Option<(Arc<dyn Texture<Float> + Send + Sync>, AlphaMode)>,
+}
+
+/// A perspective camera, in the terms `CameraBuilder` uses
+#[derive(Clone, Copy, Debug)]
+pub struct GltfCamera {
+    // vertical field of view in radians
+    pub vfov: Float,
+    pub aspect_ratio: Option<Float>,
+    pub lookfrom: Pos,
+    pub lookat: Pos,
+    pub vup: Vec3,
+}
+
+impl GltfCamera {
+    /// Sets up the builder to look through this camera, everything else is
+    /// left as it was
+    pub fn apply(&self, builder: CameraBuilder) -> CameraBuilder {
+        let builder = builder
+            .with_vfov(self.vfov)
+            .with_lookfrom(self.lookfrom)
+            .with_lookat(self.lookat)
+            .with_vup(self.vup);
+        match self.aspect_ratio {
+            Some(aspect_ratio) => builder.with_aspect_ratio(aspect_ratio),
+            None => builder,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    Point,
+    // cone angles from the axis in radians, full strength inside the inner one
+    Spot {
+        inner_cone: Float,
+        outer_cone: Float,
+    },
+    Directional,
+}
+
+/// A light from KHR_lights_punctual, as given in the file. See
+/// `GltfScene::world` for how little of it is rendered.
+#[derive(Clone, Copy, Debug)]
+pub struct PunctualLight {
+    pub kind: LightKind,
+    pub color: Color,
+    // candela for point and spot lights, lux for directional ones
+    pub intensity: Float,
+    pub position: Pos,
+    // where spot and directional lights shine
+    pub direction: Vec3,
+}
+
+impl GltfScene {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        Self::parse(&bytes, path.parent())
+    }
+
+    /// A .gltf or .glb file already in memory, with files it refers to
+    /// looked up in `dir`
+    pub fn parse(bytes: &[u8], dir: Option<&Path>) -> io::Result<Self> {
+        let (json, bin) = match bytes.starts_with(b"glTF") {
+            true => split_glb(bytes)?,
+            false => (bytes, None),
+        };
+        let text = std::str::from_utf8(json).map_err(|_| error("json isn't utf-8"))?;
+        let doc = Json::parse(text)?;
+        let version = doc.get("asset").get("version").as_str().unwrap_or("");
+        if !version.starts_with("2.") {
+            return Err(error(&format!("version {version:?} isn't glTF 2")));
+        }
+
+        let mut loader = Loader {
+            doc: &doc,
+            dir,
+            buffers: Vec::new(),
+            images: Vec::new(),
+            default_material: None,
+            warnings: Vec::new(),
+        };
+        for (i, buffer) in doc.get("buffers").items().iter().enumerate() {
+            let data = match buffer.get("uri").as_str() {
+                Some(uri) => loader.load_uri(uri)?,
+                // the first buffer of a .glb can be its binary chunk
+                None if i == 0 => bin.ok_or_else(|| error("buffer without data"))?.to_vec(),
+                None => return Err(error("buffer without data")),
+            };
+            loader.buffers.push(data);
+        }
+        loader.images = vec![None; doc.get("images").items().len()];
+        Ok(loader.load())
+    }
+
+    /// Everything in the scene, ready to render.
+    ///
+    /// Punctual lights are only approximated. The renderer doesn't sample
+    /// lights, so point and spot lights become spheres 1% of the scene's
+    /// size across, glowing as brightly as the light. Paths only find them
+    /// by chance, so they converge slowly and show up as fireflies at low
+    /// sample counts. Spot cones aren't kept, and directional lights are
+    /// left out entirely. `lights` still has all of them as given.
+    pub fn world(&self) -> HitList<'_> {
+        let mut world = HitList::default();
+        for mesh in &self.meshes {
+            let material = &self.materials[mesh.material];
+            let object = Mesh::new(&mesh.mesh, &*material.surface);
+            match &material.alpha {
+                Some((alpha, mode)) => {
+                    world.push(AlphaMask::new(object, alpha.clone()).with_mode(*mode))
+                }
+                None => world.push(object),
+            }
+        }
+        for (center, radius, light) in &self.light_spheres {
+            world.push(Sphere::new(*center, *radius, light));
+        }
+        world
+    }
+
+    /// Around the meshes, lights left out
+    pub fn bounding_box(&self) -> Aabb {
+        self.meshes
+            .iter()
+            .fold(Aabb::EMPTY, |acc, m| acc.union(&m.mesh.bounding_box()))
+    }
+}
+
+// the json and binary chunks of a .glb file
+fn split_glb(bytes: &[u8]) -> io::Result<(&[u8], Option<&[u8]>)> {
+    let word = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| error("truncated glb"))
+    };
+    if word(4)? != 2 {
+        return Err(error("only glb version 2 is supported"));
+    }
+    let length = (word(8)? as usize).min(bytes.len());
+    let (mut json, mut bin) = (None, None);
+    let mut at = 12;
+    while at + 8 <= length {
+        let chunk_length = word(at)? as usize;
+        let data = bytes
+            .get(at + 8..at + 8 + chunk_length)
+            .ok_or_else(|| error("truncated glb chunk"))?;
+        match &bytes[at + 4..at + 8] {
+            b"JSON" => json = json.or(Some(data)),
+            b"BIN\0" => bin = bin.or(Some(data)),
+            _ => {}
+        }
+        // chunks are already padded to four bytes
+        at += 8 + chunk_length;
+    }
+    Ok((json.ok_or_else(|| error("glb without json"))?, bin))
+}
+
+struct Loader<'a> {
+    doc: &'a Json,
+    dir: Option<&'a Path>,
+    buffers: Vec<Vec<u8>>,
+    // decoded on first use, None inside when decoding failed
+    images: Vec<Option<Option<DecodedImage>>>,
+    default_material: Option<usize>,
+    warnings: Vec<String>,
+}
+
+impl Loader<'_> {
+    fn warn(&mut self, message: String) {
+        self.warnings.push(message);
+    }
+
+    fn load(mut self) -> GltfScene {
+        let doc = self.doc;
+        let mut scene = GltfScene {
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            cameras: Vec::new(),
+            lights: Vec::new(),
+            warnings: Vec::new(),
+            light_spheres: Vec::new(),
+        };
+
+        for extension in doc.get("extensionsUsed").items() {
+            if let Some(name) = extension.as_str() {
+                if !SUPPORTED_EXTENSIONS.contains(&name) {
+                    self.warn(format!("extension {name} isn't supported"));
+                }
+            }
+        }
+
+        for material in doc.get("materials").items() {
+            let material = self.material(material);
+            scene.materials.push(material);
+        }
+
+        // the default scene, or the first, or without any scenes every node
+        // that isn't a child of another
+        let scenes = doc.get("scenes");
+        let roots: Vec<usize> = match doc.get("scene").as_usize().or(match scenes.items() {
+            [] => None,
+            _ => Some(0),
+        }) {
+            Some(index) => scenes
+                .at(index)
+                .get("nodes")
+                .items()
+                .iter()
+                .filter_map(Json::as_usize)
+                .collect(),
+            None => {
+                let nodes = doc.get("nodes").items();
+                let children: Vec<usize> = nodes
+                    .iter()
+                    .flat_map(|n| n.get("children").items())
+                    .filter_map(Json::as_usize)
+                    .collect();
+                (0..nodes.len()).filter(|i| !children.contains(i)).collect()
+            }
+        };
+        for root in roots {
+            self.node(root, &Mat4::IDENTITY, 0, &mut scene);
+        }
+
+        // lights get a size from the scene, so they're small but still hit
+        let bounds = scene.bounding_box();
+        let size = match scene.meshes.is_empty() {
+            true => 1.,
+            false => (bounds.max - bounds.min).length(),
+        };
+        let radius = (0.01 * size).max(1e-3);
+        for light in &scene.lights {
+            if let LightKind::Spot { .. } = light.kind {
+                self.warn("spot lights are drawn as point lights".to_string());
+            }
+            if light.kind == LightKind::Directional {
+                self.warn(
+                    "directional lights aren't supported, light the scene with a sky".to_string(),
+                );
+                continue;
+            }
+            // a sphere of radiance L has an intensity of L times its cross
+            // section in every direction
+            let radiance = light.color * (light.intensity / (consts::PI * radius * radius));
+            scene
+                .light_spheres
+                .push((light.position, radius, DiffuseLight::new(radiance)));
+        }
+
+        self.warnings.dedup();
+        scene.warnings = self.warnings;
+        scene
+    }
+
+    fn node(&mut self, index: usize, parent: &Mat4, depth: usize, scene: &mut GltfScene) {
+        let doc = self.doc;
+        let nodes = doc.get("nodes");
+        if depth > nodes.items().len() {
+            self.warn("the node hierarchy has a cycle".to_string());
+            return;
+        }
+        let node = nodes.at(index);
+        let transform = *parent * local_transform(node);
+
+        if let Some(mesh) = node.get("mesh").as_usize() {
+            if !node.get("skin").is_null() {
+                self.warn(format!(
+                    "node {index}: skins aren't supported, drawn unposed"
+                ));
+            }
+            self.mesh(mesh, &transform, scene);
+        }
+        if let Some(camera) = node.get("camera").as_usize() {
+            if let Some(camera) = self.camera(camera, &transform) {
+                scene.cameras.push(camera);
+            }
+        }
+        let light = node
+            .get("extensions")
+            .get("KHR_lights_punctual")
+            .get("light");
+        if let Some(light) = light.as_usize() {
+            if let Some(light) = self.light(light, &transform) {
+                scene.lights.push(light);
+            }
+        }
+
+        for child in node
+            .get("children")
+            .items()
+            .iter()
+            .filter_map(Json::as_usize)
+        {
+            self.node(child, &transform, depth + 1, scene);
+        }
+    }
+
+    fn mesh(&mut self, index: usize, transform: &Mat4, scene: &mut GltfScene) {
+        let doc = self.doc;
+        let primitives = doc.get("meshes").at(index).get("primitives").items();
+        for (i, primitive) in primitives.iter().enumerate() {
+            let mesh = match self.primitive(primitive, transform) {
+                Ok(mesh) => mesh,
+                Err(e) => {
+                    self.warn(format!("mesh {index} primitive {i} skipped: {e}"));
+                    continue;
+                }
+            };
+            let material = match primitive.get("material").as_usize() {
+                Some(material) if material < doc.get("materials").items().len() => material,
+                _ => self.default_material(scene),
+            };
+            scene.meshes.push(GltfMesh { mesh, material });
+        }
+    }
+
+    // white and fully rough metal, as the spec says
+    fn default_material(&mut self, scene: &mut GltfScene) -> usize {
+        *self.default_material.get_or_insert_with(|| {
+            let surface = Principled::new(Color(1., 1., 1.))
+                .with_metallic(1.)
+                .with_roughness(1.);
+            scene.materials.push(GltfMaterial {
+                surface: Box::new(surface),
+                alpha: None,
+            });
+            scene.materials.len() - 1
+        })
+    }
+
+    fn primitive(&self, primitive: &Json, transform: &Mat4) -> io::Result<TriangleMesh> {
+        let attributes = primitive.get("attributes");
+        let position = attributes
+            .get("POSITION")
+            .as_usize()
+            .ok_or_else(|| error("no positions"))?;
+        let positions: Vec<Pos> = self
+            .vectors(position, 3)?
+            .iter()
+            .map(|p| transform.transform_point(&Pos(p[0], p[1], p[2])))
+            .collect();
+
+        let indices: Vec<u32> = match primitive.get("indices").as_usize() {
+            Some(indices) => {
+                let (values, _) = self.accessor(indices)?;
+                values.iter().map(|&i| i as u32).collect()
+            }
+            None => (0..positions.len() as u32).collect(),
+        };
+        if indices.iter().any(|&i| i as usize >= positions.len()) {
+            return Err(error("vertex index out of range"));
+        }
+        let mut triangles: Vec<[u32; 3]> = match primitive.get("mode").as_usize().unwrap_or(4) {
+            4 => indices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect(),
+            // strips flip every other triangle to keep them all facing the same way
+            5 => indices
+                .windows(3)
+                .enumerate()
+                .map(|(i, t)| match i % 2 {
+                    0 => [t[0], t[1], t[2]],
+                    _ => [t[1], t[0], t[2]],
+                })
+                .collect(),
+            6 => (1..indices.len().saturating_sub(1))
+                .map(|i| [indices[0], indices[i], indices[i + 1]])
+                .collect(),
+            _ => return Err(error("only triangles are supported")),
+        };
+        if triangles.is_empty() {
+            return Err(error("no triangles"));
+        }
+
+        // a mirroring transform turns the triangles inside out
+        let m = &transform.0;
+        let linear = Mat3([
+            [m[0][0], m[0][1], m[0][2]],
+            [m[1][0], m[1][1], m[1][2]],
+            [m[2][0], m[2][1], m[2][2]],
+        ]);
+        if linear.determinant() < 0. {
+            triangles.iter_mut().for_each(|t| t.swap(1, 2));
+        }
+
+        let mut mesh = TriangleMesh::new(positions, triangles);
+        if let Some(normal) = attributes.get("NORMAL").as_usize() {
+            let to_world = transform
+                .inverse()
+                .ok_or_else(|| error("node transform can't be inverted"))?
+                .transpose();
+            let normals = self
+                .vectors(normal, 3)?
+                .iter()
+                .map(|n| to_world.transform_vector(&Vec3(n[0], n[1], n[2])))
+                .collect();
+            mesh = mesh.with_normals(normals);
+        }
+        if let Some(uv) = attributes.get("TEXCOORD_0").as_usize() {
+            // glTF puts v = 0 at the top of images
+            let uvs = self
+                .vectors(uv, 2)?
+                .iter()
+                .map(|uv| (uv[0], 1. - uv[1]))
+                .collect();
+            mesh = mesh.with_uvs(uvs);
+        }
+        Ok(mesh)
+    }
+
+    // an accessor of vectors with the given number of components, one per
+    // vertex
+    fn vectors(&self, index: usize, components: usize) -> io::Result<Vec<Vec<Float>>> {
+        let (values, found) = self.accessor(index)?;
+        if found != components {
+            return Err(error(&format!(
+                "accessor {index} has {found} components, not {components}"
+            )));
+        }
+        Ok(values
+            .chunks_exact(components)
+            .map(|v| v.iter().map(|&x| x as Float).collect())
+            .collect())
+    }
+
+    // the elements of an accessor as a flat list of components, and how many
+    // there are per element. Normalized integers are scaled to [0, 1] or
+    // [-1, 1].
+    fn accessor(&self, index: usize) -> io::Result<(Vec<f64>, usize)> {
+        let accessor = self.doc.get("accessors").at(index);
+        let count = accessor
+            .get("count")
+            .as_usize()
+            .ok_or_else(|| error(&format!("accessor {index} has no count")))?;
+        let components = match accessor.get("type").as_str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4" | "MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(error(&format!("accessor {index} has a bad type"))),
+        };
+        let component_type = accessor.get("componentType").as_usize().unwrap_or(0);
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(error(&format!("accessor {index} has a bad component type"))),
+        };
+        let normalized = accessor.get("normalized").as_bool().unwrap_or(false);
+        if !accessor.get("sparse").is_null() {
+            return Err(error("sparse accessors aren't supported"));
+        }
+        let value_count = count
+            .checked_mul(components)
+            .filter(|&n| n <= MAX_ACCESSOR_VALUES)
+            .ok_or_else(|| error(&format!("accessor {index} is too large")))?;
+
+        // no buffer view means all zeros
+        let Some(view) = accessor.get("bufferView").as_usize() else {
+            return Ok((vec![0.; value_count], components));
+        };
+        let (data, stride) = self.buffer_view(view)?;
+        let element_size = size * components;
+        let stride = stride.unwrap_or(element_size);
+        // elements can't overlap, which also keeps count within the view
+        if stride < element_size {
+            return Err(error(&format!(
+                "accessor {index} has a stride shorter than its elements"
+            )));
+        }
+        let offset = accessor.get("byteOffset").as_usize().unwrap_or(0);
+        let end = count.checked_sub(1).map_or(Some(0), |last| {
+            last.checked_mul(stride)?
+                .checked_add(offset)?
+                .checked_add(element_size)
+        });
+        if end.is_none_or(|end| end > data.len()) {
+            return Err(error(&format!(
+                "accessor {index} runs past its buffer view"
+            )));
+        }
+
+        let mut values = Vec::with_capacity(value_count);
+        for element in 0..count {
+            for component in 0..components {
+                let at = offset + element * stride + component * size;
+                let b = &data[at..at + size];
+                let value = match component_type {
+                    5120 => {
+                        let v = f64::from(b[0] as i8);
+                        if normalized {
+                            (v / 127.).max(-1.)
+                        } else {
+                            v
+                        }
+                    }
+                    5121 => {
+                        let v = f64::from(b[0]);
+                        if normalized {
+                            v / 255.
+                        } else {
+                            v
+                        }
+                    }
+                    5122 => {
+                        let v = f64::from(i16::from_le_bytes([b[0], b[1]]));
+                        if normalized {
+                            (v / 32767.).max(-1.)
+                        } else {
+                            v
+                        }
+                    }
+                    5123 => {
+                        let v = f64::from(u16::from_le_bytes([b[0], b[1]]));
+                        if normalized {
+                            v / 65535.
+                        } else {
+                            v
+                        }
+                    }
+                    5125 => f64::from(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                    _ => f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                };
+                values.push(value);
+            }
+        }
+        Ok((values, components))
+    }
+
+    // the bytes of a buffer view and its stride, if it has one
+    fn buffer_view(&self, index: usize) -> io::Result<(&[u8], Option<usize>)> {
+        let view = self.doc.get("bufferViews").at(index);
+        let buffer = view
+            .get("buffer")
+            .as_usize()
+            .and_then(|b| self.buffers.get(b))
+            .ok_or_else(|| error(&format!("buffer view {index} has no buffer")))?;
+        let offset = view.get("byteOffset").as_usize().unwrap_or(0);
+        let length = view.get("byteLength").as_usize().unwrap_or(0);
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| buffer.get(offset..end))
+            .ok_or_else(|| error(&format!("buffer view {index} runs past its buffer")))?;
+        Ok((data, view.get("byteStride").as_usize()))
+    }
+
+    fn load_uri(&self, uri: &str) -> io::Result<Vec<u8>> {
+        if let Some(data) = uri.strip_prefix("data:") {
+            let (header, payload) = data.split_once(',').ok_or_else(|| error("bad data uri"))?;
+            return match header.ends_with(";base64") {
+                true => base64(payload),
+                false => Ok(percent_decode(payload)),
+            };
+        }
+        let path = String::from_utf8_lossy(&percent_decode(uri)).into_owned();
+        let path = match self.dir {
+            Some(dir) => dir.join(path),
+            None => path.into(),
+        };
+        fs::read(&path).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+
+    fn image(&mut self, index: usize) -> Option<&DecodedImage> {
+        if self.images.get(index)?.is_none() {
+            let decoded = match self.decode_image(index) {
+                Ok(image) => Some(image),
+                Err(e) => {
+                    self.warn(format!("image {index} skipped: {e}"));
+                    None
+                }
+            };
+            self.images[index] = Some(decoded);
+        }
+        self.images[index].as_ref()?.as_ref()
+    }
+
+    fn decode_image(&self, index: usize) -> io::Result<DecodedImage> {
+        let image = self.doc.get("images").at(index);
+        let bytes = match image.get("uri").as_str() {
+            Some(uri) => self.load_uri(uri)?,
+            None => {
+                let view = image
+                    .get("bufferView")
+                    .as_usize()
+                    .ok_or_else(|| error("image without data"))?;
+                self.buffer_view(view)?.0.to_vec()
+            }
+        };
+        match bytes {
+            _ if is_png(&bytes) => read_png(&bytes),
+            _ if is_jpeg(&bytes) => read_jpeg(&bytes),
+            _ => Err(error("unknown image format")),
+        }
+    }
+
+    // the image behind a texture reference like baseColorTexture
+    fn texture(&mut self, info: &Json) -> Option<&DecodedImage> {
+        let texture = info.get("index").as_usize()?;
+        if info.get("texCoord").as_usize().unwrap_or(0) != 0 {
+            self.warn(format!(
+                "texture {texture} uses a second uv set, the first is used instead"
+            ));
+        }
+        let image = self
+            .doc
+            .get("textures")
+            .at(texture)
+            .get("source")
+            .as_usize()?;
+        self.image(image)
+    }
+
+    fn material(&mut self, material: &Json) -> GltfMaterial {
+        let pbr = material.get("pbrMetallicRoughness");
+        let extensions = material.get("extensions");
+
+        // the factors are baked into the textures, which are decoded from
+        // sRGB where they hold colors
+        let factor = floats(pbr.get("baseColorFactor"))
+            .filter(|f| f.len() == 4)
+            .unwrap_or(vec![1.; 4]);
+        let color_factor = Color(factor[0], factor[1], factor[2]);
+        let alpha_factor = factor[3];
+        let base = self.texture(pbr.get("baseColorTexture")).map(|image| {
+            let color = map_image(&image.color, |c| srgb_decode(c) * color_factor);
+            let alpha = image
+                .alpha
+                .as_ref()
+                .map(|alpha| map_image(alpha, |a| a * alpha_factor));
+            (color, alpha)
+        });
+        let (surface, base_alpha) = match base {
+            Some((color, alpha)) => (Principled::new(ImageTexture::new(color)), alpha),
+            None => (Principled::new(color_factor), None),
+        };
+
+        let alpha_mode = match material.get("alphaMode").as_str() {
+            Some("MASK") => {
+                let cutoff = material.get("alphaCutoff").as_f64().unwrap_or(0.5);
+                Some(AlphaMode::Cutout(cutoff as Float))
+            }
+            Some("BLEND") => Some(AlphaMode::Stochastic),
+            _ => None,
+        };
+        let alpha = alpha_mode.map(|mode| {
+            let texture: Arc<dyn Texture<Float> + Send + Sync> = match base_alpha {
+                Some(alpha) => Arc::new(ColorChannel::new(ImageTexture::new(alpha), 0)),
+                None => Arc::new(alpha_factor),
+            };
+            (texture, mode)
+        });
+
+        // metalness in blue and roughness in green
+        let metallic = pbr.get("metallicFactor").as_f64().unwrap_or(1.) as Float;
+        let roughness = pbr.get("roughnessFactor").as_f64().unwrap_or(1.) as Float;
+        let metallic_roughness = self
+            .texture(pbr.get("metallicRoughnessTexture"))
+            .map(|image| {
+                map_image(&image.color, |c| {
+                    Color(0., c.g() * roughness, c.b() * metallic)
+                })
+            });
+        let surface = match metallic_roughness {
+            Some(image) => {
+                let texture = Arc::new(ImageTexture::new(image));
+                surface
+                    .with_metallic(ColorChannel::new(texture.clone(), 2))
+                    .with_roughness(ColorChannel::new(texture, 1))
+            }
+            None => surface.with_metallic(metallic).with_roughness(roughness),
+        };
+
+        let strength = extensions
+            .get("KHR_materials_emissive_strength")
+            .get("emissiveStrength")
+            .as_f64()
+            .unwrap_or(1.) as Float;
+        let emissive = color3(material.get("emissiveFactor")).unwrap_or_default() * strength;
+        let emission = self
+            .texture(material.get("emissiveTexture"))
+            .map(|image| map_image(&image.color, |c| srgb_decode(c) * emissive));
+        let surface = match emission {
+            Some(image) => surface.with_emission(ImageTexture::new(image)),
+            None => surface.with_emission(emissive),
+        };
+
+        // Principled's specular of 0.5 reflects 4% head on, which is an index
+        // of 1.5
+        let ior = extensions
+            .get("KHR_materials_ior")
+            .get("ior")
+            .as_f64()
+            .unwrap_or(1.5) as Float;
+        let ior = ior.max(1.);
+        let specular = extensions
+            .get("KHR_materials_specular")
+            .get("specularFactor")
+            .as_f64()
+            .unwrap_or(1.) as Float;
+        let reflectance = ((ior - 1.) / (ior + 1.)).powi(2) * specular;
+        let surface = surface.with_ior(ior).with_specular(reflectance / 0.08);
+
+        let transmission = extensions.get("KHR_materials_transmission");
+        let clearcoat = extensions.get("KHR_materials_clearcoat");
+        let sheen = color3(
+            extensions
+                .get("KHR_materials_sheen")
+                .get("sheenColorFactor"),
+        );
+        let number = |value: &Json, default: f64| value.as_f64().unwrap_or(default) as Float;
+        let surface = surface
+            .with_transmission(number(transmission.get("transmissionFactor"), 0.))
+            .with_clearcoat(number(clearcoat.get("clearcoatFactor"), 0.))
+            .with_clearcoat_roughness(number(clearcoat.get("clearcoatRoughnessFactor"), 0.))
+            .with_sheen(sheen.map_or(0., |s| s.luminance()))
+            .with_sheen_tint(0.);
+
+        // occlusion is left out, path tracing finds it by itself
+        let normal_info = material.get("normalTexture");
+        let normal_map = self
+            .texture(normal_info)
+            .map(|image| ImageTexture::new(image.color.clone()));
+        let surface: Box<dyn Material + Sync> = match normal_map {
+            Some(map) => {
+                let scale = number(normal_info.get("scale"), 1.);
+                Box::new(NormalMap::new(surface, map).with_strength(scale))
+            }
+            None => Box::new(surface),
+        };
+
+        GltfMaterial { surface, alpha }
+    }
+
+    fn camera(&mut self, index: usize, transform: &Mat4) -> Option<GltfCamera> {
+        let camera = self.doc.get("cameras").at(index);
+        if camera.get("type").as_str() != Some("perspective") {
+            self.warn(format!(
+                "camera {index} skipped, only perspective cameras are supported"
+            ));
+            return None;
+        }
+        let perspective = camera.get("perspective");
+
+        // glTF cameras look down -z with y up
+        let lookfrom = transform.transform_point(&Pos::ORIGIN);
+        let forward = transform.transform_vector(&Vec3(0., 0., -1.));
+        if forward.near_zero() {
+            return None;
+        }
+        Some(GltfCamera {
+            vfov: perspective.get("yfov").as_f64()? as Float,
+            aspect_ratio: perspective.get("aspectRatio").as_f64().map(|a| a as Float),
+            lookfrom,
+            lookat: lookfrom + forward.unit_vec(),
+            vup: transform.transform_vector(&Vec3(0., 1., 0.)),
+        })
+    }
+
+    fn light(&mut self, index: usize, transform: &Mat4) -> Option<PunctualLight> {
+        let lights = self
+            .doc
+            .get("extensions")
+            .get("KHR_lights_punctual")
+            .get("lights");
+        let light = lights.at(index);
+        let kind = match light.get("type").as_str() {
+            Some("point") => LightKind::Point,
+            Some("spot") => {
+                let spot = light.get("spot");
+                LightKind::Spot {
+                    inner_cone: spot.get("innerConeAngle").as_f64().unwrap_or(0.) as Float,
+                    outer_cone: spot
+                        .get("outerConeAngle")
+                        .as_f64()
+                        .map_or(consts::FRAC_PI_4, |a| a as Float),
+                }
+            }
+            Some("directional") => LightKind::Directional,
+            _ => {
+                self.warn(format!("light {index} skipped, it has no known type"));
+                return None;
+            }
+        };
+        // lights shine down -z
+        let direction = transform.transform_vector(&Vec3(0., 0., -1.));
+        Some(PunctualLight {
+            kind,
+            color: color3(light.get("color")).unwrap_or(Color(1., 1., 1.)),
+            intensity: light.get("intensity").as_f64().unwrap_or(1.) as Float,
+            position: transform.transform_point(&Pos::ORIGIN),
+            direction: match direction.near_zero() {
+                true => Vec3(0., 0., -1.),
+                false => direction.unit_vec(),
+            },
+        })
+    }
+}
+
+// a node's own transform, as a matrix or as translation, rotation and scale
+fn local_transform(node: &Json) -> Mat4 {
+    if let Some(m) = floats(node.get("matrix")).filter(|m| m.len() == 16) {
+        // stored column by column
+        return Mat4(std::array::from_fn(|row| {
+            std::array::from_fn(|column| m[column * 4 + row])
+        }));
+    }
+    let vector = |key: &str, default: Vec3| match floats(node.get(key)).as_deref() {
+        Some(&[x, y, z]) => Vec3(x, y, z),
+        _ => default,
+    };
+    let translation = vector("translation", Vec3(0., 0., 0.));
+    let scale = vector("scale", Vec3(1., 1., 1.));
+    let rotation = match floats(node.get("rotation")).as_deref() {
+        Some(&[x, y, z, w]) => Quat { w, x, y, z }.normalize(),
+        _ => Quat::IDENTITY,
+    };
+    Mat4::translation(translation) * Mat4::from_quat(&rotation) * Mat4::scale(scale)
+}
+
+// an array of numbers
+fn floats(value: &Json) -> Option<Vec<Float>> {
+    value
+        .items()
+        .iter()
+        .map(|v| v.as_f64().map(|x| x as Float))
+        .collect::<Option<Vec<_>>>()
+        .filter(|v| !v.is_empty())
+}
+
+fn color3(value: &Json) -> Option<Color> {
+    match floats(value).as_deref() {
+        Some(&[r, g, b]) => Some(Color(r, g, b)),
+        _ => None,
+    }
+}
+
+fn srgb_decode(c: Color) -> Color {
+    Color(srgb_eotf(c.r()), srgb_eotf(c.g()), srgb_eotf(c.b()))
+}
+
+fn map_image(image: &Image, f: impl Fn(Color) -> Color) -> Image {
+    let mut out = image.clone();
+    out.pixels_mut().iter_mut().for_each(|p| *p = f(*p));
+    out
+}
+
+fn base64(text: &str) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b' ' | b'\t' | b'\n' | b'\r' => continue,
+            _ => return Err(error("bad base64 data")),
+        };
+        // only the bits not yet written out matter
+        bits = (bits << 6 | u32::from(value)) & 0xffff;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Ok(out)
+}
+
+// uris escape spaces and such as %xx
+fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hit::Hit, ray::Ray, shapes::look_down};
+
+    fn base64_encode(bytes: &[u8]) -> String {
+        const DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let word = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, &b)| acc | u32::from(b) << (16 - 8 * i));
+            for i in 0..4 {
+                out.push(match i <= chunk.len() {
+                    true => DIGITS[(word >> (18 - 6 * i) & 63) as usize] as char,
+                    false => '=',
+                });
+            }
+        }
+        out
+    }
+
+    fn le_bytes(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    // a buffer given inline, as a data uri
+    fn data_uri(bytes: &[u8]) -> String {
+        format!(
+            "\"buffers\": [{{\"byteLength\": {}, \"uri\": \"data:application/octet-stream;base64,{}\"}}]",
+            bytes.len(),
+            base64_encode(bytes)
+        )
+    }
+
+    fn parse(json: &str) -> GltfScene {
+        GltfScene::parse(json.as_bytes(), None).unwrap()
+    }
+
+    fn near(a: Float, b: Float) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    fn near_pos(a: Pos, b: Pos) -> bool {
+        near(a.0, b.0) && near(a.1, b.1) && near(a.2, b.2)
+    }
+
+    // the corners of a triangle facing +z, and of the unit square as a strip
+    const TRIANGLE: [f32; 9] = [0., 0., 0., 1., 0., 0., 0., 1., 0.];
+    const SQUARE: [f32; 12] = [0., 0., 0., 1., 0., 0., 0., 1., 0., 1., 1., 0.];
+
+    const TRIANGLE_NODES: &str = r#""asset": {"version": "2.0"},
+        "scenes": [{"nodes": [0, 2]}],
+        "nodes": [
+            {"translation": [0, 0, -1], "rotation": [0, 0, 0.70710678, 0.70710678], "children": [1]},
+            {"translation": [1, 0, 0], "mesh": 0},
+            {"translation": [5, 0, -1], "scale": [-1, 1, 1], "mesh": 0}
+        ],
+        "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+        "accessors": [{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}],
+        "bufferViews": [{"buffer": 0, "byteLength": 36}]"#;
+
+    // both meshes of TRIANGLE_NODES, wherever the buffer came from
+    fn check_triangles(scene: &GltfScene) {
+        assert_eq!(scene.meshes.len(), 2);
+        // the child's translation is turned by the parent: the corners end
+        // up at (0, 1), (0, 2) and (-1, 1)
+        let turned = &scene.meshes[0].mesh;
+        let seen = look_down(turned, -0.3, 1.3).unwrap();
+        assert!(seen.front_face);
+        assert!(near_pos(seen.pos, Pos(-0.3, 1.3, -1.)));
+        assert!(look_down(turned, 1.3, 0.3).is_none());
+
+        // mirrored, at (5, 0), (4, 0) and (5, 1), and still facing up
+        let mirrored = &scene.meshes[1].mesh;
+        let seen = look_down(mirrored, 4.8, 0.1).unwrap();
+        assert!(seen.front_face);
+        assert!(near(seen.normal.2, 1.));
+        assert!(look_down(mirrored, 5.2, 0.1).is_none());
+    }
+
+    #[test]
+    fn nodes_place_meshes() {
+        let json = format!("{{{TRIANGLE_NODES}, {}}}", data_uri(&le_bytes(&TRIANGLE)));
+        let scene = parse(&json);
+        assert!(scene.warnings.is_empty(), "{:?}", scene.warnings);
+        check_triangles(&scene);
+        // with nothing given, the default material
+        assert_eq!(scene.materials.len(), 1);
+    }
+
+    #[test]
+    fn strips_and_fans() {
+        let mut buffer = le_bytes(&SQUARE);
+        buffer.extend([0u16, 1, 3, 2].iter().flat_map(|i| i.to_le_bytes()));
+        let json = format!(
+            r#"{{"asset": {{"version": "2.0"}},
+            "nodes": [
+                {{"translation": [0, 0, -1], "mesh": 0}},
+                {{"translation": [2, 0, -1], "mesh": 1}}
+            ],
+            "meshes": [
+                {{"primitives": [{{"attributes": {{"POSITION": 0}}, "mode": 5}}]}},
+                {{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "mode": 6}}]}}
+            ],
+            "accessors": [
+                {{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}},
+                {{"bufferView": 1, "componentType": 5123, "count": 4, "type": "SCALAR"}}
+            ],
+            "bufferViews": [
+                {{"buffer": 0, "byteLength": 48}},
+                {{"buffer": 0, "byteOffset": 48, "byteLength": 8}}
+            ],
+            {}}}"#,
+            data_uri(&buffer)
+        );
+        let scene = parse(&json);
+        assert_eq!(scene.meshes.len(), 2);
+        // both triangles of each face up, every other one of the strip is
+        // turned around to keep it that way
+        for (mesh, x) in [(&scene.meshes[0].mesh, 0.), (&scene.meshes[1].mesh, 2.)] {
+            assert_eq!(mesh.triangle_count(), 2);
+            for (dx, y) in [(0.7, 0.2), (0.2, 0.7), (0.9, 0.8)] {
+                let seen = look_down(mesh, x + dx, y).unwrap();
+                assert!(seen.front_face, "{x} {dx} {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn accessors() {
+        let mut buffer = vec![127, 0x81, 0x80, 0, 255, 0, 51, 0];
+        buffer.extend(32767i16.to_le_bytes());
+        buffer.extend((-32768i16).to_le_bytes());
+        buffer.extend(65535u16.to_le_bytes());
+        buffer.extend(0u16.to_le_bytes());
+        buffer.extend([1, 2, 9, 9, 3, 4, 0, 0]);
+        buffer.extend(le_bytes(&[1., 2., 3., 4., 5., 6.]));
+        let doc = Json::parse(
+            r#"{
+            "bufferViews": [
+                {"buffer": 0, "byteOffset": 0, "byteLength": 3},
+                {"buffer": 0, "byteOffset": 4, "byteLength": 3},
+                {"buffer": 0, "byteOffset": 8, "byteLength": 4},
+                {"buffer": 0, "byteOffset": 12, "byteLength": 4},
+                {"buffer": 0, "byteOffset": 16, "byteLength": 6, "byteStride": 4},
+                {"buffer": 0, "byteOffset": 24, "byteLength": 24, "byteStride": 8},
+                {"buffer": 0, "byteOffset": 24, "byteLength": 24},
+                {"buffer": 0, "byteOffset": 40, "byteLength": 10}
+            ],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5120, "normalized": true, "count": 3, "type": "SCALAR"},
+                {"bufferView": 1, "componentType": 5121, "normalized": true, "count": 3, "type": "SCALAR"},
+                {"bufferView": 2, "componentType": 5122, "normalized": true, "count": 2, "type": "SCALAR"},
+                {"bufferView": 3, "componentType": 5123, "normalized": true, "count": 2, "type": "SCALAR"},
+                {"bufferView": 1, "componentType": 5121, "count": 3, "type": "SCALAR"},
+                {"bufferView": 4, "componentType": 5121, "count": 2, "type": "VEC2"},
+                {"bufferView": 6, "componentType": 5126, "count": 2, "type": "VEC3"},
+                {"componentType": 5126, "count": 2, "type": "SCALAR"},
+                {"bufferView": 5, "componentType": 5126, "count": 2, "type": "VEC3"},
+                {"bufferView": 6, "componentType": 5126, "count": 3, "type": "VEC3"},
+                {"bufferView": 6, "componentType": 5126, "count": 2, "type": "VEC3", "byteOffset": 4},
+                {"bufferView": 6, "componentType": 5126, "count": 1073741824, "type": "VEC3"},
+                {"bufferView": 7, "componentType": 5121, "count": 1, "type": "SCALAR"},
+                {"bufferView": 6, "componentType": 5126, "count": 2, "type": "VEC7"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        let loader = Loader {
+            doc: &doc,
+            dir: None,
+            buffers: vec![buffer],
+            images: Vec::new(),
+            default_material: None,
+            warnings: Vec::new(),
+        };
+        let values = |index: usize| loader.accessor(index).unwrap().0;
+
+        // normalized integers, with the most negative one clamped to -1
+        assert_eq!(values(0), [1., -1., -1.]);
+        assert_eq!(values(1), [1., 0., 0.2]);
+        assert_eq!(values(2), [1., -1.]);
+        assert_eq!(values(3), [1., 0.]);
+        assert_eq!(values(4), [255., 0., 51.]);
+        // interleaved, the stride skips the other attribute's bytes
+        assert_eq!(loader.accessor(5).unwrap(), (vec![1., 2., 3., 4.], 2));
+        assert_eq!(values(6), [1., 2., 3., 4., 5., 6.]);
+        // no buffer view is all zeros
+        assert_eq!(values(7), [0., 0.]);
+
+        // a stride shorter than the elements, elements past the end of the
+        // view, too many of them to even hold, a view past the buffer, and
+        // a type that doesn't exist
+        for index in 8..14 {
+            assert!(loader.accessor(index).is_err(), "{index}");
+        }
+        assert!(loader.accessor(99).is_err());
+    }
+
+    #[test]
+    fn cameras() {
+        let json = r#"{"asset": {"version": "2.0"},
+            "nodes": [
+                {"translation": [1, 2, 3], "children": [1]},
+                {"rotation": [0, 0.70710678, 0, 0.70710678], "camera": 0}
+            ],
+            "cameras": [
+                {"type": "perspective", "perspective": {"yfov": 0.5, "aspectRatio": 1.5, "znear": 0.1}},
+                {"type": "orthographic", "orthographic": {"xmag": 1, "ymag": 1, "znear": 0.1, "zfar": 10}}
+            ]
+        }"#;
+        let scene = parse(json);
+        assert_eq!(scene.cameras.len(), 1);
+        // turned a quarter around y, it looks down -x instead of -z
+        let camera = scene.cameras[0];
+        assert_eq!(camera.vfov, 0.5);
+        assert_eq!(camera.aspect_ratio, Some(1.5));
+        assert!(near_pos(camera.lookfrom, Pos(1., 2., 3.)));
+        assert!(near_pos(camera.lookat, Pos(0., 2., 3.)));
+        assert!(near_pos(Pos::ORIGIN + camera.vup, Pos(0., 1., 0.)));
+
+        let applied = camera.apply(CameraBuilder::default().with_image_width(64));
+        let expected = CameraBuilder::default()
+            .with_image_width(64)
+            .with_vfov(camera.vfov)
+            .with_lookfrom(camera.lookfrom)
+            .with_lookat(camera.lookat)
+            .with_vup(camera.vup)
+            .with_aspect_ratio(1.5);
+        assert_eq!(format!("{applied:?}"), format!("{expected:?}"));
+    }
+
+    #[test]
+    fn punctual_lights() {
+        let json = r#"{"asset": {"version": "2.0"},
+            "extensionsUsed": ["KHR_lights_punctual"],
+            "extensions": {"KHR_lights_punctual": {"lights": [
+                {"type": "point", "color": [1, 0.5, 0.25], "intensity": 10},
+                {"type": "spot", "spot": {"outerConeAngle": 0.5}},
+                {"type": "directional", "intensity": 3}
+            ]}},
+            "nodes": [
+                {"translation": [0, 0, -4], "extensions": {"KHR_lights_punctual": {"light": 0}}},
+                {"translation": [3, 0, 0], "rotation": [0.70710678, 0, 0, 0.70710678],
+                    "extensions": {"KHR_lights_punctual": {"light": 1}}},
+                {"extensions": {"KHR_lights_punctual": {"light": 2}}}
+            ]
+        }"#;
+        let scene = parse(json);
+        assert_eq!(scene.lights.len(), 3);
+        let point = scene.lights[0];
+        assert_eq!(point.kind, LightKind::Point);
+        assert!(near_pos(point.position, Pos(0., 0., -4.)));
+        let spot = scene.lights[1];
+        assert_eq!(
+            spot.kind,
+            LightKind::Spot {
+                inner_cone: 0.,
+                outer_cone: 0.5
+            }
+        );
+        assert_eq!(spot.color, Color(1., 1., 1.));
+        // turned a quarter around x, from shining down -z to down +y
+        assert!(near_pos(Pos::ORIGIN + spot.direction, Pos(0., 1., 0.)));
+        assert_eq!(scene.lights[2].kind, LightKind::Directional);
+        assert_eq!(scene.warnings.len(), 2, "{:?}", scene.warnings);
+
+        // without meshes the spheres are 0.01 across, and as intense as the
+        // light: radiance times their cross section
+        let world = scene.world();
+        let ray = Ray {
+            origin: Pos::ORIGIN,
+            dir: Vec3(0., 0., -1.),
+            time: 0.,
+        };
+        let hit_info = world.hit(&ray, 0.0..Float::INFINITY).unwrap();
+        assert!(near(hit_info.t, 4. - 0.01));
+        let intensity = hit_info.mat.emitted(&hit_info) * (consts::PI * 0.01 * 0.01);
+        let expected = point.color * point.intensity;
+        assert!(near(intensity.r(), expected.r()));
+        assert!(near(intensity.g(), expected.g()));
+        assert!(near(intensity.b(), expected.b()));
+    }
+
+    // a .glb file of json and binary chunks, each padded to four bytes
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = bin.to_vec();
+        bin.resize(bin.len().next_multiple_of(4), 0);
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let mut file = b"glTF".to_vec();
+        file.extend(2u32.to_le_bytes());
+        file.extend((length as u32).to_le_bytes());
+        for (kind, data) in [(b"JSON", &json), (b"BIN\0", &bin)] {
+            file.extend((data.len() as u32).to_le_bytes());
+            file.extend(kind);
+            file.extend(data);
+        }
+        file
+    }
+
+    #[test]
+    fn glb_files() {
+        let json = format!("{{{TRIANGLE_NODES}, \"buffers\": [{{\"byteLength\": 36}}]}}",);
+        let bytes = glb(&json, &le_bytes(&TRIANGLE));
+        let (found_json, found_bin) = split_glb(&bytes).unwrap();
+        assert_eq!(std::str::from_utf8(found_json).unwrap().trim_end(), json);
+        assert_eq!(found_bin.unwrap(), le_bytes(&TRIANGLE));
+        check_triangles(&GltfScene::parse(&bytes, None).unwrap());
+
+        for end in [4, 11, 30, bytes.len() - 1] {
+            assert!(GltfScene::parse(&bytes[..end], None).is_err(), "{end}");
+        }
+        let mut version_1 = bytes.clone();
+        version_1[4] = 1;
+        assert!(split_glb(&version_1).is_err());
+    }
+
+    #[test]
+    fn jpeg_textures() {
+        fastrand::seed(7);
+        let image = |bytes: &[u8]| {
+            format!(
+                r#"{{"asset": {{"version": "2.0"}},
+                "materials": [{{"pbrMetallicRoughness": {{"baseColorTexture": {{"index": 0}}}}}}],
+                "textures": [{{"source": 0}}],
+                "images": [{{"uri": "data:image/jpeg;base64,{}"}}]
+            }}"#,
+                base64_encode(bytes)
+            )
+        };
+        let jpeg = crate::import::jpeg::tests::gray_file(5, 3);
+        let scene = parse(&image(&jpeg));
+        assert!(scene.warnings.is_empty(), "{:?}", scene.warnings);
+
+        // progressive ones are skipped, and say why
+        let mut progressive = jpeg.clone();
+        let frame = jpeg.windows(2).position(|w| w == [0xff, 0xc0]).unwrap();
+        progressive[frame + 1] = 0xc2;
+        let scene = parse(&image(&progressive));
+        assert_eq!(scene.warnings.len(), 1);
+        assert!(
+            scene.warnings[0].contains("progressive"),
+            "{:?}",
+            scene.warnings
+        );
+    }
+
+    #[test]
+    fn data_uris() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for n in [0, 1, 2, 3, 100, 256] {
+            assert_eq!(base64(&base64_encode(&bytes[..n])).unwrap(), &bytes[..n]);
+        }
+        assert!(base64("abc$").is_err());
+        assert_eq!(percent_decode("a%20b%2"), b"a b%2");
+    }
+}
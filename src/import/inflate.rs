@@ -0,0 +1,402 @@
+//! Decompression of zlib streams, as found in PNG images. Slow and simple,
+//! after Mark Adler's puff.c.
+
+use std::io;
+
+fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("inflate: {message}"))
+}
+
+/// The data of a zlib stream: a two byte header, deflate blocks and a
+/// checksum, which isn't checked. Fails rather than write more than `limit`
+/// bytes.
+pub fn zlib_decompress(data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let [cmf, flg, ..] = *data else {
+        return Err(error("missing zlib header"));
+    };
+    if cmf & 0x0f != 8 || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return Err(error("bad zlib header"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(error("preset dictionaries aren't supported"));
+    }
+    inflate(&data[2..], limit)
+}
+
+/// Raw deflate data, at most `limit` bytes of it
+pub fn inflate(data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let mut state = State {
+        data,
+        at: 0,
+        bit_buffer: 0,
+        bit_count: 0,
+        out: Vec::new(),
+        limit,
+    };
+    loop {
+        let last = state.bits(1)? == 1;
+        match state.bits(2)? {
+            0 => state.stored()?,
+            1 => state.fixed()?,
+            2 => state.dynamic()?,
+            _ => return Err(error("bad block type")),
+        }
+        if last {
+            return Ok(state.out);
+        }
+    }
+}
+
+const MAX_BITS: usize = 15;
+
+// a canonical Huffman code, as the number of codes of each length and the
+// symbols ordered by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    // from the code length of each symbol, zero for unused symbols
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        if counts[0] as usize == lengths.len() {
+            // no codes, fine as long as nothing is decoded with it
+            return Ok(Self {
+                counts,
+                symbols: Vec::new(),
+            });
+        }
+
+        // more codes of a length than there is room for
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - i32::from(count);
+            if left < 0 {
+                return Err(error("oversubscribed code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+}
+
+// length and distance bases and extra bits, from RFC 1951 3.2.5
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// the order code length code lengths are stored in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+struct State<'a> {
+    data: &'a [u8],
+    at: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+    out: Vec<u8>,
+    // a few bytes of deflate can stand for a lot of output, so it is capped
+    limit: usize,
+}
+
+impl State<'_> {
+    // the next n bits, least significant first
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.bit_count < n {
+            let byte = *self
+                .data
+                .get(self.at)
+                .ok_or_else(|| error("unexpected end"))?;
+            self.at += 1;
+            self.bit_buffer |= u32::from(byte) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buffer & ((1u64 << n) - 1) as u32;
+        self.bit_buffer >>= n;
+        self.bit_count -= n;
+        Ok(value)
+    }
+
+    fn make_room(&self, n: usize) -> io::Result<()> {
+        match self.out.len() + n <= self.limit {
+            true => Ok(()),
+            false => Err(error("more data than expected")),
+        }
+    }
+
+    fn stored(&mut self) -> io::Result<()> {
+        // to the next byte boundary
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+        let header = self
+            .data
+            .get(self.at..self.at + 4)
+            .ok_or_else(|| error("unexpected end"))?;
+        let length = u16::from_le_bytes([header[0], header[1]]);
+        let complement = u16::from_le_bytes([header[2], header[3]]);
+        if length != !complement {
+            return Err(error("stored block length mismatch"));
+        }
+        self.at += 4;
+        let block = self
+            .data
+            .get(self.at..self.at + length as usize)
+            .ok_or_else(|| error("unexpected end"))?;
+        self.make_room(block.len())?;
+        self.out.extend_from_slice(block);
+        self.at += length as usize;
+        Ok(())
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> io::Result<u16> {
+        // codes are stored most significant bit first, so they're read a
+        // bit at a time and compared against each length's range
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..=MAX_BITS {
+            code |= self.bits(1)? as i32;
+            let count = i32::from(huffman.counts[length]);
+            if code - first < count {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(error("bad code"))
+    }
+
+    fn codes(&mut self, literals: &Huffman, distances: &Huffman) -> io::Result<()> {
+        loop {
+            let symbol = self.decode(literals)?;
+            match symbol {
+                0..=255 => {
+                    self.make_room(1)?;
+                    self.out.push(symbol as u8);
+                }
+                256 => return Ok(()),
+                _ => {
+                    let i = (symbol - 257) as usize;
+                    if i >= LENGTH_BASE.len() {
+                        return Err(error("bad length symbol"));
+                    }
+                    let length =
+                        LENGTH_BASE[i] as usize + self.bits(u32::from(LENGTH_EXTRA[i]))? as usize;
+
+                    let d = self.decode(distances)? as usize;
+                    if d >= DISTANCE_BASE.len() {
+                        return Err(error("bad distance symbol"));
+                    }
+                    let distance = DISTANCE_BASE[d] as usize
+                        + self.bits(u32::from(DISTANCE_EXTRA[d]))? as usize;
+                    if distance > self.out.len() {
+                        return Err(error("distance too far back"));
+                    }
+                    self.make_room(length)?;
+
+                    // byte by byte, the copy may overlap what it writes
+                    let start = self.out.len() - distance;
+                    for k in 0..length {
+                        let byte = self.out[start + k];
+                        self.out.push(byte);
+                    }
+                }
+            }
+        }
+    }
+
+    fn fixed(&mut self) -> io::Result<()> {
+        let mut lengths = [0u8; 288];
+        lengths[..144].fill(8);
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        lengths[280..].fill(8);
+        let literals = Huffman::new(&lengths)?;
+        let distances = Huffman::new(&[5; 30])?;
+        self.codes(&literals, &distances)
+    }
+
+    fn dynamic(&mut self) -> io::Result<()> {
+        let literal_count = self.bits(5)? as usize + 257;
+        let distance_count = self.bits(5)? as usize + 1;
+        let code_length_count = self.bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err(error("too many codes"));
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &i in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[i] = self.bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        // the literal and distance code lengths, run length coded together
+        let mut lengths = vec![0u8; literal_count + distance_count];
+        let mut i = 0;
+        while i < lengths.len() {
+            let symbol = self.decode(&code_length_code)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *i
+                        .checked_sub(1)
+                        .and_then(|p| lengths.get(p))
+                        .ok_or_else(|| error("repeat with no previous length"))?;
+                    (previous, 3 + self.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits(3)? as usize),
+                _ => (0, 11 + self.bits(7)? as usize),
+            };
+            if i + repeat > lengths.len() {
+                return Err(error("too many code lengths"));
+            }
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err(error("no end of block code"));
+        }
+
+        let literals = Huffman::new(&lengths[..literal_count])?;
+        let distances = Huffman::new(&lengths[literal_count..])?;
+        self.codes(&literals, &distances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "hello hello hello hello" with the fixed code, made by zlib with
+    // Z_FIXED and no header
+    const FIXED: [u8; 10] = [0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01];
+
+    // DYNAMIC_TEXT three times, compressed by zlib at level 9 without a header
+    const DYNAMIC_TEXT: &[u8] = b"a dynamic block needs enough text that a tailored code \
+        beats the fixed one, so this sentence repeats: ";
+    const DYNAMIC: [u8; 86] = [
+        0xdd, 0x8d, 0xc1, 0x0d, 0x80, 0x30, 0x0c, 0x03, 0x57, 0xf1, 0x00, 0x4c, 0xc0, 0x36, 0xa1,
+        0x35, 0xb4, 0x02, 0x12, 0xd4, 0x04, 0xa9, 0x6c, 0x4f, 0x59, 0x83, 0xef, 0xf9, 0xac, 0x13,
+        0xe4, 0x47, 0xe5, 0xac, 0x09, 0xcb, 0x61, 0x69, 0x87, 0x92, 0xd9, 0x41, 0xb5, 0x7b, 0x2b,
+        0x08, 0xf6, 0x40, 0x14, 0x09, 0x08, 0x42, 0xea, 0x61, 0x8d, 0x19, 0xc9, 0x32, 0xb1, 0x50,
+        0xc2, 0xc7, 0x44, 0xac, 0xb5, 0x0f, 0x68, 0xca, 0x09, 0x6e, 0x83, 0x54, 0x87, 0x53, 0x83,
+        0x9a, 0x88, 0xc6, 0xeb, 0xf3, 0xe6, 0x71, 0xff, 0x4f, 0xe5, 0x05,
+    ];
+
+    // a stored block holding data, the last one if `last`
+    fn stored(data: &[u8], last: bool) -> Vec<u8> {
+        let length = data.len() as u16;
+        let mut block = vec![last as u8];
+        block.extend(length.to_le_bytes());
+        block.extend((!length).to_le_bytes());
+        block.extend(data);
+        block
+    }
+
+    #[test]
+    fn stored_block() {
+        let data = stored(b"stored as it is", true);
+        assert_eq!(inflate(&data, 100).unwrap(), b"stored as it is");
+    }
+
+    #[test]
+    fn empty_stored_block() {
+        assert_eq!(inflate(&stored(b"", true), 0).unwrap(), b"");
+    }
+
+    #[test]
+    fn fixed_block() {
+        assert_eq!(FIXED[0] >> 1 & 3, 1);
+        assert_eq!(inflate(&FIXED, 100).unwrap(), b"hello hello hello hello");
+    }
+
+    #[test]
+    fn dynamic_block() {
+        assert_eq!(DYNAMIC[0] >> 1 & 3, 2);
+        assert_eq!(inflate(&DYNAMIC, 1000).unwrap(), DYNAMIC_TEXT.repeat(3));
+    }
+
+    #[test]
+    fn blocks_follow_each_other() {
+        // a stored block ends on a byte boundary, where the next one starts
+        let mut data = stored(b"first ", false);
+        data.extend(FIXED);
+        assert_eq!(
+            inflate(&data, 100).unwrap(),
+            b"first hello hello hello hello"
+        );
+    }
+
+    #[test]
+    fn zlib_stream() {
+        let mut data = vec![0x78, 0x01];
+        data.extend(stored(b"zlib", true));
+        // the checksum, which isn't checked
+        data.extend([0; 4]);
+        assert_eq!(zlib_decompress(&data, 4).unwrap(), b"zlib");
+    }
+
+    #[test]
+    fn bad_zlib_headers() {
+        let body = stored(b"zlib", true);
+        for header in [[0x78, 0x02], [0x79, 0x01], [0x78, 0xbb]] {
+            let data = [&header[..], &body].concat();
+            assert!(zlib_decompress(&data, 4).is_err(), "{header:x?}");
+        }
+        assert!(zlib_decompress(&[0x78], 4).is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        for data in [&DYNAMIC[..], &FIXED, &stored(b"stored", true)] {
+            for end in 0..data.len() {
+                assert!(inflate(&data[..end], 1000).is_err(), "cut at {end}");
+            }
+        }
+    }
+
+    #[test]
+    fn over_the_limit() {
+        assert!(inflate(&DYNAMIC, DYNAMIC_TEXT.len() * 3 - 1).is_err());
+        assert!(inflate(&FIXED, 22).is_err());
+        assert!(inflate(&stored(b"stored", true), 5).is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        // block type 3
+        assert!(inflate(&[0b111], 100).is_err());
+        // stored length that doesn't match its complement
+        assert!(inflate(&[1, 4, 0, 0xfb, 0xfe, 1, 2, 3, 4], 100).is_err());
+        // a fixed block starting with a copy from before the output
+        let error = inflate(&[0x03, 0x02], 100).unwrap_err();
+        assert!(error.to_string().contains("too far back"), "{error}");
+    }
+}
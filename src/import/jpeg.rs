@@ -0,0 +1,1085 @@
+use super::png::DecodedImage;
+use crate::{color::Color, float::Float, image::Image};
+use std::io;
+
+fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("jpeg: {message}"))
+}
+
+// bigger images are refused, so a broken or hostile header can't ask for
+// more memory than there is
+const MAX_PIXELS: usize = 1 << 26;
+
+// where each coefficient, in the order they're stored, goes in its block
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+pub fn is_jpeg(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xff, 0xd8, 0xff])
+}
+
+// a huffman table the way the standard decodes it (F.2.2.3): the largest
+// code of every length, and how far its values are from the code
+#[derive(Clone)]
+struct Huffman {
+    max_code: [i32; 17],
+    offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl Huffman {
+    // counts[i] codes of length i + 1, the values in order of their codes
+    fn new(counts: &[u8], values: &[u8]) -> io::Result<Self> {
+        let mut max_code = [-1; 17];
+        let mut offset = [0; 17];
+        let (mut code, mut index) = (0, 0);
+        for length in 1..=16 {
+            let count = i32::from(counts[length - 1]);
+            if count > 0 {
+                offset[length] = index - code;
+                index += count;
+                code += count;
+                max_code[length] = code - 1;
+            }
+            if code > 1 << length {
+                return Err(error("huffman table has too many codes"));
+            }
+            code <<= 1;
+        }
+        Ok(Self {
+            max_code,
+            offset,
+            values: values.to_vec(),
+        })
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> io::Result<u8> {
+        let mut code = 0;
+        for length in 1..=16 {
+            code = code << 1 | bits.bit()? as i32;
+            if code <= self.max_code[length] {
+                return Ok(self.values[(code + self.offset[length]) as usize]);
+            }
+        }
+        Err(error("bad huffman code"))
+    }
+}
+
+// the entropy coded data of a scan, where 0xff is followed by a zero byte
+// that isn't data
+struct BitReader<'a> {
+    data: &'a [u8],
+    at: usize,
+    byte: u32,
+    left: u32,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> io::Result<u32> {
+        if self.left == 0 {
+            let truncated = || error("truncated scan");
+            let byte = *self.data.get(self.at).ok_or_else(truncated)?;
+            self.byte = match byte {
+                0xff => match *self.data.get(self.at + 1).ok_or_else(truncated)? {
+                    0 => {
+                        self.at += 2;
+                        0xff
+                    }
+                    // a marker, the coder stopped early and the rest is zeros
+                    _ => 0,
+                },
+                _ => {
+                    self.at += 1;
+                    u32::from(byte)
+                }
+            };
+            self.left = 8;
+        }
+        self.left -= 1;
+        Ok(self.byte >> self.left & 1)
+    }
+
+    fn bits(&mut self, count: u32) -> io::Result<i32> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = value << 1 | self.bit()?;
+        }
+        Ok(value as i32)
+    }
+
+    // a number of the given size, where the lower half of the codes are
+    // the negative ones (F.2.2.1)
+    fn signed(&mut self, size: u32) -> io::Result<i32> {
+        if size == 0 {
+            return Ok(0);
+        }
+        let value = self.bits(size)?;
+        Ok(match value < 1 << (size - 1) {
+            true => value - (1 << size) + 1,
+            false => value,
+        })
+    }
+
+    // the rest of the byte is padding, then comes a restart marker
+    fn restart(&mut self) -> io::Result<()> {
+        self.left = 0;
+        while self.data.get(self.at..self.at + 2) == Some(&[0xff, 0xff]) {
+            self.at += 1;
+        }
+        match self.data.get(self.at..self.at + 2) {
+            Some(&[0xff, 0xd0..=0xd7]) => {
+                self.at += 2;
+                Ok(())
+            }
+            _ => Err(error("missing restart marker")),
+        }
+    }
+}
+
+struct Component {
+    id: u8,
+    // sampling factors, how many blocks across and down it has per MCU
+    h: usize,
+    v: usize,
+    quant: usize,
+    // blocks covering the whole of every MCU
+    blocks_across: usize,
+    samples: Vec<u8>,
+    scanned: bool,
+}
+
+impl Component {
+    fn stride(&self) -> usize {
+        self.blocks_across * 8
+    }
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    h_max: usize,
+    v_max: usize,
+    mcus_across: usize,
+    mcus_down: usize,
+    components: Vec<Component>,
+}
+
+impl Frame {
+    fn new(segment: &[u8]) -> io::Result<Self> {
+        if segment.len() < 6 {
+            return Err(error("short frame header"));
+        }
+        if segment[0] != 8 {
+            return Err(error("only 8 bit samples are supported"));
+        }
+        let height = usize::from(u16::from_be_bytes([segment[1], segment[2]]));
+        let width = usize::from(u16::from_be_bytes([segment[3], segment[4]]));
+        if width == 0 || height == 0 {
+            return Err(error("image has no size"));
+        }
+        if width * height > MAX_PIXELS {
+            return Err(error("image too large"));
+        }
+        let count = usize::from(segment[5]);
+        if count != 1 && count != 3 {
+            return Err(error("only grayscale and color images are supported"));
+        }
+        let specs = segment
+            .get(6..6 + 3 * count)
+            .ok_or_else(|| error("short frame header"))?;
+        let mut components = Vec::with_capacity(count);
+        for spec in specs.chunks_exact(3) {
+            let (h, v) = (usize::from(spec[1] >> 4), usize::from(spec[1] & 15));
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) || spec[2] > 3 {
+                return Err(error("bad frame component"));
+            }
+            components.push(Component {
+                id: spec[0],
+                h,
+                v,
+                quant: usize::from(spec[2]),
+                blocks_across: 0,
+                samples: Vec::new(),
+                scanned: false,
+            });
+        }
+
+        let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+        let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+        let mcus_across = width.div_ceil(8 * h_max);
+        let mcus_down = height.div_ceil(8 * v_max);
+        for c in &mut components {
+            c.blocks_across = mcus_across * c.h;
+            c.samples = vec![0; c.stride() * mcus_down * c.v * 8];
+        }
+        Ok(Self {
+            width,
+            height,
+            h_max,
+            v_max,
+            mcus_across,
+            mcus_down,
+            components,
+        })
+    }
+
+    // samples are taken from the nearest one of subsampled components
+    fn image(&self, rgb: bool) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        let mut values = [0.; 3];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for (value, c) in values.iter_mut().zip(&self.components) {
+                    let at = y * c.v / self.v_max * c.stride() + x * c.h / self.h_max;
+                    *value = Float::from(c.samples[at]);
+                }
+                let color = match (self.components.len(), rgb) {
+                    (1, _) => Color(values[0], values[0], values[0]),
+                    (_, true) => Color(values[0], values[1], values[2]),
+                    // JFIF's YCbCr, full range (JFIF 1.02, section 7)
+                    _ => {
+                        let [luma, cb, cr] = [values[0], values[1] - 128., values[2] - 128.];
+                        let rgb = [
+                            luma + 1.402 * cr,
+                            luma - 0.344136 * cb - 0.714136 * cr,
+                            luma + 1.772 * cb,
+                        ]
+                        .map(|v| v.round().clamp(0., 255.));
+                        Color(rgb[0], rgb[1], rgb[2])
+                    }
+                };
+                image.set(x, y, color / 255.);
+            }
+        }
+        image
+    }
+}
+
+struct Tables {
+    quant: [Option<[u16; 64]>; 4],
+    dc: [Option<Huffman>; 4],
+    ac: [Option<Huffman>; 4],
+    // MCUs between restart markers, 0 for none
+    restart_interval: usize,
+    idct: [[f32; 8]; 8],
+}
+
+/// Baseline JPEG, and the extended sequential kind with 8 bit samples,
+/// grayscale or color with any chroma subsampling. Progressive and
+/// arithmetic coded files aren't supported.
+pub fn read_jpeg(bytes: &[u8]) -> io::Result<DecodedImage> {
+    if !is_jpeg(bytes) {
+        return Err(error("not a jpeg file"));
+    }
+
+    let mut tables = Tables {
+        quant: [None; 4],
+        dc: Default::default(),
+        ac: Default::default(),
+        restart_interval: 0,
+        idct: idct_table(),
+    };
+    let mut frame = None;
+    // Adobe's marker says whether 3 components are YCbCr or RGB
+    let mut adobe_transform = None;
+    let mut at = 2;
+    while at < bytes.len() {
+        if bytes[at] != 0xff {
+            return Err(error("expected a marker"));
+        }
+        // markers can be padded with any number of 0xff
+        while bytes.get(at) == Some(&0xff) {
+            at += 1;
+        }
+        let marker = *bytes.get(at).ok_or_else(|| error("truncated marker"))?;
+        at += 1;
+        match marker {
+            // end of image
+            0xd9 => break,
+            // markers without a segment
+            0x01 | 0xd0..=0xd7 => continue,
+            _ => {}
+        }
+        let length = bytes
+            .get(at..at + 2)
+            .map(|b| usize::from(u16::from_be_bytes([b[0], b[1]])))
+            .filter(|&length| length >= 2)
+            .ok_or_else(|| error("truncated segment"))?;
+        let segment = bytes
+            .get(at + 2..at + length)
+            .ok_or_else(|| error("truncated segment"))?;
+        at += length;
+
+        match marker {
+            0xdb => read_quant_tables(segment, &mut tables.quant)?,
+            0xc4 => read_huffman_tables(segment, &mut tables)?,
+            0xc0 | 0xc1 if frame.is_some() => return Err(error("more than one frame")),
+            0xc0 | 0xc1 => frame = Some(Frame::new(segment)?),
+            0xc2 | 0xc6 | 0xca | 0xce => {
+                return Err(error("progressive jpegs aren't supported"));
+            }
+            0xc3 | 0xc5 | 0xc7 | 0xc9 | 0xcb | 0xcd | 0xcf => {
+                return Err(error("only baseline and sequential jpegs are supported"));
+            }
+            0xdd => {
+                let interval = segment
+                    .get(..2)
+                    .ok_or_else(|| error("short restart interval"))?;
+                tables.restart_interval =
+                    usize::from(u16::from_be_bytes([interval[0], interval[1]]));
+            }
+            0xee if segment.starts_with(b"Adobe") && segment.len() >= 12 => {
+                adobe_transform = Some(segment[11]);
+            }
+            0xda => {
+                let frame = frame.as_mut().ok_or_else(|| error("scan before frame"))?;
+                at = read_scan(bytes, at, segment, frame, &tables)?;
+            }
+            // application data and comments
+            _ => {}
+        }
+    }
+
+    let frame = frame.ok_or_else(|| error("no image"))?;
+    if !frame.components.iter().all(|c| c.scanned) {
+        return Err(error("missing scans"));
+    }
+    let ids: Vec<u8> = frame.components.iter().map(|c| c.id).collect();
+    let rgb = adobe_transform == Some(0) || ids == b"RGB";
+    Ok(DecodedImage {
+        color: frame.image(rgb),
+        alpha: None,
+    })
+}
+
+// stored in the order of the coefficients, zigzag
+fn read_quant_tables(mut segment: &[u8], quant: &mut [Option<[u16; 64]>; 4]) -> io::Result<()> {
+    while let Some((&info, rest)) = segment.split_first() {
+        let (wide, id) = (info >> 4 == 1, usize::from(info & 15));
+        let size = if wide { 128 } else { 64 };
+        let values = rest
+            .get(..size)
+            .ok_or_else(|| error("short quantization table"))?;
+        let table = quant
+            .get_mut(id)
+            .ok_or_else(|| error("bad quantization table"))?;
+        *table = Some(std::array::from_fn(|i| match wide {
+            true => u16::from_be_bytes([values[2 * i], values[2 * i + 1]]),
+            false => u16::from(values[i]),
+        }));
+        segment = &rest[size..];
+    }
+    Ok(())
+}
+
+fn read_huffman_tables(mut segment: &[u8], tables: &mut Tables) -> io::Result<()> {
+    while let Some((&info, rest)) = segment.split_first() {
+        let counts = rest.get(..16).ok_or_else(|| error("short huffman table"))?;
+        let count = counts.iter().map(|&c| usize::from(c)).sum::<usize>();
+        let values = rest
+            .get(16..16 + count)
+            .ok_or_else(|| error("short huffman table"))?;
+        let table = match info >> 4 {
+            0 => tables.dc.get_mut(usize::from(info & 15)),
+            1 => tables.ac.get_mut(usize::from(info & 15)),
+            _ => None,
+        }
+        .ok_or_else(|| error("bad huffman table"))?;
+        *table = Some(Huffman::new(counts, values)?);
+        segment = &rest[16 + count..];
+    }
+    Ok(())
+}
+
+fn huffman(tables: &[Option<Huffman>; 4], id: u8) -> io::Result<&Huffman> {
+    tables
+        .get(usize::from(id))
+        .and_then(Option::as_ref)
+        .ok_or_else(|| error("missing huffman table"))
+}
+
+// decodes the scan starting at `at` into the frame's components, and returns
+// where the next marker is
+fn read_scan(
+    bytes: &[u8],
+    at: usize,
+    header: &[u8],
+    frame: &mut Frame,
+    tables: &Tables,
+) -> io::Result<usize> {
+    let count = usize::from(*header.first().ok_or_else(|| error("short scan header"))?);
+    let specs = header
+        .get(1..1 + 2 * count)
+        .filter(|_| (1..=4).contains(&count))
+        .ok_or_else(|| error("bad scan header"))?;
+    // the spectral selection and approximation of progressive scans
+    if header.get(1 + 2 * count..1 + 2 * count + 3) != Some(&[0, 63, 0]) {
+        return Err(error("only sequential scans are supported"));
+    }
+    let mut scan = Vec::with_capacity(count);
+    for spec in specs.chunks_exact(2) {
+        let index = frame
+            .components
+            .iter()
+            .position(|c| c.id == spec[0])
+            .ok_or_else(|| error("scan of a missing component"))?;
+        let dc = huffman(&tables.dc, spec[1] >> 4)?;
+        let ac = huffman(&tables.ac, spec[1] & 15)?;
+        let quant = tables.quant[frame.components[index].quant]
+            .as_ref()
+            .ok_or_else(|| error("missing quantization table"))?;
+        scan.push((index, dc, ac, quant));
+    }
+
+    // a component on its own is coded block by block over just its part of
+    // the image, not by MCU
+    let (across, down) = match scan[..] {
+        [(index, ..)] => {
+            let c = &frame.components[index];
+            (
+                (frame.width * c.h).div_ceil(frame.h_max).div_ceil(8),
+                (frame.height * c.v).div_ceil(frame.v_max).div_ceil(8),
+            )
+        }
+        _ => (frame.mcus_across, frame.mcus_down),
+    };
+    let mut reader = BitReader {
+        data: bytes,
+        at,
+        byte: 0,
+        left: 0,
+    };
+    let mut predictions = vec![0; scan.len()];
+    let mut block = [0; 64];
+    for unit in 0..across * down {
+        if tables.restart_interval > 0 && unit > 0 && unit % tables.restart_interval == 0 {
+            reader.restart()?;
+            predictions.fill(0);
+        }
+        let (ux, uy) = (unit % across, unit / across);
+        for ((index, dc, ac, quant), prediction) in scan.iter().zip(&mut predictions) {
+            let c = &mut frame.components[*index];
+            let (h, v) = match count {
+                1 => (1, 1),
+                _ => (c.h, c.v),
+            };
+            for by in 0..v {
+                for bx in 0..h {
+                    read_block(&mut reader, dc, ac, quant, prediction, &mut block)?;
+                    let (x, y) = ((ux * h + bx) * 8, (uy * v + by) * 8);
+                    let stride = c.stride();
+                    idct(
+                        &block,
+                        &tables.idct,
+                        &mut c.samples[y * stride + x..],
+                        stride,
+                    );
+                }
+            }
+        }
+    }
+    for (index, ..) in scan {
+        frame.components[index].scanned = true;
+    }
+
+    // past any padding to the next marker
+    let mut at = reader.at;
+    let is_marker = |at: usize| bytes[at] == 0xff && !matches!(bytes[at + 1], 0 | 0xd0..=0xd7);
+    while at + 1 < bytes.len() && !is_marker(at) {
+        at += 1;
+    }
+    Ok(at)
+}
+
+// one block's coefficients, dequantized and in place (F.2.2)
+fn read_block(
+    reader: &mut BitReader,
+    dc: &Huffman,
+    ac: &Huffman,
+    quant: &[u16; 64],
+    prediction: &mut i32,
+    block: &mut [i32; 64],
+) -> io::Result<()> {
+    block.fill(0);
+    let size = dc.decode(reader)?;
+    if size > 11 {
+        return Err(error("bad dc coefficient"));
+    }
+    *prediction += reader.signed(u32::from(size))?;
+    block[0] = *prediction * i32::from(quant[0]);
+
+    let mut k = 1;
+    while k < 64 {
+        let symbol = ac.decode(reader)?;
+        let (run, size) = (usize::from(symbol >> 4), u32::from(symbol & 15));
+        if size == 0 {
+            match run {
+                // sixteen zeros
+                15 => {
+                    k += 16;
+                    continue;
+                }
+                // the rest are zeros
+                _ => break,
+            }
+        }
+        k += run;
+        if k > 63 {
+            return Err(error("too many coefficients"));
+        }
+        block[ZIGZAG[k]] = reader.signed(size)? * i32::from(quant[k]);
+        k += 1;
+    }
+    Ok(())
+}
+
+// cos((2x + 1) u pi / 16) with the scale of u's basis function, for
+// table[x][u]
+fn idct_table() -> [[f32; 8]; 8] {
+    std::array::from_fn(|x| {
+        std::array::from_fn(|u| {
+            let scale = if u == 0 { 0.5 / 2f32.sqrt() } else { 0.5 };
+            scale * ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.).cos()
+        })
+    })
+}
+
+// the 8x8 inverse dct straight from its definition, along the rows and then
+// the columns, into samples shifted back up to 0..=255
+fn idct(block: &[i32; 64], table: &[[f32; 8]; 8], out: &mut [u8], stride: usize) {
+    let mut rows = [0f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8).map(|u| table[x][u] * block[v * 8 + u] as f32).sum();
+        }
+    }
+    for y in 0..8 {
+        for x in 0..8 {
+            let value: f32 = (0..8).map(|v| table[y][v] * rows[v * 8 + x]).sum();
+            out[y * stride + x] = (value + 128.).round().clamp(0., 255.) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::float::random;
+
+    // a component's samples, at its own size
+    struct Plane {
+        id: u8,
+        h: usize,
+        v: usize,
+        quant: usize,
+        samples: Vec<u8>,
+    }
+
+    // what goes into a test file, coded with huffman tables made for it
+    struct Jpeg {
+        width: usize,
+        height: usize,
+        planes: Vec<Plane>,
+        // in zigzag order, written with 16 bits when any value needs it
+        quant: Vec<[u16; 64]>,
+        restart_interval: usize,
+        // one scan per component instead of all of them together
+        separate_scans: bool,
+        adobe_transform: Option<u8>,
+    }
+
+    #[derive(Default)]
+    struct BitWriter {
+        out: Vec<u8>,
+        byte: u32,
+        filled: u32,
+    }
+
+    impl BitWriter {
+        fn put(&mut self, code: u32, length: u32) {
+            for i in (0..length).rev() {
+                self.byte = self.byte << 1 | (code >> i & 1);
+                self.filled += 1;
+                if self.filled == 8 {
+                    self.out.push(self.byte as u8);
+                    if self.byte == 0xff {
+                        self.out.push(0);
+                    }
+                    (self.byte, self.filled) = (0, 0);
+                }
+            }
+        }
+
+        // pads the last byte with ones
+        fn flush(&mut self) {
+            if self.filled > 0 {
+                self.put(0x7f, 8 - self.filled);
+            }
+        }
+    }
+
+    enum Code {
+        // class 0 for dc and 1 for ac, table, symbol, and the bits after it
+        Symbol(usize, usize, u8, u32, u32),
+        Restart(usize),
+    }
+
+    // the size of a coefficient and its bits, the inverse of `signed`
+    fn size_and_bits(value: i32) -> (u32, u32) {
+        let size = 32 - value.unsigned_abs().leading_zeros();
+        let bits = match value < 0 {
+            true => value - 1 + (1 << size),
+            false => value,
+        };
+        (size, bits as u32 & ((1 << size) - 1))
+    }
+
+    // quantized coefficients of the block at (bx, by), in zigzag order,
+    // with the samples past the edge repeating the last ones
+    fn forward_dct(
+        plane: &Plane,
+        width: usize,
+        height: usize,
+        bx: usize,
+        by: usize,
+        quant: &[u16; 64],
+    ) -> [i32; 64] {
+        let table = idct_table();
+        let sample = |x: usize, y: usize| {
+            let (x, y) = ((bx * 8 + x).min(width - 1), (by * 8 + y).min(height - 1));
+            f32::from(plane.samples[y * width + x]) - 128.
+        };
+        std::array::from_fn(|k| {
+            let (u, v) = (ZIGZAG[k] % 8, ZIGZAG[k] / 8);
+            let mut sum = 0.;
+            for y in 0..8 {
+                for x in 0..8 {
+                    sum += table[x][u] * table[y][v] * sample(x, y);
+                }
+            }
+            (sum / f32::from(quant[k])).round() as i32
+        })
+    }
+
+    impl Jpeg {
+        fn plane_size(&self, plane: &Plane) -> (usize, usize) {
+            let h_max = self.planes.iter().map(|p| p.h).max().unwrap();
+            let v_max = self.planes.iter().map(|p| p.v).max().unwrap();
+            (
+                (self.width * plane.h).div_ceil(h_max),
+                (self.height * plane.v).div_ceil(v_max),
+            )
+        }
+
+        // the codes of one scan, the first plane uses tables 0, the others 1
+        fn scan(&self, planes: &[usize], codes: &mut Vec<Code>) {
+            let h_max = self.planes.iter().map(|p| p.h).max().unwrap();
+            let v_max = self.planes.iter().map(|p| p.v).max().unwrap();
+            let (across, down) = match planes {
+                [i] => {
+                    let (width, height) = self.plane_size(&self.planes[*i]);
+                    (width.div_ceil(8), height.div_ceil(8))
+                }
+                _ => (
+                    self.width.div_ceil(8 * h_max),
+                    self.height.div_ceil(8 * v_max),
+                ),
+            };
+            let mut predictions = vec![0; planes.len()];
+            let mut restarts = 0;
+            for unit in 0..across * down {
+                if self.restart_interval > 0 && unit > 0 && unit % self.restart_interval == 0 {
+                    codes.push(Code::Restart(restarts));
+                    restarts += 1;
+                    predictions.fill(0);
+                }
+                let (ux, uy) = (unit % across, unit / across);
+                for (&i, prediction) in planes.iter().zip(&mut predictions) {
+                    let plane = &self.planes[i];
+                    let (width, height) = self.plane_size(plane);
+                    let (h, v) = match planes.len() {
+                        1 => (1, 1),
+                        _ => (plane.h, plane.v),
+                    };
+                    let table = usize::from(i > 0);
+                    for by in 0..v {
+                        for bx in 0..h {
+                            let quant = &self.quant[plane.quant];
+                            let block =
+                                forward_dct(plane, width, height, ux * h + bx, uy * v + by, quant);
+                            let (size, bits) = size_and_bits(block[0] - *prediction);
+                            *prediction = block[0];
+                            codes.push(Code::Symbol(0, table, size as u8, bits, size));
+                            let mut run = 0;
+                            for &value in &block[1..] {
+                                if value == 0 {
+                                    run += 1;
+                                    continue;
+                                }
+                                while run >= 16 {
+                                    codes.push(Code::Symbol(1, table, 0xf0, 0, 0));
+                                    run -= 16;
+                                }
+                                let (size, bits) = size_and_bits(value);
+                                codes.push(Code::Symbol(
+                                    1,
+                                    table,
+                                    (run << 4) as u8 | size as u8,
+                                    bits,
+                                    size,
+                                ));
+                                run = 0;
+                            }
+                            if run > 0 {
+                                codes.push(Code::Symbol(1, table, 0, 0, 0));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        fn file(&self) -> Vec<u8> {
+            let scans: Vec<Vec<usize>> = match self.separate_scans {
+                true => (0..self.planes.len()).map(|i| vec![i]).collect(),
+                false => vec![(0..self.planes.len()).collect()],
+            };
+            let coded: Vec<Vec<Code>> = scans
+                .iter()
+                .map(|planes| {
+                    let mut codes = Vec::new();
+                    self.scan(planes, &mut codes);
+                    codes
+                })
+                .collect();
+
+            // every symbol used gets a code: two of 3 bits, four of 5, and
+            // the rest 9, which never runs out or makes a code of all ones
+            let mut tables = [[(); 2]; 2].map(|t| t.map(|_| Vec::<u8>::new()));
+            for code in coded.iter().flatten() {
+                if let Code::Symbol(class, table, symbol, ..) = *code {
+                    tables[class][table].push(symbol);
+                }
+            }
+            let mut lookup = [[(); 2]; 2].map(|t| t.map(|_| [(0u32, 0u32); 256]));
+            let mut out = vec![0xff, 0xd8];
+            let segment = |out: &mut Vec<u8>, marker: u8, data: &[u8]| {
+                out.extend([0xff, marker]);
+                out.extend(((data.len() + 2) as u16).to_be_bytes());
+                out.extend(data);
+            };
+            if let Some(transform) = self.adobe_transform {
+                let mut data = b"Adobe".to_vec();
+                data.extend([0, 100, 0, 0, 0, 0, transform]);
+                segment(&mut out, 0xee, &data);
+            }
+            for (id, table) in self.quant.iter().enumerate() {
+                let wide = table.iter().any(|&q| q > 255);
+                let mut data = vec![(wide as u8) << 4 | id as u8];
+                for &q in table {
+                    match wide {
+                        true => data.extend(q.to_be_bytes()),
+                        false => data.push(q as u8),
+                    }
+                }
+                segment(&mut out, 0xdb, &data);
+            }
+            let mut frame = vec![8];
+            frame.extend((self.height as u16).to_be_bytes());
+            frame.extend((self.width as u16).to_be_bytes());
+            frame.push(self.planes.len() as u8);
+            for plane in &self.planes {
+                frame.extend([plane.id, (plane.h << 4 | plane.v) as u8, plane.quant as u8]);
+            }
+            segment(&mut out, 0xc0, &frame);
+            for class in 0..2 {
+                for table in 0..2 {
+                    let symbols = &mut tables[class][table];
+                    symbols.sort();
+                    symbols.dedup();
+                    if symbols.is_empty() {
+                        continue;
+                    }
+                    let mut counts = [0u8; 16];
+                    let lengths: Vec<usize> = (0..symbols.len())
+                        .map(|i| match i {
+                            0..2 => 3,
+                            2..6 => 5,
+                            _ => 9,
+                        })
+                        .collect();
+                    lengths.iter().for_each(|&l| counts[l - 1] += 1);
+                    let mut code = 0;
+                    let mut previous = 1;
+                    for (&symbol, &length) in symbols.iter().zip(&lengths) {
+                        code <<= length - previous;
+                        previous = length;
+                        lookup[class][table][usize::from(symbol)] = (code, length as u32);
+                        code += 1;
+                    }
+                    let mut data = vec![(class << 4 | table) as u8];
+                    data.extend(counts);
+                    data.extend(symbols.iter());
+                    segment(&mut out, 0xc4, &data);
+                }
+            }
+            if self.restart_interval > 0 {
+                segment(
+                    &mut out,
+                    0xdd,
+                    &(self.restart_interval as u16).to_be_bytes(),
+                );
+            }
+
+            for (planes, codes) in scans.iter().zip(&coded) {
+                let mut header = vec![planes.len() as u8];
+                for &i in planes {
+                    let table = u8::from(i > 0);
+                    header.extend([self.planes[i].id, table << 4 | table]);
+                }
+                header.extend([0, 63, 0]);
+                segment(&mut out, 0xda, &header);
+
+                let mut writer = BitWriter::default();
+                for code in codes {
+                    match *code {
+                        Code::Symbol(class, table, symbol, bits, size) => {
+                            let (code, length) = lookup[class][table][usize::from(symbol)];
+                            writer.put(code, length);
+                            writer.put(bits, size);
+                        }
+                        Code::Restart(n) => {
+                            writer.flush();
+                            writer.out.extend([0xff, 0xd0 + (n % 8) as u8]);
+                        }
+                    }
+                }
+                writer.flush();
+                out.extend(writer.out);
+            }
+            out.extend([0xff, 0xd9]);
+            out
+        }
+    }
+
+    // a plane of smooth gradients with some noise, at its share of the
+    // image's size
+    fn plane(id: u8, (h, v): (usize, usize), quant: usize, size: (usize, usize)) -> Plane {
+        let samples = (0..size.0 * size.1)
+            .map(|i| {
+                let (x, y) = ((i % size.0) as Float, (i / size.0) as Float);
+                let smooth = 128. + 80. * (0.3 * x + 0.2 * y + Float::from(id)).sin();
+                (smooth + 40. * (random() - 0.5)) as u8
+            })
+            .collect();
+        Plane {
+            id,
+            h,
+            v,
+            quant,
+            samples,
+        }
+    }
+
+    fn gray(width: usize, height: usize) -> Jpeg {
+        Jpeg {
+            width,
+            height,
+            planes: vec![plane(1, (1, 1), 0, (width, height))],
+            quant: vec![[1; 64]],
+            restart_interval: 0,
+            separate_scans: false,
+            adobe_transform: None,
+        }
+    }
+
+    /// A small grayscale file, for tests of what reads jpegs
+    pub(crate) fn gray_file(width: usize, height: usize) -> Vec<u8> {
+        gray(width, height).file()
+    }
+
+    // 4:2:0, the usual subsampling of color jpegs
+    fn color(width: usize, height: usize) -> Jpeg {
+        let half = (width.div_ceil(2), height.div_ceil(2));
+        Jpeg {
+            width,
+            height,
+            planes: vec![
+                plane(1, (2, 2), 0, (width, height)),
+                plane(2, (1, 1), 1, half),
+                plane(3, (1, 1), 1, half),
+            ],
+            quant: vec![[1; 64], [1; 64]],
+            restart_interval: 0,
+            separate_scans: false,
+            adobe_transform: None,
+        }
+    }
+
+    // the largest difference from the samples, in steps of 1 / 255, with
+    // subsampled planes scaled up to the nearest sample
+    fn largest_error(jpeg: &Jpeg, decoded: &Image, rgb: bool) -> Float {
+        assert_eq!(
+            (decoded.width(), decoded.height()),
+            (jpeg.width, jpeg.height)
+        );
+        let h_max = jpeg.planes.iter().map(|p| p.h).max().unwrap();
+        let v_max = jpeg.planes.iter().map(|p| p.v).max().unwrap();
+        let mut largest: Float = 0.;
+        for y in 0..jpeg.height {
+            for x in 0..jpeg.width {
+                let values: Vec<Float> = jpeg
+                    .planes
+                    .iter()
+                    .map(|p| {
+                        let width = jpeg.plane_size(p).0;
+                        Float::from(p.samples[y * p.v / v_max * width + x * p.h / h_max])
+                    })
+                    .collect();
+                let expected = match (&values[..], rgb) {
+                    (&[g], _) => [g, g, g],
+                    (&[r, g, b], true) => [r, g, b],
+                    (&[y, cb, cr], false) => [
+                        y + 1.402 * (cr - 128.),
+                        y - 0.344136 * (cb - 128.) - 0.714136 * (cr - 128.),
+                        y + 1.772 * (cb - 128.),
+                    ]
+                    .map(|v| v.clamp(0., 255.)),
+                    _ => unreachable!(),
+                };
+                let found = decoded.get(x, y) * 255.;
+                for (found, expected) in [found.r(), found.g(), found.b()].iter().zip(expected) {
+                    largest = largest.max((found - expected).abs());
+                }
+            }
+        }
+        largest
+    }
+
+    fn decode(jpeg: &Jpeg) -> Image {
+        let decoded = read_jpeg(&jpeg.file()).unwrap();
+        assert!(decoded.alpha.is_none());
+        decoded.color
+    }
+
+    #[test]
+    fn grayscale() {
+        fastrand::seed(1);
+        // sizes that aren't whole blocks, with and without restarts
+        for (width, height, restart_interval) in [(19, 11, 0), (8, 8, 0), (1, 1, 0), (30, 17, 3)] {
+            let jpeg = Jpeg {
+                restart_interval,
+                ..gray(width, height)
+            };
+            let error = largest_error(&jpeg, &decode(&jpeg), false);
+            assert!(error <= 1.01, "{width} {height}: {error}");
+        }
+    }
+
+    #[test]
+    fn subsampled_color() {
+        fastrand::seed(2);
+        for restart_interval in [0, 1, 4] {
+            let jpeg = Jpeg {
+                restart_interval,
+                ..color(37, 21)
+            };
+            let error = largest_error(&jpeg, &decode(&jpeg), false);
+            // each of Y, Cb and Cr can be off by one, and blue takes 1.772
+            // times Cb, then it's rounded
+            assert!(error < 3.5, "{restart_interval}: {error}");
+        }
+    }
+
+    #[test]
+    fn quantization_tables() {
+        fastrand::seed(3);
+        // coarser steps the further along the zigzag, and one table with
+        // 16 bit values
+        let mut jpeg = color(21, 13);
+        jpeg.quant[0] = std::array::from_fn(|k| 1 + k as u16 / 8);
+        jpeg.quant[1] = std::array::from_fn(|k| 300 - 299 * u16::from(k < 32));
+        let file = jpeg.file();
+        assert!(file
+            .windows(5)
+            .any(|w| w[..2] == [0xff, 0xdb] && w[4] == 0x11));
+        let decoded = read_jpeg(&file).unwrap().color;
+        // the coefficients are each off by at most half a step, and the
+        // chroma loses most of its detail. steps applied to the wrong
+        // coefficients are far worse than that
+        let error = largest_error(&jpeg, &decoded, false);
+        assert!(error < 40., "{error}");
+    }
+
+    #[test]
+    fn separate_scans() {
+        fastrand::seed(4);
+        // on its own a subsampled plane is coded over just its own blocks,
+        // which here are fewer than the MCUs cover
+        let jpeg = Jpeg {
+            separate_scans: true,
+            restart_interval: 2,
+            ..color(21, 13)
+        };
+        let error = largest_error(&jpeg, &decode(&jpeg), false);
+        assert!(error < 3.5, "{error}");
+    }
+
+    #[test]
+    fn rgb() {
+        fastrand::seed(5);
+        let mut named = color(12, 10);
+        for (plane, id) in named.planes.iter_mut().zip(*b"RGB") {
+            plane.id = id;
+        }
+        let error = largest_error(&named, &decode(&named), true);
+        assert!(error <= 1.01, "{error}");
+
+        let adobe = Jpeg {
+            adobe_transform: Some(0),
+            ..color(12, 10)
+        };
+        let error = largest_error(&adobe, &decode(&adobe), true);
+        assert!(error <= 1.01, "{error}");
+        let ycbcr = Jpeg {
+            adobe_transform: Some(1),
+            ..color(12, 10)
+        };
+        let error = largest_error(&ycbcr, &decode(&ycbcr), false);
+        assert!(error < 3.5, "{error}");
+    }
+
+    #[test]
+    fn bad_files() {
+        fastrand::seed(6);
+        let file = Jpeg {
+            separate_scans: true,
+            restart_interval: 2,
+            ..color(21, 13)
+        }
+        .file();
+        // without its end of image marker the image is still all there
+        assert!(read_jpeg(&file[..file.len() - 2]).is_ok());
+        for end in 0..file.len() - 2 {
+            assert!(read_jpeg(&file[..end]).is_err(), "{end}");
+        }
+
+        let frame = file.windows(2).position(|w| w == [0xff, 0xc0]).unwrap();
+        let with = |at: usize, byte: u8| {
+            let mut file = file.clone();
+            file[at] = byte;
+            read_jpeg(&file)
+        };
+        let progressive = with(frame + 1, 0xc2).err().unwrap();
+        assert!(progressive.to_string().contains("progressive"));
+        // arithmetic coding, 12 bit samples, no height and CMYK
+        assert!(with(frame + 1, 0xc9).is_err());
+        assert!(with(frame + 4, 12).is_err());
+        assert!(with(frame + 6, 0).is_err());
+        assert!(with(frame + 9, 4).is_err());
+        assert!(read_jpeg(b"\x89PNG").is_err());
+    }
+}
@@ -0,0 +1,14 @@
+pub mod gltf;
+mod inflate;
+pub mod jpeg;
+pub mod json;
+pub mod ply;
+pub mod png;
+pub mod stl;
+
+pub use gltf::*;
+pub use jpeg::*;
+pub use json::*;
+pub use ply::*;
+pub use png::*;
+pub use stl::*;
@@ -0,0 +1,583 @@
+//! Stanford PLY meshes, ASCII or binary in either byte order. Vertices can
+//! carry normals, texture coordinates and colors, faces with more than three
+//! corners are split into fans.
+
+use crate::{color::Color, float::Float, shapes::TriangleMesh, tone_map::srgb_eotf, vec3::*};
+use std::{fs, io, path::Path};
+
+fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("ply: {message}"))
+}
+
+pub fn read_ply(path: impl AsRef<Path>) -> io::Result<TriangleMesh> {
+    parse_ply(&fs::read(path)?)
+}
+
+/// A PLY file already in memory. 8 and 16 bit colors are taken as sRGB and
+/// decoded, float colors are used as they are.
+pub fn parse_ply(bytes: &[u8]) -> io::Result<TriangleMesh> {
+    let (header, body) = split_header(bytes)?;
+    let mut reader = Reader {
+        data: body,
+        at: 0,
+        format: header.format,
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut triangles = Vec::new();
+    let mut row = Vec::new();
+    let mut corners = Vec::new();
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let find = |names: &[&str]| {
+                    names
+                        .iter()
+                        .find_map(|name| element.properties.iter().position(|p| p.name == *name))
+                };
+                let find3 = |names: [&[&str]; 3]| {
+                    let [a, b, c] = names.map(find);
+                    Some([a?, b?, c?])
+                };
+                let position = find3([&["x"], &["y"], &["z"]])
+                    .ok_or_else(|| error("vertices without positions"))?;
+                let normal = find3([&["nx"], &["ny"], &["nz"]]);
+                let uv = match [
+                    find(&["u", "s", "texture_u", "texture_s"]),
+                    find(&["v", "t", "texture_v", "texture_t"]),
+                ] {
+                    [Some(u), Some(v)] => Some([u, v]),
+                    _ => None,
+                };
+                let color = find3([
+                    &["red", "r", "diffuse_red"],
+                    &["green", "g", "diffuse_green"],
+                    &["blue", "b", "diffuse_blue"],
+                ]);
+
+                for _ in 0..element.count {
+                    row.clear();
+                    for property in &element.properties {
+                        // lists on vertices aren't used, they stay zero
+                        match property.list {
+                            Some(count) => {
+                                reader.skip_list(count, property.kind)?;
+                                row.push(0.);
+                            }
+                            None => row.push(reader.read(property.kind)?),
+                        }
+                    }
+                    let value = |i: usize| row[i];
+                    let [x, y, z] = position.map(value);
+                    positions.push(Pos(x as Float, y as Float, z as Float));
+                    if let Some(normal) = normal {
+                        let [x, y, z] = normal.map(value);
+                        normals.push(Vec3(x as Float, y as Float, z as Float));
+                    }
+                    if let Some([u, v]) = uv {
+                        uvs.push((value(u) as Float, value(v) as Float));
+                    }
+                    if let Some(color) = color {
+                        let [r, g, b] = color.map(|i| {
+                            let kind = element.properties[i].kind;
+                            match kind.max() {
+                                Some(max) => srgb_eotf((value(i) / max) as Float),
+                                None => value(i) as Float,
+                            }
+                        });
+                        colors.push(Color(r, g, b));
+                    }
+                }
+            }
+            "face" => {
+                let indices = element
+                    .properties
+                    .iter()
+                    .position(|p| {
+                        p.list.is_some() && (p.name == "vertex_indices" || p.name == "vertex_index")
+                    })
+                    .ok_or_else(|| error("faces without vertex indices"))?;
+                for _ in 0..element.count {
+                    for (i, property) in element.properties.iter().enumerate() {
+                        match property.list {
+                            Some(count) if i == indices => {
+                                let n = reader.read_index(count)? as usize;
+                                corners.clear();
+                                for _ in 0..n {
+                                    corners.push(reader.read_index(property.kind)?);
+                                }
+                                for k in 1..n.saturating_sub(1) {
+                                    triangles.push([corners[0], corners[k], corners[k + 1]]);
+                                }
+                            }
+                            Some(count) => reader.skip_list(count, property.kind)?,
+                            None => {
+                                reader.read(property.kind)?;
+                            }
+                        }
+                    }
+                }
+            }
+            // edges, materials and such are read past
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        match property.list {
+                            Some(count) => reader.skip_list(count, property.kind)?,
+                            None => {
+                                reader.read(property.kind)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if triangles
+        .iter()
+        .flatten()
+        .any(|&i| i as usize >= positions.len())
+    {
+        return Err(error("face index out of range"));
+    }
+    let mut mesh = TriangleMesh::new(positions, triangles);
+    if !normals.is_empty() {
+        mesh = mesh.with_normals(normals);
+    }
+    if !uvs.is_empty() {
+        mesh = mesh.with_uvs(uvs);
+    }
+    if !colors.is_empty() {
+        mesh = mesh.with_colors(colors);
+    }
+    Ok(mesh)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> io::Result<Scalar> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(error(&format!("unknown type {name}"))),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    // full brightness for colors stored as integers
+    fn max(self) -> Option<f64> {
+        match self {
+            Scalar::U8 => Some(255.),
+            Scalar::U16 => Some(65535.),
+            _ => None,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    kind: Scalar,
+    // the type of the length for list properties
+    list: Option<Scalar>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+// the parsed header and the data after it
+fn split_header(bytes: &[u8]) -> io::Result<(Header, &[u8])> {
+    const END: &[u8] = b"end_header";
+    if !bytes.starts_with(b"ply") {
+        return Err(error("not a ply file"));
+    }
+    let end = bytes
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or_else(|| error("no end_header"))?;
+    // the data starts after the line break that ends the header
+    let newline = bytes[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(|| error("no data after the header"))?;
+    let text = std::str::from_utf8(&bytes[..end]).map_err(|_| error("header isn't text"))?;
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in text.lines().skip(1) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::LittleEndian,
+                    "binary_big_endian" => Format::BigEndian,
+                    _ => return Err(error(&format!("unknown format {kind}"))),
+                })
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| error("bad element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, kind, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element"))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: Scalar::parse(kind)?,
+                    list: Some(Scalar::parse(count)?),
+                });
+            }
+            ["property", kind, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before any element"))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: Scalar::parse(kind)?,
+                    list: None,
+                });
+            }
+            ["comment" | "obj_info", ..] | [] => {}
+            _ => return Err(error(&format!("bad header line {line:?}"))),
+        }
+    }
+
+    let format = format.ok_or_else(|| error("no format"))?;
+    let header = Header { format, elements };
+    Ok((header, &bytes[end + newline + 1..]))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+    format: Format,
+}
+
+impl Reader<'_> {
+    fn read(&mut self, kind: Scalar) -> io::Result<f64> {
+        if self.format == Format::Ascii {
+            return self.read_ascii();
+        }
+        let size = kind.size();
+        let bytes = self
+            .data
+            .get(self.at..self.at + size)
+            .ok_or_else(|| error("unexpected end of data"))?;
+        self.at += size;
+        let mut b = [0u8; 8];
+        b[..size].copy_from_slice(bytes);
+        if self.format == Format::BigEndian {
+            b[..size].reverse();
+        }
+        Ok(match kind {
+            Scalar::I8 => f64::from(b[0] as i8),
+            Scalar::U8 => f64::from(b[0]),
+            Scalar::I16 => f64::from(i16::from_le_bytes([b[0], b[1]])),
+            Scalar::U16 => f64::from(u16::from_le_bytes([b[0], b[1]])),
+            Scalar::I32 => f64::from(i32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            Scalar::U32 => f64::from(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            Scalar::F32 => f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            Scalar::F64 => f64::from_le_bytes(b),
+        })
+    }
+
+    // the next number, wherever the line breaks are
+    fn read_ascii(&mut self) -> io::Result<f64> {
+        while self.data.get(self.at).is_some_and(u8::is_ascii_whitespace) {
+            self.at += 1;
+        }
+        let start = self.at;
+        while self
+            .data
+            .get(self.at)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.at += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.at])
+            .ok()
+            .and_then(|word| word.parse().ok())
+            .ok_or_else(|| error("bad or missing number"))
+    }
+
+    // a list length or vertex index, which has to be whole and not negative
+    // rather than be rounded into some other valid number
+    fn read_index(&mut self, kind: Scalar) -> io::Result<u32> {
+        let value = self.read(kind)?;
+        match value >= 0. && value.fract() == 0. && value <= f64::from(u32::MAX) {
+            true => Ok(value as u32),
+            false => Err(error(&format!("bad index or list length {value}"))),
+        }
+    }
+
+    fn skip_list(&mut self, count: Scalar, kind: Scalar) -> io::Result<()> {
+        let n = self.read_index(count)?;
+        for _ in 0..n {
+            self.read(kind)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::look_down;
+
+    fn near(a: [Float; 3], b: [Float; 3]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4)
+    }
+
+    const ASCII: &str = "ply
+format ascii 1.0
+comment a pentagon, a triangle and an edge between them
+element vertex 8
+property float x
+property float y
+property float z
+element face 2
+property uchar flags
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 0 0
+1 0 0
+1.5 1 0
+0.5 1.5 0
+-0.5 1 0
+2 0 0
+3 0 0
+2 1 0
+1 5 0 1 2 3 4
+0 3 5 6 7
+1 2
+";
+
+    #[test]
+    fn ascii() {
+        let mesh = parse_ply(ASCII.as_bytes()).unwrap();
+        // the pentagon is split into a fan of three
+        assert_eq!(mesh.triangle_count(), 4);
+        let bounds = mesh.bounding_box();
+        assert!(near(
+            [bounds.min.0, bounds.min.1, bounds.min.2],
+            [-0.5, 0., 0.]
+        ));
+        assert!(near(
+            [bounds.max.0, bounds.max.1, bounds.max.2],
+            [3., 1.5, 0.]
+        ));
+        // every part of the pentagon is covered, facing up
+        for (x, y) in [(0.5, 0.2), (1.2, 0.8), (0.5, 1.3), (-0.2, 0.9), (0.5, 0.8)] {
+            let seen = look_down(&mesh, x, y).unwrap();
+            assert!(seen.front_face, "{x} {y}");
+            assert!(near([seen.pos.0, seen.pos.1, seen.pos.2], [x, y, 0.]));
+            assert!(near(
+                [seen.normal.0, seen.normal.1, seen.normal.2],
+                [0., 0., 1.]
+            ));
+        }
+        assert!(look_down(&mesh, 2.2, 0.2).unwrap().front_face);
+        assert!(look_down(&mesh, 1.5, 0.2).is_none());
+        // without colors in the file, vertices are white
+        let color = look_down(&mesh, 0.5, 0.5).unwrap().color;
+        assert!(near([color.0, color.1, color.2], [1., 1., 1.]));
+    }
+
+    // one triangle with every kind of vertex data, in a mix of types, and
+    // properties that are read past
+    fn binary(format: Format) -> Vec<u8> {
+        let name = match format {
+            Format::LittleEndian => "binary_little_endian",
+            _ => "binary_big_endian",
+        };
+        let mut file = format!(
+            "ply
+format {name} 1.0
+element vertex 3
+property double x
+property double y
+property double z
+property float nx
+property float ny
+property float nz
+property uchar red
+property ushort green
+property float blue
+property float s
+property float t
+property int confidence
+property list uchar short junk
+element face 1
+property list uchar uint vertex_indices
+property uchar flags
+end_header
+"
+        )
+        .into_bytes();
+        let mut put = |le: &[u8], be: &[u8]| match format {
+            Format::LittleEndian => file.extend_from_slice(le),
+            _ => file.extend_from_slice(be),
+        };
+        for ([x, y, z], [s, t]) in [
+            ([0., 0., 0.], [0., 0.]),
+            ([1., 0., 0.], [1., 0.]),
+            ([0., 1., 0.], [0., 1.]),
+        ] {
+            for c in [x, y, z] {
+                put(&f64::to_le_bytes(c), &f64::to_be_bytes(c));
+            }
+            for n in [0., 0.6, 0.8f32] {
+                put(&n.to_le_bytes(), &n.to_be_bytes());
+            }
+            put(&[255], &[255]);
+            put(&0u16.to_le_bytes(), &0u16.to_be_bytes());
+            put(&0.5f32.to_le_bytes(), &0.5f32.to_be_bytes());
+            for c in [s, t] {
+                put(&f32::to_le_bytes(c), &f32::to_be_bytes(c));
+            }
+            put(&(-7i32).to_le_bytes(), &(-7i32).to_be_bytes());
+            put(&[2], &[2]);
+            for junk in [300i16, -300] {
+                put(&junk.to_le_bytes(), &junk.to_be_bytes());
+            }
+        }
+        put(&[3], &[3]);
+        for i in [0u32, 1, 2] {
+            put(&i.to_le_bytes(), &i.to_be_bytes());
+        }
+        put(&[9], &[9]);
+        file
+    }
+
+    #[test]
+    fn binary_both_byte_orders() {
+        for format in [Format::LittleEndian, Format::BigEndian] {
+            let mesh = parse_ply(&binary(format)).unwrap();
+            assert_eq!(mesh.triangle_count(), 1);
+            let seen = look_down(&mesh, 0.25, 0.25).unwrap();
+            assert!(seen.front_face);
+            assert!(near([seen.pos.0, seen.pos.1, seen.pos.2], [0.25, 0.25, 0.]));
+            // the file's normals, not the flat one
+            assert!(near(
+                [seen.normal.0, seen.normal.1, seen.normal.2],
+                [0., 0.6, 0.8]
+            ));
+            assert!(near([seen.uv.0, seen.uv.1, 0.], [0.25, 0.25, 0.]));
+            // integers are sRGB, floats are linear
+            assert!(near(
+                [seen.color.0, seen.color.1, seen.color.2],
+                [1., 0., 0.5]
+            ));
+        }
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let file = binary(Format::BigEndian);
+        for end in 0..file.len() {
+            assert!(parse_ply(&file[..end]).is_err(), "{end}");
+        }
+    }
+
+    #[test]
+    fn bad_faces() {
+        let header = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+0 1 0
+";
+        assert!(parse_ply(format!("{header}3 0 1 2").as_bytes()).is_ok());
+        for face in [
+            "3 0 1 -1",
+            "3 0 1 1.5",
+            "3 0 1 3",
+            "3 0 1 4294967296",
+            "-3 0 1 2",
+            "2.5 0 1 2",
+            "3 0 1",
+            "3 0 1 x",
+        ] {
+            assert!(
+                parse_ply(format!("{header}{face}").as_bytes()).is_err(),
+                "{face}"
+            );
+        }
+    }
+
+    #[test]
+    fn bad_headers() {
+        for file in [
+            "",
+            "plx\nformat ascii 1.0\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex 0\n",
+            "ply\nformat ascii 1.0\nend_header",
+            "ply\nelement vertex 0\nend_header\n",
+            "ply\nformat utf8 1.0\nend_header\n",
+            "ply\nformat ascii 1.0\nproperty float x\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex many\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nend_header\n0 0\n",
+            "ply\nformat ascii 1.0\nelement face 0\nproperty uchar flags\nend_header\n",
+            "ply\nformat ascii 1.0\nsomething else\nend_header\n",
+        ] {
+            assert!(parse_ply(file.as_bytes()).is_err(), "{file:?}");
+        }
+    }
+}
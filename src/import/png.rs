@@ -0,0 +1,644 @@
+use super::inflate::zlib_decompress;
+use crate::{color::Color, float::Float, image::Image};
+use std::io;
+
+/// An image decoded from a file, with values as stored scaled to [0, 1].
+/// Colors usually still need decoding from sRGB.
+#[derive(Clone)]
+pub struct DecodedImage {
+    pub color: Image,
+    // opacity in every channel, when the file has any
+    pub alpha: Option<Image>,
+}
+
+fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("png: {message}"))
+}
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// bigger images are refused, so a broken or hostile header can't ask for
+// more memory than there is
+const MAX_PIXELS: usize = 1 << 26;
+
+pub fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(&SIGNATURE)
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            0 | 3 => 1,
+            4 => 2,
+            2 => 3,
+            _ => 4,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    // bytes in a filtered row of the given width, without its filter byte
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    // the whole image, or the seven passes of Adam7 interlacing, each as
+    // first pixel and step in x and y
+    fn passes(&self) -> &'static [(usize, usize, usize, usize)] {
+        match self.interlaced {
+            false => &[(0, 0, 1, 1)],
+            true => &[
+                (0, 0, 8, 8),
+                (4, 0, 8, 8),
+                (0, 4, 4, 8),
+                (2, 0, 4, 4),
+                (0, 2, 2, 4),
+                (1, 0, 2, 2),
+                (0, 1, 1, 2),
+            ],
+        }
+    }
+
+    // width and height of a pass
+    fn pass_size(&self, (x0, y0, dx, dy): (usize, usize, usize, usize)) -> (usize, usize) {
+        (
+            (self.width + dx - 1 - x0) / dx,
+            (self.height + dy - 1 - y0) / dy,
+        )
+    }
+
+    // bytes of filtered data in all passes, a filter byte and a row each
+    fn data_size(&self) -> usize {
+        self.passes()
+            .iter()
+            .map(|&pass| match self.pass_size(pass) {
+                (0, _) | (_, 0) => 0,
+                (width, height) => height * (1 + self.row_bytes(width)),
+            })
+            .sum()
+    }
+}
+
+/// PNG of any color type and bit depth, interlaced or not
+pub fn read_png(bytes: &[u8]) -> io::Result<DecodedImage> {
+    if !is_png(bytes) {
+        return Err(error("not a png file"));
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut at = SIGNATURE.len();
+    while at + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        let kind = &bytes[at + 4..at + 8];
+        let data = bytes
+            .get(at + 8..at + 8 + length)
+            .ok_or_else(|| error("truncated chunk"))?;
+        // and the crc, which isn't checked
+        at += 12 + length;
+
+        match kind {
+            b"IHDR" => {
+                if data.len() < 13 {
+                    return Err(error("short header"));
+                }
+                let read = |i: usize| u32::from_be_bytes(data[i..i + 4].try_into().unwrap());
+                header = Some(Header {
+                    width: read(0) as usize,
+                    height: read(4) as usize,
+                    bit_depth: data[8],
+                    color_type: data[9],
+                    interlaced: data[12] == 1,
+                });
+            }
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or_else(|| error("no header"))?;
+    let valid_depth = match header.color_type {
+        0 => matches!(header.bit_depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(header.bit_depth, 1 | 2 | 4 | 8),
+        2 | 4 | 6 => matches!(header.bit_depth, 8 | 16),
+        _ => false,
+    };
+    if !valid_depth {
+        return Err(error("bad color type or bit depth"));
+    }
+    if header.width == 0 || header.height == 0 {
+        return Err(error("empty image"));
+    }
+    if header
+        .width
+        .checked_mul(header.height)
+        .is_none_or(|pixels| pixels > MAX_PIXELS)
+    {
+        return Err(error("image too large"));
+    }
+
+    // checked before the image is allocated, the header alone proves nothing
+    let data_size = header.data_size();
+    let raw = zlib_decompress(&compressed, data_size)?;
+    if raw.len() < data_size {
+        return Err(error("image data too short"));
+    }
+    let mut color = Image::new(header.width, header.height);
+    let mut alpha = Image::new(header.width, header.height);
+    let mut has_alpha = header.color_type == 4 || header.color_type == 6;
+    let max = ((1u32 << header.bit_depth) - 1) as Float;
+
+    // the samples of a pixel as color and opacity
+    let to_color = |samples: &[u32]| -> (Color, Float) {
+        match header.color_type {
+            0 => {
+                let transparent = transparency.len() >= 2
+                    && samples[0]
+                        == u32::from(u16::from_be_bytes([transparency[0], transparency[1]]));
+                let v = samples[0] as Float / max;
+                (Color(v, v, v), if transparent { 0. } else { 1. })
+            }
+            2 => {
+                let key = |i: usize| {
+                    transparency
+                        .get(2 * i..2 * i + 2)
+                        .map(|b| u32::from(u16::from_be_bytes([b[0], b[1]])))
+                };
+                let transparent = (0..3).all(|i| key(i) == Some(samples[i]));
+                let c = Color(
+                    samples[0] as Float,
+                    samples[1] as Float,
+                    samples[2] as Float,
+                ) / max;
+                (c, if transparent { 0. } else { 1. })
+            }
+            3 => {
+                let i = samples[0] as usize;
+                let rgb = palette.get(3 * i..3 * i + 3).unwrap_or(&[0, 0, 0]);
+                let c = Color(rgb[0] as Float, rgb[1] as Float, rgb[2] as Float) / 255.;
+                let a = transparency.get(i).map_or(1., |&a| a as Float / 255.);
+                (c, a)
+            }
+            4 => {
+                let v = samples[0] as Float / max;
+                (Color(v, v, v), samples[1] as Float / max)
+            }
+            _ => {
+                let c = Color(
+                    samples[0] as Float,
+                    samples[1] as Float,
+                    samples[2] as Float,
+                ) / max;
+                (c, samples[3] as Float / max)
+            }
+        }
+    };
+
+    let bytes_per_pixel = header.bits_per_pixel().div_ceil(8);
+    let mut at = 0;
+    for &(x0, y0, dx, dy) in header.passes() {
+        let (width, height) = header.pass_size((x0, y0, dx, dy));
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let row_bytes = header.row_bytes(width);
+        let mut previous = vec![0u8; row_bytes];
+        for row in 0..height {
+            let filter = *raw.get(at).ok_or_else(|| error("image data too short"))?;
+            let mut line = raw
+                .get(at + 1..at + 1 + row_bytes)
+                .ok_or_else(|| error("image data too short"))?
+                .to_vec();
+            at += 1 + row_bytes;
+            unfilter(filter, &mut line, &previous, bytes_per_pixel)?;
+
+            let mut samples = [0u32; 4];
+            for column in 0..width {
+                for (channel, sample) in samples[..header.channels()].iter_mut().enumerate() {
+                    *sample = read_sample(
+                        &line,
+                        column * header.channels() + channel,
+                        header.bit_depth,
+                    );
+                }
+                let (c, a) = to_color(&samples[..header.channels()]);
+                let (x, y) = (x0 + column * dx, y0 + row * dy);
+                color.set(x, y, c);
+                alpha.set(x, y, Color(a, a, a));
+                has_alpha |= a < 1.;
+            }
+            previous = line;
+        }
+    }
+
+    Ok(DecodedImage {
+        color,
+        alpha: has_alpha.then_some(alpha),
+    })
+}
+
+// sample number i of an unfiltered row
+fn read_sample(line: &[u8], i: usize, bit_depth: u8) -> u32 {
+    match bit_depth {
+        16 => u32::from(u16::from_be_bytes([line[2 * i], line[2 * i + 1]])),
+        8 => u32::from(line[i]),
+        _ => {
+            // packed from the most significant bits
+            let bits = bit_depth as usize;
+            let per_byte = 8 / bits;
+            let byte = line[i / per_byte];
+            let shift = 8 - bits * (i % per_byte + 1);
+            u32::from((byte >> shift) & ((1u16 << bits) - 1) as u8)
+        }
+    }
+}
+
+fn unfilter(filter: u8, line: &mut [u8], previous: &[u8], bpp: usize) -> io::Result<()> {
+    for i in 0..line.len() {
+        let left = if i >= bpp { line[i - bpp] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(error("bad filter type")),
+        };
+        line[i] = line[i].wrapping_add(predicted);
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSES: [(usize, usize, usize, usize); 7] = [
+        (0, 0, 8, 8),
+        (4, 0, 8, 8),
+        (0, 4, 4, 8),
+        (2, 0, 4, 4),
+        (0, 2, 2, 4),
+        (1, 0, 2, 2),
+        (0, 1, 1, 2),
+    ];
+
+    fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        out.extend((data.len() as u32).to_be_bytes());
+        out.extend(kind);
+        out.extend(data);
+        // the crc isn't checked
+        out.extend([0; 4]);
+    }
+
+    // the filtered image data in stored deflate blocks, so no compressor is
+    // needed
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x78, 0x01];
+        let blocks: Vec<_> = data.chunks(0xffff).collect();
+        for (i, block) in blocks.iter().enumerate() {
+            let length = block.len() as u16;
+            out.push((i + 1 == blocks.len()) as u8);
+            out.extend(length.to_le_bytes());
+            out.extend((!length).to_le_bytes());
+            out.extend(*block);
+        }
+        if blocks.is_empty() {
+            out.extend([1, 0, 0, 0xff, 0xff]);
+        }
+        out.extend([0; 4]);
+        out
+    }
+
+    struct Png<'a> {
+        width: u32,
+        height: u32,
+        bit_depth: u8,
+        color_type: u8,
+        interlaced: bool,
+        chunks: Vec<(&'a [u8; 4], &'a [u8])>,
+    }
+
+    impl Png<'_> {
+        fn new(width: u32, height: u32, bit_depth: u8, color_type: u8) -> Self {
+            Png {
+                width,
+                height,
+                bit_depth,
+                color_type,
+                interlaced: false,
+                chunks: Vec::new(),
+            }
+        }
+
+        fn file(&self, data: &[u8]) -> Vec<u8> {
+            let mut out = SIGNATURE.to_vec();
+            let mut header = Vec::new();
+            header.extend(self.width.to_be_bytes());
+            header.extend(self.height.to_be_bytes());
+            header.extend([self.bit_depth, self.color_type, 0, 0, self.interlaced as u8]);
+            chunk(&mut out, b"IHDR", &header);
+            for (kind, data) in &self.chunks {
+                chunk(&mut out, kind, data);
+            }
+            chunk(&mut out, b"IDAT", &zlib(data));
+            chunk(&mut out, b"IEND", &[]);
+            out
+        }
+    }
+
+    fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+        let p = i32::from(a) + i32::from(b) - i32::from(c);
+        let distances = [a, b, c].map(|x| (p - i32::from(x)).abs());
+        match distances {
+            [pa, pb, pc] if pa <= pb && pa <= pc => a,
+            [_, pb, pc] if pb <= pc => b,
+            _ => c,
+        }
+    }
+
+    // a row with its filter byte in front
+    fn filter(kind: u8, row: &[u8], previous: &[u8], bpp: usize) -> Vec<u8> {
+        let mut out = vec![kind];
+        for i in 0..row.len() {
+            let left = if i >= bpp { row[i - bpp] } else { 0 };
+            let up = previous.get(i).copied().unwrap_or(0);
+            let up_left = if i >= bpp {
+                previous.get(i - bpp).copied().unwrap_or(0)
+            } else {
+                0
+            };
+            let predicted = match kind {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                _ => paeth_predictor(left, up, up_left),
+            };
+            out.push(row[i].wrapping_sub(predicted));
+        }
+        out
+    }
+
+    // rows of packed samples filtered one after the other, picking each row's
+    // filter by its index
+    fn filter_rows(rows: &[Vec<u8>], bpp: usize, kind: impl Fn(usize) -> u8) -> Vec<u8> {
+        let mut previous = Vec::new();
+        let mut out = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            out.extend(filter(kind(y), row, &previous, bpp));
+            previous = row.clone();
+        }
+        out
+    }
+
+    // samples of bit_depth bits packed from the most significant end
+    fn pack(samples: &[u8], bit_depth: u8) -> Vec<u8> {
+        let per_byte = 8 / bit_depth as usize;
+        samples
+            .chunks(per_byte)
+            .map(|group| {
+                group.iter().enumerate().fold(0, |byte, (i, &sample)| {
+                    byte | sample << (8 - bit_depth as usize * (i + 1))
+                })
+            })
+            .collect()
+    }
+
+    // the image data of a grid of samples, interlaced or not
+    fn image_data(
+        samples: &[Vec<Vec<u8>>],
+        bit_depth: u8,
+        bpp: usize,
+        interlaced: bool,
+        kind: impl Fn(usize) -> u8,
+    ) -> Vec<u8> {
+        let (width, height) = (samples[0].len(), samples.len());
+        let row = |y: usize, xs: &mut dyn Iterator<Item = usize>| {
+            let flat: Vec<u8> = xs.flat_map(|x| samples[y][x].clone()).collect();
+            pack(&flat, bit_depth)
+        };
+        if !interlaced {
+            let rows: Vec<_> = (0..height).map(|y| row(y, &mut (0..width))).collect();
+            return filter_rows(&rows, bpp, kind);
+        }
+        let mut out = Vec::new();
+        for (x0, y0, dx, dy) in PASSES {
+            if x0 >= width || y0 >= height {
+                continue;
+            }
+            let rows: Vec<_> = (y0..height)
+                .step_by(dy)
+                .map(|y| row(y, &mut (x0..width).step_by(dx)))
+                .collect();
+            out.extend(filter_rows(&rows, bpp, &kind));
+        }
+        out
+    }
+
+    fn rgb_samples(width: usize, height: usize) -> Vec<Vec<Vec<u8>>> {
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        (0..3)
+                            .map(|c| ((x * 53 + y * 31 + c * 97) % 256) as u8)
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn assert_rgb(image: &DecodedImage, samples: &[Vec<Vec<u8>>]) {
+        for (y, row) in samples.iter().enumerate() {
+            for (x, s) in row.iter().enumerate() {
+                let expected = Color(s[0] as Float, s[1] as Float, s[2] as Float) / 255.;
+                assert_eq!(image.color.get(x, y), expected, "pixel {x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn each_filter_type() {
+        let samples = rgb_samples(5, 4);
+        for kind in 0..5 {
+            let data = image_data(&samples, 8, 3, false, |_| kind);
+            let image = read_png(&Png::new(5, 4, 8, 2).file(&data)).unwrap();
+            assert_rgb(&image, &samples);
+            assert!(image.alpha.is_none());
+        }
+
+        // and a different one on every row
+        let samples = rgb_samples(7, 10);
+        let data = image_data(&samples, 8, 3, false, |y| (y % 5) as u8);
+        assert_rgb(
+            &read_png(&Png::new(7, 10, 8, 2).file(&data)).unwrap(),
+            &samples,
+        );
+    }
+
+    #[test]
+    fn adam7() {
+        // odd sizes, so some passes are narrower than others or empty
+        for (width, height) in [(10, 9), (1, 1), (3, 2), (17, 13)] {
+            let samples = rgb_samples(width, height);
+            let data = image_data(&samples, 8, 3, true, |y| (y % 5) as u8);
+            let mut png = Png::new(width as u32, height as u32, 8, 2);
+            png.interlaced = true;
+            assert_rgb(&read_png(&png.file(&data)).unwrap(), &samples);
+        }
+    }
+
+    #[test]
+    fn palette_and_transparency() {
+        let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        // the last two entries have no alpha, so are opaque
+        let alphas = [0, 128];
+        let (width, height) = (7, 5);
+        let samples: Vec<Vec<Vec<u8>>> = (0..height)
+            .map(|y| (0..width).map(|x| vec![((x + 2 * y) % 4) as u8]).collect())
+            .collect();
+
+        for interlaced in [false, true] {
+            let data = image_data(&samples, 2, 1, interlaced, |y| (y % 5) as u8);
+            let mut png = Png::new(width as u32, height as u32, 2, 3);
+            png.interlaced = interlaced;
+            png.chunks = vec![(b"PLTE", &palette), (b"tRNS", &alphas)];
+            let image = read_png(&png.file(&data)).unwrap();
+            let alpha = image.alpha.as_ref().expect("the palette has alpha");
+
+            for (y, row) in samples.iter().enumerate() {
+                for (x, s) in row.iter().enumerate() {
+                    let i = s[0] as usize;
+                    let rgb = &palette[3 * i..3 * i + 3];
+                    let color = Color(rgb[0] as Float, rgb[1] as Float, rgb[2] as Float) / 255.;
+                    let a = alphas.get(i).map_or(1., |&a| a as Float / 255.);
+                    assert_eq!(image.color.get(x, y), color);
+                    assert_eq!(alpha.get(x, y), Color(a, a, a));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gray_with_transparent_key() {
+        // 16 bits, with 0x1234 as the transparent value
+        let values: [u16; 4] = [0, 0x1234, 0xffff, 0x8000];
+        let samples = vec![values.iter().map(|v| v.to_be_bytes().to_vec()).collect()];
+        let data = image_data(&samples, 8, 2, false, |_| 4);
+        let mut png = Png::new(4, 1, 16, 0);
+        png.chunks = vec![(b"tRNS", &[0x12, 0x34])];
+        let image = read_png(&png.file(&data)).unwrap();
+        let alpha = image.alpha.expect("a pixel matches the key");
+
+        for (x, &v) in values.iter().enumerate() {
+            let gray = v as Float / 65535.;
+            let a = if v == 0x1234 { 0. } else { 1. };
+            assert_eq!(image.color.get(x, 0), Color(gray, gray, gray));
+            assert_eq!(alpha.get(x, 0), Color(a, a, a));
+        }
+    }
+
+    #[test]
+    fn gray_and_alpha() {
+        let samples = vec![vec![vec![0, 255], vec![255, 51], vec![102, 0]]];
+        let data = image_data(&samples, 8, 2, false, |_| 1);
+        let image = read_png(&Png::new(3, 1, 8, 4).file(&data)).unwrap();
+        let alpha = image.alpha.unwrap();
+        for (x, s) in samples[0].iter().enumerate() {
+            let (v, a) = (s[0] as Float / 255., s[1] as Float / 255.);
+            assert_eq!(image.color.get(x, 0), Color(v, v, v));
+            assert_eq!(alpha.get(x, 0), Color(a, a, a));
+        }
+    }
+
+    #[test]
+    fn huge_header() {
+        // would be gigabytes of pixels, refused before anything is allocated
+        let png = Png::new(0x7fff_ffff, 0x7fff_ffff, 8, 2).file(&[0; 16]);
+        assert!(read_png(&png).is_err());
+        let png = Png::new(1 << 16, 1 << 16, 1, 0).file(&[0; 16]);
+        assert!(read_png(&png).is_err());
+    }
+
+    #[test]
+    fn wrong_amount_of_data() {
+        let samples = rgb_samples(4, 4);
+        let data = image_data(&samples, 8, 3, false, |_| 0);
+        let png = Png::new(4, 4, 8, 2);
+        assert!(read_png(&png.file(&data)).is_ok());
+        assert!(read_png(&png.file(&data[..data.len() - 1])).is_err());
+        assert!(read_png(&png.file(&[&data[..], &[0]].concat())).is_err());
+        assert!(read_png(&png.file(&[])).is_err());
+    }
+
+    #[test]
+    fn truncated_file() {
+        let samples = rgb_samples(4, 4);
+        let file = Png::new(4, 4, 8, 2).file(&image_data(&samples, 8, 3, false, |_| 0));
+        // the crc of the data and the IEND chunk after it take the last 16
+        // bytes, cutting into those loses nothing
+        for end in 0..file.len() - 16 {
+            assert!(read_png(&file[..end]).is_err(), "cut at {end}");
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let samples = rgb_samples(2, 2);
+        let mut data = image_data(&samples, 8, 3, false, |_| 0);
+        let png = Png::new(2, 2, 8, 2);
+
+        data[0] = 5;
+        assert!(read_png(&png.file(&data)).is_err(), "bad filter type");
+        data[0] = 0;
+        assert!(
+            read_png(&Png::new(2, 2, 4, 2).file(&data)).is_err(),
+            "bad bit depth"
+        );
+        assert!(
+            read_png(&Png::new(2, 2, 8, 7).file(&data)).is_err(),
+            "bad color type"
+        );
+        assert!(
+            read_png(&Png::new(0, 2, 8, 2).file(&[])).is_err(),
+            "empty image"
+        );
+        assert!(read_png(b"GIF89a").is_err(), "not a png");
+        assert!(read_png(&SIGNATURE).is_err(), "no header");
+    }
+}
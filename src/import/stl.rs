@@ -0,0 +1,207 @@
+//! STL meshes, binary or ASCII. STL lists every triangle with its own
+//! corners, so corners at the same spot are merged into shared vertices.
+//! The facet normals are ignored, triangles face the way their corners wind.
+
+use crate::{float::Float, shapes::TriangleMesh, vec3::Pos};
+use std::{collections::HashMap, fs, io, path::Path};
+
+fn error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("stl: {message}"))
+}
+
+pub fn read_stl(path: impl AsRef<Path>) -> io::Result<TriangleMesh> {
+    parse_stl(&fs::read(path)?)
+}
+
+pub fn parse_stl(bytes: &[u8]) -> io::Result<TriangleMesh> {
+    // binary files may start with "solid" too, their size gives them away
+    let binary_size = bytes
+        .get(80..84)
+        .map(|b| 84 + 50 * u32::from_le_bytes(b.try_into().unwrap()) as usize);
+    let corners = match binary_size == Some(bytes.len()) || !bytes.starts_with(b"solid") {
+        true => binary_corners(bytes)?,
+        false => ascii_corners(bytes)?,
+    };
+
+    let mut vertices = Vertices::default();
+    let triangles = corners
+        .chunks_exact(3)
+        .map(|t| {
+            [
+                vertices.index(t[0]),
+                vertices.index(t[1]),
+                vertices.index(t[2]),
+            ]
+        })
+        .collect();
+    Ok(TriangleMesh::new(vertices.positions, triangles))
+}
+
+// every three corners make a triangle
+fn binary_corners(bytes: &[u8]) -> io::Result<Vec<[f32; 3]>> {
+    let count = bytes
+        .get(80..84)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+        .ok_or_else(|| error("truncated header"))?;
+    let facets = bytes
+        .get(84..)
+        .filter(|facets| facets.len() / 50 >= count)
+        .ok_or_else(|| error("fewer triangles than the header says"))?;
+
+    let float = |b: &[u8]| f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    let mut corners = Vec::with_capacity(3 * count);
+    // a normal, three corners and two attribute bytes each
+    for facet in facets.chunks_exact(50).take(count) {
+        for corner in facet[12..48].chunks_exact(12) {
+            corners.push([
+                float(&corner[0..]),
+                float(&corner[4..]),
+                float(&corner[8..]),
+            ]);
+        }
+    }
+    Ok(corners)
+}
+
+fn ascii_corners(bytes: &[u8]) -> io::Result<Vec<[f32; 3]>> {
+    let text = std::str::from_utf8(bytes).map_err(|_| error("not text"))?;
+    let mut corners = Vec::new();
+    let mut words = text.split_whitespace();
+    while let Some(word) = words.next() {
+        if word != "vertex" {
+            continue;
+        }
+        let mut coordinate = || {
+            words
+                .next()
+                .and_then(|w| w.parse().ok())
+                .ok_or_else(|| error("bad vertex"))
+        };
+        corners.push([coordinate()?, coordinate()?, coordinate()?]);
+    }
+    if corners.len() % 3 != 0 {
+        return Err(error("a facet doesn't have three vertices"));
+    }
+    Ok(corners)
+}
+
+// positions seen so far, looked up by their exact bits
+#[derive(Default)]
+struct Vertices {
+    positions: Vec<Pos>,
+    indices: HashMap<[u32; 3], u32>,
+}
+
+impl Vertices {
+    fn index(&mut self, corner: [f32; 3]) -> u32 {
+        // 0 and -0 are the same place
+        let key = corner.map(|x| (x + 0.).to_bits());
+        *self.indices.entry(key).or_insert_with(|| {
+            let [x, y, z] = corner.map(|x| x as Float);
+            self.positions.push(Pos(x, y, z));
+            (self.positions.len() - 1) as u32
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::look_down;
+
+    // two triangles making the unit square, facing up
+    const SQUARE: [[[f32; 3]; 3]; 2] = [
+        [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.]],
+        [[0., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+    ];
+
+    // whether a ray going straight down onto (x, y) hits the front
+    fn front_from_above(mesh: &TriangleMesh, x: Float, y: Float) -> Option<bool> {
+        look_down(mesh, x, y).map(|seen| seen.front_face)
+    }
+
+    fn binary(header: &[u8; 80], triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut file = header.to_vec();
+        file.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            // a normal that's ignored, pointing the wrong way
+            for x in [0., 0., -1f32] {
+                file.extend_from_slice(&x.to_le_bytes());
+            }
+            for x in triangle.iter().flatten() {
+                file.extend_from_slice(&x.to_le_bytes());
+            }
+            file.extend_from_slice(&[0, 0]);
+        }
+        file
+    }
+
+    fn check_square(mesh: &TriangleMesh) {
+        assert_eq!(mesh.triangle_count(), 2);
+        // the shared corners are merged
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(front_from_above(mesh, 0.7, 0.2), Some(true));
+        assert_eq!(front_from_above(mesh, 0.2, 0.7), Some(true));
+        assert_eq!(front_from_above(mesh, 1.2, 0.5), None);
+    }
+
+    #[test]
+    fn ascii() {
+        let file = "solid square
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 -0
+      vertex 1e0 1 0
+      vertex 0 1.0 0
+    endloop
+  endfacet
+endsolid square
+";
+        check_square(&parse_stl(file.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn binary_files() {
+        check_square(&parse_stl(&binary(&[0; 80], &SQUARE)).unwrap());
+        // a header starting with "solid" doesn't make it ascii
+        let mut header = [b' '; 80];
+        header[..12].copy_from_slice(b"solid square");
+        check_square(&parse_stl(&binary(&header, &SQUARE)).unwrap());
+    }
+
+    #[test]
+    fn empty_binary() {
+        let mesh = parse_stl(&binary(&[0; 80], &[])).unwrap();
+        assert_eq!(mesh.triangle_count(), 0);
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let file = binary(&[0; 80], &SQUARE);
+        for end in 0..file.len() {
+            assert!(parse_stl(&file[..end]).is_err(), "{end}");
+        }
+    }
+
+    #[test]
+    fn bad_ascii() {
+        for file in [
+            "solid x\nfacet\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\nendfacet\nendsolid x\n",
+            "solid x\nfacet\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 1 1\nendloop\nendsolid\n",
+            "solid x\nfacet\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 1 one 0\nendloop\nendsolid\n",
+            "solid x\nvertex 0 0 0\nvertex 1 0",
+        ] {
+            assert!(parse_stl(file.as_bytes()).is_err(), "{file:?}");
+        }
+        let mut not_text = b"solid x\nvertex 0 0 0\n".to_vec();
+        not_text.push(0xff);
+        assert!(parse_stl(&not_text).is_err());
+    }
+}
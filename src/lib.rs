@@ -0,0 +1,21 @@
+// constants are written out to f64 precision
+#![cfg_attr(feature = "f32", allow(clippy::excessive_precision))]
+
+pub mod aabb;
+pub mod camera;
+pub mod color;
+pub mod denoise;
+pub mod float;
+pub mod hit;
+pub mod image;
+pub mod import;
+pub mod materials;
+pub mod medium;
+pub mod ray;
+pub mod sampler;
+pub mod scenes;
+pub mod shapes;
+pub mod texture;
+pub mod tone_map;
+pub mod transform;
+pub mod vec3;
@@ -0,0 +1,72 @@
+use super::*;
+use crate::{color::Color, float::Float};
+
+/// Complex index of refraction eta + i k of a metal, per color channel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComplexIor {
+    pub eta: Color,
+    pub k: Color,
+}
+
+// measured spectra averaged over the red, green and blue ranges
+impl ComplexIor {
+    pub const GOLD: ComplexIor = ComplexIor {
+        eta: Color(0.143, 0.374, 1.442),
+        k: Color(3.983, 2.385, 1.603),
+    };
+    pub const COPPER: ComplexIor = ComplexIor {
+        eta: Color(0.200, 0.924, 1.102),
+        k: Color(3.912, 2.452, 2.142),
+    };
+    pub const ALUMINIUM: ComplexIor = ComplexIor {
+        eta: Color(1.657, 0.880, 0.521),
+        k: Color(9.224, 6.270, 4.837),
+    };
+    pub const SILVER: ComplexIor = ComplexIor {
+        eta: Color(0.155, 0.117, 0.138),
+        k: Color(4.828, 3.122, 2.147),
+    };
+}
+
+/// A metal with microfacet roughness. Unlike `Metal` the color comes from
+/// the Fresnel equations and nothing is lost to rays fuzzed into the
+/// surface, only to shadowing between the microfacets.
+pub struct Conductor {
+    pub ior: ComplexIor,
+    pub roughness: Float,
+}
+
+impl Conductor {
+    pub fn new(ior: ComplexIor, roughness: Float) -> Self {
+        Self { ior, roughness }
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        // relative to whatever the metal is in, usually air
+        let outside = path
+            .media
+            .current()
+            .map_or(1., |medium| medium.ior_at(path.wavelength));
+        let (eta, k) = (self.ior.eta / outside, self.ior.k / outside);
+
+        // with visible normals sampled, f cos / pdf comes down to F G2 / G1
+        let wo = -ray.dir.unit_vec();
+        let distribution = TrowbridgeReitz::from_roughness(self.roughness);
+        let (dir, cos_h, shadowing) =
+            distribution.sample_reflection(&hit_info.normal, &wo, sampler.get_2d())?;
+        let attenuation = fresnel_conductor(cos_h, &eta, &k) * shadowing;
+        Some((hit_info.spawn_ray(dir, ray.time), attenuation))
+    }
+
+    fn albedo(&self, _hit_info: &HitInfo) -> Color {
+        fresnel_conductor(1., &self.ior.eta, &self.ior.k)
+    }
+}
@@ -1,42 +1,149 @@
-use super::Material;
-use crate::{color::*, hit::HitInfo, ray::*};
+use super::*;
+use crate::{color::*, float::Float};
 
+/// Glass, water and other clear materials: reflects or refracts, frosted
+/// when rough
 pub struct Dialectric {
-    pub refraction_index: f64,
+    pub ior: Ior,
+    // perceptual microfacet roughness in [0, 1], 0 is smooth
+    pub roughness: Float,
+    // Beer-Lambert absorption coefficients inside, per unit of distance
+    pub absorption: Color,
+    // where objects overlap, the path is inside the one with the highest priority
+    pub priority: u32,
+}
+
+impl Dialectric {
+    /// Clear, with the same index of refraction for every wavelength
+    pub fn new(refraction_index: Float) -> Self {
+        Self {
+            ior: Ior::Constant(refraction_index),
+            roughness: 0.,
+            absorption: Color(0., 0., 0.),
+            priority: 0,
+        }
+    }
+
+    /// A wavelength dependent index splits white light into colors
+    pub fn with_ior(self, ior: Ior) -> Self {
+        Self { ior, ..self }
+    }
+
+    pub fn with_roughness(self, roughness: Float) -> Self {
+        Self { roughness, ..self }
+    }
+
+    pub fn with_absorption(self, absorption: Color) -> Self {
+        Self { absorption, ..self }
+    }
+
+    /// Absorbs so that white light has turned into `color` after travelling
+    /// `distance` inside, which is easier to pick than coefficients
+    pub fn with_transmission_color(self, color: Color, distance: Float) -> Self {
+        let coefficient = |c: Float| -c.max(Float::MIN_POSITIVE).ln() / distance;
+        self.with_absorption(Color(
+            coefficient(color.r()),
+            coefficient(color.g()),
+            coefficient(color.b()),
+        ))
+    }
+
+    pub fn with_priority(self, priority: u32) -> Self {
+        Self { priority, ..self }
+    }
+
+    fn medium(&self) -> Medium {
+        Medium {
+            id: self as *const Self as usize,
+            priority: self.priority,
+            ior: self.ior,
+            absorption: self.absorption,
+        }
+    }
 }
 
 impl Material for Dialectric {
-    fn scatter(&self, ray: &Ray, hit_info: &HitInfo) -> Option<(Ray, Color)> {
-        let attenuation = Color(1., 1., 1.);
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let medium = self.medium();
+        // the medium on the other side of the surface from this one
+        let other = path.media.current_without(medium.id).copied();
+
+        // inside something of higher priority this surface isn't really
+        // there, the path goes straight through and only notes the change
+        if other.is_some_and(|other| other.priority > self.priority) {
+            match hit_info.front_face {
+                true => path.media.enter(medium),
+                false => path.media.leave(medium.id),
+            }
+            return Some((hit_info.spawn_ray(ray.dir, ray.time), Color(1., 1., 1.)));
+        }
+
+        // the first dispersive surface picks one wavelength for the rest of
+        // the path, which then carries only that wavelength's share of the color
+        let mut attenuation = Color(1., 1., 1.);
+        if self.ior.is_dispersive() && path.wavelength.is_none() {
+            let (min, max) = VISIBLE_WAVELENGTHS;
+            let wavelength = min + (max - min) * sampler.get_1d();
+            path.wavelength = Some(wavelength);
+            attenuation = wavelength_color(wavelength);
+        }
+
+        let inner = medium.ior_at(path.wavelength);
+        let outer = other.map_or(1., |other| other.ior_at(path.wavelength));
         let ri = if hit_info.front_face {
-            1. / self.refraction_index
+            outer / inner
         } else {
-            self.refraction_index
+            inner / outer
         };
 
         let unit_dir = ray.dir.unit_vec();
+        let distribution = TrowbridgeReitz::from_roughness(self.roughness);
 
-        let cos_theta = (-unit_dir).dot(&hit_info.normal).min(1.);
-        let sin_theta = (1. - cos_theta * cos_theta).sqrt();
-        let cannot_refract = ri * sin_theta > 1.;
+        let (dir, refracted) = if distribution.is_smooth() {
+            let cos_theta = hit_info.normal.dot(&-unit_dir).min(1.);
+            let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+            let cannot_refract = ri * sin_theta > 1.;
 
-        let dir = if cannot_refract || Dialectric::reflectance(cos_theta, ri) > fastrand::f64() {
-            unit_dir.reflect(&hit_info.normal)
+            if cannot_refract || Dialectric::reflectance(cos_theta, ri) > sampler.get_1d() {
+                (unit_dir.reflect(&hit_info.normal), false)
+            } else {
+                (unit_dir.refract(&hit_info.normal, ri), true)
+            }
         } else {
-            unit_dir.refract(&hit_info.normal, ri)
+            // reflect or refract on a visible microfacet, picked by its Fresnel term
+            let wo = -unit_dir;
+            let (dir, refracted, shadowing) = distribution.sample_dielectric(
+                &hit_info.normal,
+                &wo,
+                ri,
+                sampler.get_2d(),
+                sampler.get_1d(),
+            )?;
+            attenuation *= shadowing;
+            (dir, refracted)
         };
 
-        let scattered = Ray {
-            origin: hit_info.pos,
-            dir,
-        };
+        if refracted {
+            match hit_info.front_face {
+                true => path.media.enter(medium),
+                false => path.media.leave(medium.id),
+            }
+        }
+
+        let scattered = hit_info.spawn_ray(dir, ray.time);
 
         Some((scattered, attenuation))
     }
 }
 
 impl Dialectric {
-    fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
+    fn reflectance(cosine: Float, refraction_index: Float) -> Float {
         // schlicky boi
         let r0 = (1. - refraction_index) / (1. + refraction_index);
         let r0 = r0 * r0;
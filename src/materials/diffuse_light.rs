@@ -0,0 +1,39 @@
+use super::*;
+use crate::{color::Color, texture::*};
+
+/// Gives off light evenly in every direction from its front side, and
+/// reflects nothing
+pub struct DiffuseLight {
+    pub emit: ColorTexture,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: impl Texture<Color> + Sync + 'static) -> Self {
+        Self {
+            emit: Box::new(emit),
+        }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _hit_info: &HitInfo,
+        _path: &mut PathState,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn albedo(&self, _hit_info: &HitInfo) -> Color {
+        Color(0., 0., 0.)
+    }
+
+    fn emitted(&self, hit_info: &HitInfo) -> Color {
+        match hit_info.front_face {
+            true => self.emit.value(hit_info),
+            false => Color(0., 0., 0.),
+        }
+    }
+}
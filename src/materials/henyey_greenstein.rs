@@ -0,0 +1,49 @@
+use super::*;
+use crate::{
+    color::Color,
+    float::{consts, Float},
+};
+
+/// Phase function of a medium that scatters mostly forward (g > 0), like
+/// clouds and smoke, or backward (g < 0). g = 0 is the same as `Isotropic`,
+/// and g has to stay inside (-1, 1).
+pub struct HenyeyGreenstein {
+    pub albedo: Color,
+    // average cosine of the angle the ray turns by
+    pub g: Float,
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        _path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let (u, v) = sampler.get_2d();
+        let g = self.g;
+
+        // inverting the cdf of the phase function in cos theta,
+        // relative to the direction the ray was going
+        let cos_theta = if g.abs() < 1e-3 {
+            1. - 2. * u
+        } else {
+            let s = (1. - g * g) / (1. - g + 2. * g * u);
+            ((1. + g * g - s * s) / (2. * g)).clamp(-1., 1.)
+        };
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let phi = 2. * consts::PI * v;
+
+        let forward = ray.dir.unit_vec();
+        let (tangent, bitangent) = forward.orthonormal_basis();
+        let dir = (tangent * phi.cos() + bitangent * phi.sin()) * sin_theta + forward * cos_theta;
+
+        // the sampled phase function cancels its own pdf
+        Some((hit_info.spawn_ray(dir, ray.time), self.albedo))
+    }
+
+    fn albedo(&self, _hit_info: &HitInfo) -> Color {
+        self.albedo
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{color::Color, float::Float, vec3::Mat3};
+use std::sync::OnceLock;
+
+/// Index of refraction, constant or depending on the wavelength.
+/// Wavelengths are in nanometers, the formulas take micrometers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ior {
+    Constant(Float),
+    /// n = a + b / l^2
+    Cauchy {
+        a: Float,
+        b: Float,
+    },
+    /// n^2 = 1 + sum of b_i l^2 / (l^2 - c_i)
+    Sellmeier {
+        b: [Float; 3],
+        c: [Float; 3],
+    },
+}
+
+impl Ior {
+    /// Borosilicate crown glass, the usual lens glass
+    pub const BK7: Ior = Ior::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [6.00069867e-3, 2.00179144e-2, 103.560653],
+    };
+    pub const FUSED_SILICA: Ior = Ior::Sellmeier {
+        b: [0.6961663, 0.4079426, 0.8974794],
+        c: [4.67914826e-3, 1.35120631e-2, 97.9340025],
+    };
+    /// Dense flint glass, disperses a lot more than crown glass
+    pub const SF11: Ior = Ior::Sellmeier {
+        b: [1.73759695, 0.313747346, 1.89878101],
+        c: [1.3188707e-2, 6.23068142e-2, 155.23629],
+    };
+    pub const DIAMOND: Ior = Ior::Sellmeier {
+        b: [4.3356, 0.3306, 0.],
+        c: [1.1236e-2, 3.0625e-2, 0.],
+    };
+    pub const WATER: Ior = Ior::Cauchy {
+        a: 1.3199,
+        b: 0.00653,
+    };
+
+    /// The wavelength `nominal` uses, the sodium d line
+    pub const NOMINAL_WAVELENGTH: Float = 587.6;
+
+    pub fn at(&self, wavelength: Float) -> Float {
+        let l2 = (wavelength / 1000.) * (wavelength / 1000.);
+        match self {
+            Ior::Constant(n) => *n,
+            Ior::Cauchy { a, b } => a + b / l2,
+            Ior::Sellmeier { b, c } => {
+                let sum: Float = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+                (1. + sum).sqrt()
+            }
+        }
+    }
+
+    /// The index for paths that haven't picked a wavelength
+    pub fn nominal(&self) -> Float {
+        self.at(Self::NOMINAL_WAVELENGTH)
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Ior::Constant(_))
+    }
+}
+
+impl From<Float> for Ior {
+    fn from(n: Float) -> Self {
+        Ior::Constant(n)
+    }
+}
+
+/// Range of the wavelengths dispersion picks from, in nanometers
+pub const VISIBLE_WAVELENGTHS: (Float, Float) = (380., 720.);
+
+/// Linear sRGB of a single wavelength, scaled so the average over
+/// `VISIBLE_WAVELENGTHS` is white. A path that picks its wavelength uniformly
+/// and is weighted by this stays white on average.
+pub fn wavelength_color(wavelength: Float) -> Color {
+    static SCALE: OnceLock<Color> = OnceLock::new();
+    let scale = SCALE.get_or_init(|| {
+        let (min, max) = VISIBLE_WAVELENGTHS;
+        let steps = 1000;
+        let sum: Color = (0..steps)
+            .map(|i| raw_wavelength_color(min + (max - min) * (i as Float + 0.5) / steps as Float))
+            .sum();
+        let mean = sum / steps as Float;
+        Color(1. / mean.r(), 1. / mean.g(), 1. / mean.b())
+    });
+    raw_wavelength_color(wavelength) * *scale
+}
+
+// CIE 1931 color matching functions by the multi-lobe gaussian fit of Wyman,
+// Sloan and Shirley, "Simple Analytic Approximations to the CIE XYZ Color
+// Matching Functions", then XYZ to linear sRGB. Colors outside sRGB are
+// clipped, so no wavelength gets a negative weight.
+fn raw_wavelength_color(wavelength: Float) -> Color {
+    let g = |mu: Float, sigma_below: Float, sigma_above: Float| {
+        let sigma = if wavelength < mu {
+            sigma_below
+        } else {
+            sigma_above
+        };
+        let x = (wavelength - mu) / sigma;
+        (-0.5 * x * x).exp()
+    };
+    let xyz = Color(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    );
+    const XYZ_TO_SRGB: Mat3 = Mat3([
+        [3.2406, -1.5372, -0.4986],
+        [-0.9689, 1.8758, 0.0415],
+        [0.0557, -0.2040, 1.0570],
+    ]);
+    let rgb = XYZ_TO_SRGB * xyz;
+    Color(rgb.r().max(0.), rgb.g().max(0.), rgb.b().max(0.))
+}
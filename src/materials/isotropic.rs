@@ -0,0 +1,25 @@
+use super::*;
+use crate::{color::Color, vec3::Vec3};
+
+/// Phase function of a participating medium that scatters the same amount
+/// in every direction, for use with `ConstantMedium`
+pub struct Isotropic {
+    pub albedo: Color,
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        _path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let scattered = hit_info.spawn_ray(Vec3::unit_vec_from_sample(sampler.get_2d()), ray.time);
+        Some((scattered, self.albedo))
+    }
+
+    fn albedo(&self, _hit_info: &HitInfo) -> Color {
+        self.albedo
+    }
+}
@@ -6,18 +6,26 @@ pub struct Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, hit_info: &HitInfo) -> Option<(Ray, Color)> {
-        let mut scatter_dir = Vec3::rand_unit_vec() + hit_info.normal;
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        _path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let mut scatter_dir =
+            Vec3::unit_vec_from_sample(sampler.get_2d()) + hit_info.normal.to_vec();
         // catching problems
         if scatter_dir.near_zero() {
-            scatter_dir = hit_info.normal;
+            scatter_dir = hit_info.normal.to_vec();
         }
 
-        let scattered = Ray {
-            origin: hit_info.pos,
-            dir: scatter_dir,
-        };
+        let scattered = hit_info.spawn_ray(scatter_dir, ray.time);
 
         Some((scattered, self.albedo))
     }
+
+    fn albedo(&self, _hit_info: &HitInfo) -> Color {
+        self.albedo
+    }
 }
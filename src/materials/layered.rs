@@ -0,0 +1,130 @@
+use super::*;
+use crate::{color::Color, float::Float};
+
+// after this many trips between the base and the underside of the coat the
+// path is given up on, which only loses light from very shiny bases
+const MAX_INNER_BOUNCES: usize = 16;
+
+/// A clear coat over another material, like varnished wood or car paint.
+/// The coat is a `Dialectric`, whose index and roughness make the reflection
+/// on top and whose absorption tints light going through it. Light that
+/// gets in bounces between the base and the underside of the coat, each
+/// time with the Fresnel chance of being reflected back down, until it
+/// finds its way out.
+pub struct Layered<M: Material> {
+    pub coat: Dialectric,
+    pub base: M,
+    // how far light travels straight through the coat, for its absorption
+    pub thickness: Float,
+}
+
+impl<M: Material> Layered<M> {
+    /// The base can be a material or a reference to one
+    pub fn new(coat: Dialectric, base: M) -> Self {
+        Self {
+            coat,
+            base,
+            thickness: 1.,
+        }
+    }
+
+    pub fn with_thickness(self, thickness: Float) -> Self {
+        Self { thickness, ..self }
+    }
+
+    // how much of the light crossing the coat at an angle survives it
+    fn absorbed(&self, cos: Float) -> Color {
+        let distance = self.thickness / cos.abs().max(1e-3);
+        let a = self.coat.absorption;
+        Color(
+            (-a.r() * distance).exp(),
+            (-a.g() * distance).exp(),
+            (-a.b() * distance).exp(),
+        )
+    }
+}
+
+impl<M: Material> Material for Layered<M> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        // only the outside is coated
+        if !hit_info.front_face {
+            return self.base.scatter(ray, hit_info, path, sampler);
+        }
+
+        let outer = path
+            .media
+            .current()
+            .map_or(1., |medium| medium.ior_at(path.wavelength));
+        let inner = path
+            .wavelength
+            .map_or(self.coat.ior.nominal(), |wavelength| {
+                self.coat.ior.at(wavelength)
+            });
+        let distribution = TrowbridgeReitz::from_roughness(self.coat.roughness);
+        let normal = hit_info.normal;
+
+        // the top of the coat
+        let wo = -ray.dir.unit_vec();
+        let (mut dir, refracted, shadowing) = distribution.sample_dielectric(
+            &normal,
+            &wo,
+            outer / inner,
+            sampler.get_2d(),
+            sampler.get_1d(),
+        )?;
+        let mut attenuation = Color(1., 1., 1.) * shadowing;
+        if !refracted {
+            return Some((hit_info.spawn_ray(dir, ray.time), attenuation));
+        }
+
+        for _ in 0..MAX_INNER_BOUNCES {
+            // down through the coat to the base, which it reaches at the
+            // hit point since the coat has no real thickness
+            attenuation *= self.absorbed(normal.dot(&dir));
+            let inner_ray = Ray {
+                origin: hit_info.pos,
+                dir,
+                time: ray.time,
+            };
+            let (scattered, color) = self.base.scatter(&inner_ray, hit_info, path, sampler)?;
+            attenuation *= color;
+
+            // went through the base, like when it is glass
+            if normal.dot(&scattered.dir) <= 0. {
+                return Some((scattered, attenuation));
+            }
+
+            // back up through the coat, to leave or be reflected down again
+            let up = scattered.dir.unit_vec();
+            attenuation *= self.absorbed(normal.dot(&up));
+            let (next, refracted, shadowing) = distribution.sample_dielectric(
+                &-normal,
+                &-up,
+                inner / outer,
+                sampler.get_2d(),
+                sampler.get_1d(),
+            )?;
+            attenuation *= shadowing;
+            if refracted {
+                return Some((hit_info.spawn_ray(next, ray.time), attenuation));
+            }
+            dir = next;
+        }
+        None
+    }
+
+    fn albedo(&self, hit_info: &HitInfo) -> Color {
+        self.base.albedo(hit_info)
+    }
+
+    // seen through the coat, ignoring its absorption and reflection
+    fn emitted(&self, hit_info: &HitInfo) -> Color {
+        self.base.emitted(hit_info)
+    }
+}
@@ -1,25 +1,31 @@
-use crate::{color::Color, hit::HitInfo, ray::Ray, vec3::*};
+use crate::{color::Color, float::Float, hit::HitInfo, ray::Ray, sampler::Sampler, vec3::*};
 
-use super::Material;
+use super::{Material, PathState};
 
+/// The fuzzy metal of the book: a mirror with the reflection pushed off by
+/// up to `fuzz`. See `Conductor` for physically based metals.
 pub struct Metal {
     pub albedo: Color,
-    pub fuzz: f64,
+    pub fuzz: Float,
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, hit_info: &HitInfo) -> Option<(Ray, Color)> {
-        let scattered =
-            ray.dir.reflect(&hit_info.normal).unit_vec() + Vec3::rand_unit_vec() * self.fuzz;
-        match scattered.dot(&hit_info.normal) > 0. {
-            true => Some((
-                Ray {
-                    origin: hit_info.pos,
-                    dir: scattered,
-                },
-                self.albedo,
-            )),
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        _path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let scattered = ray.dir.reflect(&hit_info.normal).unit_vec()
+            + Vec3::unit_vec_from_sample(sampler.get_2d()) * self.fuzz;
+        match hit_info.normal.dot(&scattered) > 0. {
+            true => Some((hit_info.spawn_ray(scattered, ray.time), self.albedo)),
             false => None,
         }
     }
+
+    fn albedo(&self, _hit_info: &HitInfo) -> Color {
+        self.albedo
+    }
 }
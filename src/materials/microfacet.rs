@@ -0,0 +1,172 @@
+use crate::{
+    color::Color,
+    float::{consts, Float},
+    vec3::*,
+};
+
+/// The GGX, or Trowbridge-Reitz, distribution of microfacet normals, for
+/// rough surfaces made of tiny perfect mirrors. Isotropic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrowbridgeReitz {
+    // the width of the distribution, 0 is a perfect mirror
+    pub alpha: Float,
+}
+
+impl TrowbridgeReitz {
+    /// Perceptual roughness in [0, 1], squared so the look changes about
+    /// evenly over the range
+    pub fn from_roughness(roughness: Float) -> Self {
+        Self {
+            alpha: roughness * roughness,
+        }
+    }
+
+    /// Close enough to a mirror that sampling microfacets would only lose
+    /// precision
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < 1e-3
+    }
+
+    // the share of microfacets hidden from a direction at cos_theta from the
+    // normal, relative to the visible ones
+    fn lambda(&self, cos_theta: Float) -> Float {
+        let cos2 = cos_theta * cos_theta;
+        let tan2 = (1. - cos2).max(0.) / cos2;
+        ((1. + self.alpha * self.alpha * tan2).sqrt() - 1.) / 2.
+    }
+
+    /// Fraction of microfacets visible from a direction
+    pub fn g1(&self, cos_theta: Float) -> Float {
+        1. / (1. + self.lambda(cos_theta))
+    }
+
+    /// Fraction of microfacets visible from both directions
+    pub fn g2(&self, cos_o: Float, cos_i: Float) -> Float {
+        1. / (1. + self.lambda(cos_o) + self.lambda(cos_i))
+    }
+
+    /// A microfacet normal around `normal`, picked in proportion to how much
+    /// of it is visible from the unit direction wo, which is on the normal's
+    /// side. Mirroring wo on it then only misses the surface because of
+    /// shadowing, which `g2 / g1` accounts for (Dupuy and Benyoub, "Sampling
+    /// Visible GGX Normals with Spherical Caps").
+    pub fn sample_visible_normal(&self, normal: &Normal, wo: &Vec3, u: (Float, Float)) -> Normal {
+        let (tangent, bitangent) = normal.to_vec().orthonormal_basis();
+        let n = normal.to_vec();
+        let local = Vec3(wo.dot(&tangent), wo.dot(&bitangent), wo.dot(&n));
+
+        // stretched so the distribution becomes the hemisphere, where the
+        // visible normals are a spherical cap around wo
+        let wo_std = Vec3(local.0 * self.alpha, local.1 * self.alpha, local.2).unit_vec();
+        let phi = 2. * consts::PI * u.0;
+        let z = (1. - u.1) * (1. + wo_std.2) - wo_std.2;
+        let sin_theta = (1. - z * z).clamp(0., 1.).sqrt();
+        let m_std = Vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), z) + wo_std;
+
+        // and back
+        let m = Vec3(m_std.0 * self.alpha, m_std.1 * self.alpha, m_std.2.max(0.));
+        Normal::new(tangent * m.0 + bitangent * m.1 + n * m.2)
+    }
+
+    /// Mirrors the unit direction wo on a visible microfacet, or on the normal
+    /// itself when smooth. Returns the direction, the cosine between wo and
+    /// the microfacet normal for the Fresnel term, and the `g2 / g1` shadowing
+    /// weight. None if the reflection went into the surface.
+    pub fn sample_reflection(
+        &self,
+        normal: &Normal,
+        wo: &Vec3,
+        u: (Float, Float),
+    ) -> Option<(Vec3, Float, Float)> {
+        let cos_o = normal.dot(wo);
+        if self.is_smooth() {
+            return Some(((-wo).reflect(normal), cos_o, 1.));
+        }
+
+        let m = self.sample_visible_normal(normal, wo, u);
+        let dir = (-wo).reflect(&m);
+        let cos_i = normal.dot(&dir);
+        if cos_i <= 0. {
+            return None;
+        }
+        Some((dir, m.dot(wo), self.g2(cos_o, cos_i) / self.g1(cos_o)))
+    }
+
+    /// Reflects or refracts the unit direction wo at a dielectric boundary,
+    /// picking by the Fresnel term of a visible microfacet, with `ri` the
+    /// index on wo's side over the other. Returns the direction, whether it
+    /// refracted, and the `g2 / g1` shadowing weight, which is all of
+    /// f cos / pdf. None if the microfacet sent it to the wrong side.
+    pub fn sample_dielectric(
+        &self,
+        normal: &Normal,
+        wo: &Vec3,
+        ri: Float,
+        u: (Float, Float),
+        u_choice: Float,
+    ) -> Option<(Vec3, bool, Float)> {
+        let m = match self.is_smooth() {
+            true => *normal,
+            false => self.sample_visible_normal(normal, wo, u),
+        };
+        let reflected = fresnel_dielectric(m.dot(wo), 1. / ri) > u_choice;
+        let dir = match reflected {
+            true => (-wo).reflect(&m),
+            false => (-wo).refract(&m, ri),
+        };
+        if self.is_smooth() {
+            return Some((dir, !reflected, 1.));
+        }
+
+        let (cos_o, cos_i) = (normal.dot(wo), normal.dot(&dir));
+        if reflected != (cos_i > 0.) {
+            return None;
+        }
+        Some((
+            dir,
+            !reflected,
+            self.g2(cos_o, cos_i.abs()) / self.g1(cos_o),
+        ))
+    }
+}
+
+/// Fraction of light reflected by a smooth dielectric boundary, unpolarized.
+/// eta is the index on the far side over the index on the near side, 1 if
+/// totally internally reflected.
+pub fn fresnel_dielectric(cos_theta_i: Float, eta: Float) -> Float {
+    let cos_i = cos_theta_i.clamp(0., 1.);
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (parallel * parallel + perpendicular * perpendicular) / 2.
+}
+
+/// Fraction of light reflected by a metal, per channel, with the complex
+/// index eta + i k relative to the outside (Lagarde, "Memo on Fresnel
+/// equations")
+pub fn fresnel_conductor(cos_theta_i: Float, eta: &Color, k: &Color) -> Color {
+    let cos = cos_theta_i.clamp(0., 1.);
+    let cos2 = cos * cos;
+    let sin2 = 1. - cos2;
+    let channel = |eta: Float, k: Float| {
+        let t0 = eta * eta - k * k - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4. * eta * eta * k * k).sqrt();
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let t2 = 2. * a * cos;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rp + rs)
+    };
+    Color(
+        channel(eta.r(), k.r()),
+        channel(eta.g(), k.g()),
+        channel(eta.b(), k.b()),
+    )
+}
@@ -0,0 +1,46 @@
+use super::*;
+use crate::{color::Color, float::Float, texture::*};
+
+/// Blends two materials, by picking one of them for each bounce. A weight
+/// of 0 is all `a`, 1 is all `b`.
+pub struct Mix<A: Material, B: Material> {
+    pub a: A,
+    pub b: B,
+    pub weight: FloatTexture,
+}
+
+impl<A: Material, B: Material> Mix<A, B> {
+    /// Like `NormalMap`, both can be materials or references to them
+    pub fn new(a: A, b: B, weight: impl Texture<Float> + Sync + 'static) -> Self {
+        Self {
+            a,
+            b,
+            weight: Box::new(weight),
+        }
+    }
+}
+
+impl<A: Material, B: Material> Material for Mix<A, B> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        match sampler.get_1d() < self.weight.value(hit_info) {
+            true => self.b.scatter(ray, hit_info, path, sampler),
+            false => self.a.scatter(ray, hit_info, path, sampler),
+        }
+    }
+
+    fn albedo(&self, hit_info: &HitInfo) -> Color {
+        let weight = self.weight.value(hit_info).clamp(0., 1.);
+        self.a.albedo(hit_info) * (1. - weight) + self.b.albedo(hit_info) * weight
+    }
+
+    fn emitted(&self, hit_info: &HitInfo) -> Color {
+        let weight = self.weight.value(hit_info).clamp(0., 1.);
+        self.a.emitted(hit_info) * (1. - weight) + self.b.emitted(hit_info) * weight
+    }
+}
@@ -1,14 +1,76 @@
+pub mod conductor;
 pub mod dialectric;
+pub mod diffuse_light;
+pub mod henyey_greenstein;
+pub mod ior;
+pub mod isotropic;
 pub mod lambertian;
+pub mod layered;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_map;
+pub mod path;
+pub mod principled;
 
+pub use conductor::*;
 pub use dialectric::*;
+pub use diffuse_light::*;
+pub use henyey_greenstein::*;
+pub use ior::*;
+pub use isotropic::*;
 pub use lambertian::*;
+pub use layered::*;
 pub use metal::*;
+pub use microfacet::*;
+pub use mix::*;
+pub use normal_map::*;
+pub use path::*;
+pub use principled::*;
 
-use crate::{color::Color, hit::HitInfo, ray::Ray};
+use crate::{color::Color, hit::HitInfo, ray::Ray, sampler::Sampler};
 
 pub trait Material {
-    /// Given an in-ray and hit info, returns the scattered ray and attenuated color
-    fn scatter(&self, ray: &Ray, hit_info: &HitInfo) -> Option<(Ray, Color)>;
+    /// Given an in-ray and hit info, returns the scattered ray and attenuated color.
+    /// The path state is shared by every bounce of the path.
+    /// Random numbers should come from the sampler.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)>;
+
+    /// The base color of the surface, used for the albedo AOV
+    fn albedo(&self, _hit_info: &HitInfo) -> Color {
+        Color(1., 1., 1.)
+    }
+
+    /// Light given off by the surface towards the ray that hit it
+    fn emitted(&self, _hit_info: &HitInfo) -> Color {
+        Color(0., 0., 0.)
+    }
+}
+
+// a reference to a material works as the material, so wrappers can own
+// their base or borrow it
+impl<M: Material + ?Sized> Material for &M {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        (**self).scatter(ray, hit_info, path, sampler)
+    }
+
+    fn albedo(&self, hit_info: &HitInfo) -> Color {
+        (**self).albedo(hit_info)
+    }
+
+    fn emitted(&self, hit_info: &HitInfo) -> Color {
+        (**self).emitted(hit_info)
+    }
 }
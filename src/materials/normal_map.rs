@@ -0,0 +1,209 @@
+use super::*;
+use crate::{color::Color, float::Float, texture::*, vec3::*};
+
+/// Bends the shading normal of another material by a tangent-space normal
+/// map, the usual purple-blue images where (0.5, 0.5, 1) is straight out.
+/// The image should hold the values as stored in the file, not decoded from
+/// sRGB. Red follows u and green follows v.
+pub struct NormalMap<M: Material> {
+    pub base: M,
+    pub map: ImageTexture,
+    // 0 leaves the normal alone, 1 is the map as it is
+    pub strength: Float,
+}
+
+impl<M: Material> NormalMap<M> {
+    /// The base can be a material or a reference to one
+    pub fn new(base: M, map: ImageTexture) -> Self {
+        Self {
+            base,
+            map,
+            strength: 1.,
+        }
+    }
+
+    pub fn with_strength(self, strength: Float) -> Self {
+        Self { strength, ..self }
+    }
+}
+
+impl<M: Material> Material for NormalMap<M> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let (tangent, bitangent, normal) = tangent_frame(hit_info);
+        let c = self.map.value(hit_info);
+        let local = Vec3(
+            (2. * c.r() - 1.) * self.strength,
+            (2. * c.g() - 1.) * self.strength,
+            2. * c.b() - 1.,
+        );
+        let bent = tangent * local.0 + bitangent * local.1 + normal.to_vec() * local.2;
+        let shading = match bent.near_zero() || bent.dot(&normal.to_vec()) <= 0. {
+            true => normal,
+            false => Normal::new(bent),
+        };
+        scatter_shaded(
+            &self.base,
+            ray,
+            hit_info,
+            facing_ray(hit_info, shading),
+            path,
+            sampler,
+        )
+    }
+
+    fn albedo(&self, hit_info: &HitInfo) -> Color {
+        self.base.albedo(hit_info)
+    }
+
+    fn emitted(&self, hit_info: &HitInfo) -> Color {
+        self.base.emitted(hit_info)
+    }
+}
+
+/// Bends the shading normal of another material as if the surface were
+/// pushed out along its normal by a height texture, in world units times
+/// `scale`. The surface itself stays where it is.
+pub struct BumpMap<M: Material> {
+    pub base: M,
+    pub height: FloatTexture,
+    pub scale: Float,
+}
+
+impl<M: Material> BumpMap<M> {
+    pub fn new(base: M, height: impl Texture<Float> + Sync + 'static, scale: Float) -> Self {
+        Self {
+            base,
+            height: Box::new(height),
+            scale,
+        }
+    }
+}
+
+// the step in u and v for the height's finite differences
+const BUMP_DELTA: Float = 1. / 1024.;
+
+impl<M: Material> Material for BumpMap<M> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let normal = outward_normal(hit_info);
+        if hit_info.dpdu.near_zero() || hit_info.dpdv.near_zero() {
+            return self.base.scatter(ray, hit_info, path, sampler);
+        }
+
+        // the height here and a little along u and v (pbrt 10.5.2, leaving
+        // out how the normal itself turns)
+        let height_at = |du: Float, dv: Float| {
+            let moved = HitInfo {
+                pos: hit_info.pos + hit_info.dpdu * du + hit_info.dpdv * dv,
+                u: hit_info.u + du,
+                v: hit_info.v + dv,
+                ..*hit_info
+            };
+            self.height.value(&moved) * self.scale
+        };
+        let height = height_at(0., 0.);
+        let slope_u = (height_at(BUMP_DELTA, 0.) - height) / BUMP_DELTA;
+        let slope_v = (height_at(0., BUMP_DELTA) - height) / BUMP_DELTA;
+
+        let n = normal.to_vec();
+        let dpdu = hit_info.dpdu + n * slope_u;
+        let dpdv = hit_info.dpdv + n * slope_v;
+        let bent = dpdu.cross(&dpdv);
+        let shading = match bent.near_zero() {
+            true => normal,
+            // the cross product faces either way depending on the uv layout
+            false if bent.dot(&n) < 0. => Normal::new(-bent),
+            false => Normal::new(bent),
+        };
+        scatter_shaded(
+            &self.base,
+            ray,
+            hit_info,
+            facing_ray(hit_info, shading),
+            path,
+            sampler,
+        )
+    }
+
+    fn albedo(&self, hit_info: &HitInfo) -> Color {
+        self.base.albedo(hit_info)
+    }
+
+    fn emitted(&self, hit_info: &HitInfo) -> Color {
+        self.base.emitted(hit_info)
+    }
+}
+
+// maps are made for the front side of the surface, so they work with the
+// shading normal turned that way, and turn the result back after
+fn outward_normal(hit_info: &HitInfo) -> Normal {
+    facing_ray(hit_info, hit_info.normal)
+}
+
+fn facing_ray(hit_info: &HitInfo, normal: Normal) -> Normal {
+    match hit_info.front_face {
+        true => normal,
+        false => -normal,
+    }
+}
+
+// unit tangent along u, bitangent along v and the outward shading normal,
+// with the tangents made up where the shape has no uv derivatives
+fn tangent_frame(hit_info: &HitInfo) -> (Vec3, Vec3, Normal) {
+    let normal = outward_normal(hit_info);
+    let n = normal.to_vec();
+    let along_u = hit_info.dpdu - n * n.dot(&hit_info.dpdu);
+    if along_u.near_zero() {
+        let (tangent, bitangent) = n.orthonormal_basis();
+        return (tangent, bitangent, normal);
+    }
+    let tangent = along_u.unit_vec();
+    let bitangent = n.cross(&tangent);
+    // mirrored uvs flip which way v goes
+    match bitangent.dot(&hit_info.dpdv) < 0. {
+        true => (tangent, -bitangent, normal),
+        false => (tangent, bitangent, normal),
+    }
+}
+
+/// Scatters off the base material with the shading normal swapped in. The
+/// bent normal can put the incoming or outgoing ray on the wrong side of the
+/// real surface, where light would leak through it: an incoming ray behind
+/// the shading normal shades with the geometric one instead, and a ray sent
+/// to opposite sides of the two normals is dropped.
+fn scatter_shaded(
+    base: &impl Material,
+    ray: &Ray,
+    hit_info: &HitInfo,
+    shading: Normal,
+    path: &mut PathState,
+    sampler: &mut dyn Sampler,
+) -> Option<(Ray, Color)> {
+    let shading = match shading.dot(&-ray.dir) > 0. {
+        true => shading,
+        false => hit_info.geometric_normal,
+    };
+    let shaded = HitInfo {
+        normal: shading,
+        ..*hit_info
+    };
+    let (scattered, color) = base.scatter(ray, &shaded, path, sampler)?;
+
+    let geometric_side = hit_info.geometric_normal.dot(&scattered.dir) > 0.;
+    let shading_side = shading.dot(&scattered.dir) > 0.;
+    match geometric_side == shading_side {
+        true => Some((scattered, color)),
+        false => None,
+    }
+}
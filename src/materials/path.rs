@@ -0,0 +1,87 @@
+use super::Ior;
+use crate::{color::Color, float::Float};
+
+/// What a light path carries from bounce to bounce, for materials that
+/// depend on more than the current hit
+#[derive(Clone, Debug, Default)]
+pub struct PathState {
+    /// The dielectrics the path is inside
+    pub media: MediumStack,
+    /// Picked by the first dispersive surface the path meets, in nanometers.
+    /// After that every index of refraction is taken at this wavelength.
+    pub wavelength: Option<Float>,
+}
+
+/// A dielectric the path is inside
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Medium {
+    // tells the materials apart, the address of the material
+    pub id: usize,
+    pub priority: u32,
+    pub ior: Ior,
+    // Beer-Lambert absorption coefficients, per unit of distance
+    pub absorption: Color,
+}
+
+impl Medium {
+    pub fn ior_at(&self, wavelength: Option<Float>) -> Float {
+        match wavelength {
+            Some(wavelength) => self.ior.at(wavelength),
+            None => self.ior.nominal(),
+        }
+    }
+}
+
+/// The dielectrics a path has entered and not yet left. Where objects
+/// overlap, like a liquid filling a glass or ice floating in water, the
+/// path is only in the one with the highest priority, and the surfaces of
+/// the others inside it don't count (Schmidt and Budge, "Simple Nested
+/// Dielectrics in Ray Traced Images").
+#[derive(Clone, Debug, Default)]
+pub struct MediumStack {
+    media: Vec<Medium>,
+}
+
+impl MediumStack {
+    /// The medium the path is in: the highest priority one, the latest
+    /// entered of those. None is air.
+    pub fn current(&self) -> Option<&Medium> {
+        // max_by_key keeps the last of equal keys
+        self.media.iter().max_by_key(|medium| medium.priority)
+    }
+
+    /// The medium the path would be in without the one with this id
+    pub fn current_without(&self, id: usize) -> Option<&Medium> {
+        self.media
+            .iter()
+            .filter(|medium| medium.id != id)
+            .max_by_key(|medium| medium.priority)
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.media.iter().any(|medium| medium.id == id)
+    }
+
+    pub fn enter(&mut self, medium: Medium) {
+        self.media.push(medium);
+    }
+
+    /// Leaves the most recently entered medium with this id
+    pub fn leave(&mut self, id: usize) {
+        if let Some(i) = self.media.iter().rposition(|medium| medium.id == id) {
+            self.media.remove(i);
+        }
+    }
+
+    /// Fraction of light left after travelling the distance through the
+    /// current medium (Beer-Lambert law)
+    pub fn transmittance(&self, distance: Float) -> Color {
+        match self.current() {
+            Some(medium) => {
+                let a = medium.absorption * distance;
+                Color((-a.r()).exp(), (-a.g()).exp(), (-a.b()).exp())
+            }
+            None => Color(1., 1., 1.),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use super::*;
+use crate::{
+    color::Color,
+    float::{consts, Float},
+    texture::*,
+    vec3::Vec3,
+};
+
+/// One material for most surfaces, after Burley's "Physically Based Shading
+/// at Disney" and the glTF metallic-roughness model. Every parameter is a
+/// texture, plain numbers and colors work where a constant will do.
+///
+/// The surface is a clear coat over a mix of metal, glass and a plastic-like
+/// base of diffuse under a specular layer. Each bounce picks one of those in
+/// proportion to its share, so no lobe needs evaluating for directions it
+/// didn't sample.
+pub struct Principled {
+    pub base_color: ColorTexture,
+    // 0 is a dielectric, 1 a metal colored by base_color
+    pub metallic: FloatTexture,
+    // perceptual roughness of the metal, specular and glass
+    pub roughness: FloatTexture,
+    // strength of the dielectric specular, 0.5 is an index of 1.5
+    pub specular: FloatTexture,
+    // tints the dielectric specular towards the base color
+    pub specular_tint: FloatTexture,
+    // extra grazing reflection for cloth
+    pub sheen: FloatTexture,
+    pub sheen_tint: FloatTexture,
+    // a second, colorless specular layer on top, like car paint
+    pub clearcoat: FloatTexture,
+    pub clearcoat_roughness: FloatTexture,
+    // share of the dielectric that is glass instead of diffuse
+    pub transmission: FloatTexture,
+    // flattens the diffuse to look like light scattered under the surface
+    pub subsurface: FloatTexture,
+    // index of refraction of the glass
+    pub ior: FloatTexture,
+    // light given off, from either side
+    pub emission: ColorTexture,
+}
+
+impl Principled {
+    /// A rough white-ish plastic in the given color, the other parameters
+    /// default to the glTF defaults where there is one
+    pub fn new(base_color: impl Texture<Color> + Sync + 'static) -> Self {
+        Self {
+            base_color: Box::new(base_color),
+            metallic: Box::new(0.),
+            roughness: Box::new(0.5),
+            specular: Box::new(0.5),
+            specular_tint: Box::new(0.),
+            sheen: Box::new(0.),
+            sheen_tint: Box::new(0.5),
+            clearcoat: Box::new(0.),
+            clearcoat_roughness: Box::new(0.03),
+            transmission: Box::new(0.),
+            subsurface: Box::new(0.),
+            ior: Box::new(1.5),
+            emission: Box::new(Color(0., 0., 0.)),
+        }
+    }
+
+    pub fn with_metallic(self, metallic: impl Texture<Float> + Sync + 'static) -> Self {
+        let metallic = Box::new(metallic);
+        Self { metallic, ..self }
+    }
+
+    pub fn with_roughness(self, roughness: impl Texture<Float> + Sync + 'static) -> Self {
+        let roughness = Box::new(roughness);
+        Self { roughness, ..self }
+    }
+
+    pub fn with_specular(self, specular: impl Texture<Float> + Sync + 'static) -> Self {
+        let specular = Box::new(specular);
+        Self { specular, ..self }
+    }
+
+    pub fn with_specular_tint(self, specular_tint: impl Texture<Float> + Sync + 'static) -> Self {
+        let specular_tint = Box::new(specular_tint);
+        Self {
+            specular_tint,
+            ..self
+        }
+    }
+
+    pub fn with_sheen(self, sheen: impl Texture<Float> + Sync + 'static) -> Self {
+        let sheen = Box::new(sheen);
+        Self { sheen, ..self }
+    }
+
+    pub fn with_sheen_tint(self, sheen_tint: impl Texture<Float> + Sync + 'static) -> Self {
+        let sheen_tint = Box::new(sheen_tint);
+        Self { sheen_tint, ..self }
+    }
+
+    pub fn with_clearcoat(self, clearcoat: impl Texture<Float> + Sync + 'static) -> Self {
+        let clearcoat = Box::new(clearcoat);
+        Self { clearcoat, ..self }
+    }
+
+    pub fn with_clearcoat_roughness(
+        self,
+        clearcoat_roughness: impl Texture<Float> + Sync + 'static,
+    ) -> Self {
+        let clearcoat_roughness = Box::new(clearcoat_roughness);
+        Self {
+            clearcoat_roughness,
+            ..self
+        }
+    }
+
+    pub fn with_transmission(self, transmission: impl Texture<Float> + Sync + 'static) -> Self {
+        let transmission = Box::new(transmission);
+        Self {
+            transmission,
+            ..self
+        }
+    }
+
+    pub fn with_subsurface(self, subsurface: impl Texture<Float> + Sync + 'static) -> Self {
+        let subsurface = Box::new(subsurface);
+        Self { subsurface, ..self }
+    }
+
+    pub fn with_ior(self, ior: impl Texture<Float> + Sync + 'static) -> Self {
+        let ior = Box::new(ior);
+        Self { ior, ..self }
+    }
+
+    pub fn with_emission(self, emission: impl Texture<Color> + Sync + 'static) -> Self {
+        let emission = Box::new(emission);
+        Self { emission, ..self }
+    }
+
+    /// Glass with the given tint, roughness and index
+    pub fn glass(color: Color, roughness: Float, ior: Float) -> Self {
+        Self::new(color)
+            .with_roughness(roughness)
+            .with_transmission(1.)
+            .with_ior(ior)
+    }
+
+    /// Metal reflecting in the given color
+    pub fn metal(color: Color, roughness: Float) -> Self {
+        Self::new(color).with_metallic(1.).with_roughness(roughness)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_info: &HitInfo,
+        path: &mut PathState,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let base_color = self.base_color.value(hit_info);
+        let metallic = self.metallic.value(hit_info).clamp(0., 1.);
+        let transmission = self.transmission.value(hit_info).clamp(0., 1.);
+        let roughness = self.roughness.value(hit_info).clamp(0., 1.);
+        let distribution = TrowbridgeReitz::from_roughness(roughness);
+
+        let wo = -ray.dir.unit_vec();
+        let normal = hit_info.normal;
+        let cos_o = normal.dot(&wo).max(1e-6);
+
+        let medium = Medium {
+            id: self as *const Self as usize,
+            priority: 0,
+            ior: Ior::Constant(self.ior.value(hit_info)),
+            absorption: Color(0., 0., 0.),
+        };
+        // a path that went in through the glass can only come out through it
+        let inside = !hit_info.front_face && path.media.contains(medium.id);
+
+        // the clear coat, as an index 1.5 layer that reflects by its Fresnel
+        // term and lets the rest through to the base unchanged
+        let clearcoat = self.clearcoat.value(hit_info).clamp(0., 1.);
+        if !inside
+            && clearcoat > 0.
+            && sampler.get_1d() < clearcoat * fresnel_dielectric(cos_o, 1.5)
+        {
+            let coat = TrowbridgeReitz::from_roughness(self.clearcoat_roughness.value(hit_info));
+            let (dir, _, shadowing) = coat.sample_reflection(&normal, &wo, sampler.get_2d())?;
+            return Some((
+                hit_info.spawn_ray(dir, ray.time),
+                Color(1., 1., 1.) * shadowing,
+            ));
+        }
+
+        let lobe = sampler.get_1d();
+        if !inside && lobe < metallic {
+            let (dir, cos_h, shadowing) =
+                distribution.sample_reflection(&normal, &wo, sampler.get_2d())?;
+            let attenuation = schlick(base_color, cos_h) * shadowing;
+            return Some((hit_info.spawn_ray(dir, ray.time), attenuation));
+        }
+
+        if inside || lobe < metallic + (1. - metallic) * transmission {
+            let other = path.media.current_without(medium.id).copied();
+            let inner = medium.ior_at(path.wavelength);
+            let outer = other.map_or(1., |other| other.ior_at(path.wavelength));
+            let ri = if hit_info.front_face {
+                outer / inner
+            } else {
+                inner / outer
+            };
+
+            let (dir, refracted, shadowing) = distribution.sample_dielectric(
+                &normal,
+                &wo,
+                ri,
+                sampler.get_2d(),
+                sampler.get_1d(),
+            )?;
+            // like glTF, light passing through is tinted by the base color
+            let attenuation = match refracted {
+                true => base_color * shadowing,
+                false => Color(1., 1., 1.) * shadowing,
+            };
+            if refracted {
+                match hit_info.front_face {
+                    true => path.media.enter(medium),
+                    false => path.media.leave(medium.id),
+                }
+            }
+            return Some((hit_info.spawn_ray(dir, ray.time), attenuation));
+        }
+
+        // the plastic: a specular layer that reflects by Schlick's Fresnel
+        // term, over diffuse that gets what the layer lets through
+        let tint = match base_color.luminance() {
+            lum if lum > 0. => base_color / lum,
+            _ => Color(1., 1., 1.),
+        };
+        let specular_tint = self.specular_tint.value(hit_info);
+        let f0 =
+            lerp(Color(1., 1., 1.), tint, specular_tint) * 0.08 * self.specular.value(hit_info);
+        let fresnel_o = schlick(f0, cos_o);
+        let specular_chance = (fresnel_o.r() + fresnel_o.g() + fresnel_o.b()) / 3.;
+
+        if sampler.get_1d() < specular_chance {
+            let (dir, cos_h, shadowing) =
+                distribution.sample_reflection(&normal, &wo, sampler.get_2d())?;
+            let attenuation = schlick(f0, cos_h) * shadowing / specular_chance;
+            return Some((hit_info.spawn_ray(dir, ray.time), attenuation));
+        }
+
+        // cosine weighted, so f cos / pdf is f pi
+        let mut dir = Vec3::unit_vec_from_sample(sampler.get_2d()) + normal.to_vec();
+        if dir.near_zero() {
+            dir = normal.to_vec();
+        }
+        let dir = dir.unit_vec();
+        let cos_i = normal.dot(&dir).max(1e-6);
+        let cos_d = match (wo + dir).near_zero() {
+            true => 0.,
+            false => (wo + dir).unit_vec().dot(&dir),
+        };
+
+        let (fl, fv) = (schlick_weight(cos_i), schlick_weight(cos_o));
+        // Burley's diffuse, darker at grazing angles when smooth and brighter
+        // when rough
+        let fd90 = 0.5 + 2. * roughness * cos_d * cos_d;
+        let diffuse = (1. + (fd90 - 1.) * fl) * (1. + (fd90 - 1.) * fv);
+        // and the Hanrahan-Krueger inspired approximation of subsurface scattering
+        let fss90 = roughness * cos_d * cos_d;
+        let fss = (1. + (fss90 - 1.) * fl) * (1. + (fss90 - 1.) * fv);
+        let subsurface = 1.25 * (fss * (1. / (cos_i + cos_o) - 0.5) + 0.5);
+        let diffuse = base_color * lerp_float(diffuse, subsurface, self.subsurface.value(hit_info));
+
+        let sheen_color = lerp(Color(1., 1., 1.), tint, self.sheen_tint.value(hit_info));
+        let sheen = sheen_color * self.sheen.value(hit_info) * schlick_weight(cos_d) * consts::PI;
+
+        let through_layer = (Color(1., 1., 1.) - fresnel_o) / (1. - specular_chance);
+        let attenuation = (diffuse + sheen) * through_layer;
+        Some((hit_info.spawn_ray(dir, ray.time), attenuation))
+    }
+
+    fn albedo(&self, hit_info: &HitInfo) -> Color {
+        self.base_color.value(hit_info)
+    }
+
+    fn emitted(&self, hit_info: &HitInfo) -> Color {
+        self.emission.value(hit_info)
+    }
+}
+
+fn schlick_weight(cos: Float) -> Float {
+    (1. - cos).clamp(0., 1.).powi(5)
+}
+
+fn schlick(f0: Color, cos: Float) -> Color {
+    f0 + (Color(1., 1., 1.) - f0) * schlick_weight(cos)
+}
+
+fn lerp(a: Color, b: Color, t: Float) -> Color {
+    a * (1. - t) + b * t
+}
+
+fn lerp_float(a: Float, b: Float, t: Float) -> Float {
+    a * (1. - t) + b * t
+}
@@ -0,0 +1,83 @@
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    float::{random, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// Fog or smoke of the same density everywhere inside a closed boundary.
+/// Rays pass through it and scatter after a random distance, more likely to
+/// be short the denser it is. Usually given an `Isotropic` material.
+///
+/// The distance is drawn with `float::random`, not the camera's `Sampler`:
+/// the medium decides inside `Hit::hit`, which every shape shares and which
+/// has no sampler to draw from.
+pub struct ConstantMedium<'a, H: Hit> {
+    pub boundary: H,
+    neg_inv_density: Float,
+    pub phase_function: &'a (dyn Material + Sync),
+}
+
+impl<'a, H: Hit> ConstantMedium<'a, H> {
+    pub fn new(boundary: H, density: Float, phase_function: &'a (dyn Material + Sync)) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1. / density,
+            phase_function,
+        }
+    }
+}
+
+impl<H: Hit> Hit for ConstantMedium<'_, H> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        // where the whole line enters and leaves the boundary, which only works
+        // for convex boundaries
+        let entry = self
+            .boundary
+            .hit(ray, Float::NEG_INFINITY..Float::INFINITY)?;
+        let exit = self.boundary.hit(ray, entry.t..Float::INFINITY)?;
+
+        let t_start = entry.t.max(ray_t_interval.start);
+        let t_end = exit.t.min(ray_t_interval.end);
+        if t_start >= t_end {
+            return None;
+        }
+
+        // the distance travelled before scattering is exponentially distributed,
+        // random() stands in for the sampler, see above
+        let ray_length = ray.dir.length();
+        let distance_inside = (t_end - t_start) * ray_length;
+        let hit_distance = self.neg_inv_density * random().ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_start + hit_distance / ray_length;
+        Some(HitInfo {
+            pos: ray.at(t),
+            // nothing to avoid hitting again, so the scattered ray can start
+            // right there
+            pos_error: Vec3(0., 0., 0.),
+            // arbitrary, a medium has no surface
+            normal: Normal::new(Vec3(1., 0., 0.)),
+            geometric_normal: Normal::new(Vec3(1., 0., 0.)),
+            t,
+            front_face: true,
+            u: 0.,
+            v: 0.,
+            dpdu: Vec3(0., 0., 0.),
+            dpdv: Vec3(0., 0., 0.),
+            vertex_color: Color(1., 1., 1.),
+            object_id: 0,
+            mat: self.phase_function,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
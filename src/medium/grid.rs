@@ -0,0 +1,125 @@
+use crate::{float::Float, vec3::Vec3};
+use std::io::{self, BufRead, Read, Write};
+
+/// Densities on a regular 3D grid of voxels, stored x fastest, then y, then z.
+/// Sampled in grid space, the unit cube, with the values at the voxel centers.
+#[derive(Clone)]
+pub struct DensityGrid {
+    dims: (usize, usize, usize),
+    values: Vec<Float>,
+    max: Float,
+}
+
+impl DensityGrid {
+    /// Panics if the number of values doesn't match the dimensions
+    pub fn new(dims: (usize, usize, usize), values: Vec<Float>) -> Self {
+        assert_eq!(
+            values.len(),
+            dims.0 * dims.1 * dims.2,
+            "grid values don't match its dimensions"
+        );
+        let max = values.iter().copied().fold(0., Float::max);
+        Self { dims, values, max }
+    }
+
+    /// A grid of densities from a function of the voxel centers in grid space,
+    /// e.g. built from `Perlin` noise
+    pub fn from_fn(dims: (usize, usize, usize), density: impl Fn(Vec3) -> Float) -> Self {
+        let (nx, ny, nz) = dims;
+        let center = |i: usize, n: usize| (i as Float + 0.5) / n as Float;
+        let values = (0..nz)
+            .flat_map(|z| (0..ny).flat_map(move |y| (0..nx).map(move |x| (x, y, z))))
+            .map(|(x, y, z)| density(Vec3(center(x, nx), center(y, ny), center(z, nz))))
+            .collect();
+        Self::new(dims, values)
+    }
+
+    pub fn dims(&self) -> (usize, usize, usize) {
+        self.dims
+    }
+
+    /// The largest density in the grid, which no interpolated value exceeds
+    pub fn max(&self) -> Float {
+        self.max
+    }
+
+    fn get(&self, x: usize, y: usize, z: usize) -> Float {
+        self.values[(z * self.dims.1 + y) * self.dims.0 + x]
+    }
+
+    /// Trilinearly interpolated density at a point in grid space. Points
+    /// outside get the value at the nearest edge.
+    pub fn sample(&self, p: &Vec3) -> Float {
+        // index of the voxel below and how far towards the next one
+        let axis = |p: Float, n: usize| {
+            let x = (p * n as Float - 0.5).clamp(0., (n - 1) as Float);
+            let i = (x as usize).min(n - 1);
+            (i, (i + 1).min(n - 1), x - i as Float)
+        };
+        let (x0, x1, fx) = axis(p.x(), self.dims.0);
+        let (y0, y1, fy) = axis(p.y(), self.dims.1);
+        let (z0, z1, fz) = axis(p.z(), self.dims.2);
+
+        let lerp = |a: Float, b: Float, f: Float| a + (b - a) * f;
+        let plane = |z: usize| {
+            lerp(
+                lerp(self.get(x0, y0, z), self.get(x1, y0, z), fx),
+                lerp(self.get(x0, y1, z), self.get(x1, y1, z), fx),
+                fy,
+            )
+        };
+        lerp(plane(z0), plane(z1), fz)
+    }
+
+    /// Reads a grid in the same style as PFM: a `VOL` line, a line with the
+    /// x, y and z resolution, then the densities as little-endian f32s, x fastest
+    pub fn read(input: impl Read) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut input = io::BufReader::new(input);
+
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        if line.trim() != "VOL" {
+            return Err(invalid("not a VOL file"));
+        }
+        line.clear();
+        input.read_line(&mut line)?;
+        let dims: Vec<usize> = line
+            .split_whitespace()
+            .map(|n| n.parse().map_err(|_| invalid("bad VOL dimensions")))
+            .collect::<io::Result<_>>()?;
+        let &[nx, ny, nz] = dims.as_slice() else {
+            return Err(invalid("VOL dimensions need x, y and z"));
+        };
+        if nx == 0 || ny == 0 || nz == 0 {
+            return Err(invalid("empty VOL grid"));
+        }
+
+        let mut bytes = vec![0; nx * ny * nz * 4];
+        input.read_exact(&mut bytes)?;
+        let values: Vec<Float> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as Float)
+            .collect();
+        if values.iter().any(|v| !(v.is_finite() && *v >= 0.)) {
+            return Err(invalid("VOL densities must be finite and not negative"));
+        }
+        Ok(Self::new((nx, ny, nz), values))
+    }
+
+    /// Writes the grid in the format `read` expects
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut out = io::BufWriter::new(out);
+        write!(
+            out,
+            "VOL\n{} {} {}\n",
+            self.dims.0, self.dims.1, self.dims.2
+        )?;
+        for value in &self.values {
+            // already f32 with the f32 feature
+            #[allow(clippy::unnecessary_cast)]
+            out.write_all(&(*value as f32).to_le_bytes())?;
+        }
+        out.flush()
+    }
+}
@@ -0,0 +1,200 @@
+use super::DensityGrid;
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    float::{random, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// A participating medium whose density varies, given by a `DensityGrid`
+/// stretched over a box. Move or rotate it with `Transformed`.
+///
+/// Free flights are sampled by delta tracking: steps as if the whole box had
+/// the grid's largest density, and each step only counts as a real collision
+/// with probability density / largest density. That's unbiased without ever
+/// integrating the density along the ray. A flight takes as many random
+/// numbers as it takes steps, so they come from `float::random` rather than
+/// the camera's `Sampler`, whose dimensions are fixed per bounce and which
+/// `Hit::hit` has no access to anyway.
+///
+/// `transmittance` estimates how much light gets through by ratio tracking.
+/// The camera doesn't call it: it follows paths without shadow rays, so
+/// rendering only ever uses delta tracking. It's there for visibility
+/// queries, e.g. light sampling through the volume.
+pub struct GridMedium<'a> {
+    pub grid: DensityGrid,
+    pub bounds: Aabb,
+    // multiplies every density in the grid
+    pub density_scale: Float,
+    pub phase_function: &'a (dyn Material + Sync),
+}
+
+impl<'a> GridMedium<'a> {
+    pub fn new(
+        grid: DensityGrid,
+        bounds: Aabb,
+        density_scale: Float,
+        phase_function: &'a (dyn Material + Sync),
+    ) -> Self {
+        Self {
+            grid,
+            bounds,
+            density_scale,
+            phase_function,
+        }
+    }
+
+    pub fn density_at(&self, pos: &Pos) -> Float {
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        let grid_pos = Vec3(
+            (pos.x() - min.x()) / (max.x() - min.x()),
+            (pos.y() - min.y()) / (max.y() - min.y()),
+            (pos.z() - min.z()) / (max.z() - min.z()),
+        );
+        self.grid.sample(&grid_pos) * self.density_scale
+    }
+
+    // the density used for the tracking steps, at least every density in the box
+    fn majorant(&self) -> Float {
+        self.grid.max() * self.density_scale
+    }
+
+    /// Fraction of light that makes it through the medium along the ray over
+    /// the interval, estimated by ratio tracking: the same steps as delta
+    /// tracking, but instead of stopping at a collision every step keeps the
+    /// chance it wasn't one. Gives shadows through the volume without noise
+    /// from stopping.
+    pub fn transmittance(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Float {
+        let Some(range) = self.bounds.ray_interval(ray, ray_t_interval) else {
+            return 1.;
+        };
+        let majorant = self.majorant();
+        if majorant <= 0. {
+            return 1.;
+        }
+
+        let step_scale = 1. / (majorant * ray.dir.length());
+        let mut transmittance = 1.;
+        let mut t = range.start;
+        loop {
+            t -= (1. - random()).ln() * step_scale;
+            if t >= range.end {
+                return transmittance;
+            }
+            transmittance *= 1. - self.density_at(&ray.at(t)) / majorant;
+        }
+    }
+}
+
+impl Hit for GridMedium<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let range = self.bounds.ray_interval(ray, ray_t_interval)?;
+        let majorant = self.majorant();
+        if majorant <= 0. {
+            return None;
+        }
+
+        // delta tracking, with exponential steps in t
+        let step_scale = 1. / (majorant * ray.dir.length());
+        let mut t = range.start;
+        loop {
+            t -= (1. - random()).ln() * step_scale;
+            if t >= range.end {
+                return None;
+            }
+            let pos = ray.at(t);
+            if random() * majorant < self.density_at(&pos) {
+                return Some(HitInfo {
+                    pos,
+                    // like ConstantMedium, nothing to avoid hitting again
+                    pos_error: Vec3(0., 0., 0.),
+                    normal: Normal::new(Vec3(1., 0., 0.)),
+                    geometric_normal: Normal::new(Vec3(1., 0., 0.)),
+                    t,
+                    front_face: true,
+                    u: 0.,
+                    v: 0.,
+                    dpdu: Vec3(0., 0., 0.),
+                    dpdv: Vec3(0., 0., 0.),
+                    vertex_color: Color(1., 1., 1.),
+                    object_id: 0,
+                    mat: self.phase_function,
+                });
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Isotropic;
+
+    const FOG: Isotropic = Isotropic {
+        albedo: Color(1., 1., 1.),
+    };
+
+    // through the middle of the box along x, entering at x = 0
+    fn across(y: Float, z: Float) -> Ray {
+        Ray {
+            origin: Pos(-1., y, z),
+            dir: Vec3(2., 0., 0.),
+            time: 0.,
+        }
+    }
+
+    fn unit_box(grid: DensityGrid, density_scale: Float) -> GridMedium<'static> {
+        let bounds = Aabb::new(Pos(0., 0., 0.), Pos(1., 1., 1.));
+        GridMedium::new(grid, bounds, density_scale, &FOG)
+    }
+
+    fn mean(n: usize, estimate: impl Fn() -> Float) -> Float {
+        (0..n).map(|_| estimate()).sum::<Float>() / n as Float
+    }
+
+    #[test]
+    fn uniform_transmittance_is_beer_lambert() {
+        fastrand::seed(1);
+        let medium = unit_box(DensityGrid::new((2, 2, 2), vec![1.; 8]), 1.5);
+        let ray = across(0.5, 0.5);
+        let expected = (-1.5 as Float).exp();
+        let ratio = mean(20_000, || medium.transmittance(&ray, 0.0..Float::INFINITY));
+        assert!((ratio - expected).abs() < 0.01, "{ratio} {expected}");
+        // and half way through, where the ray only went through half of it
+        let half = mean(20_000, || medium.transmittance(&ray, 0.0..0.75));
+        assert!((half - (-0.75 as Float).exp()).abs() < 0.01, "{half}");
+
+        // outside the box or without density all of it gets through
+        assert_eq!(
+            medium.transmittance(&across(2., 0.5), 0.0..Float::INFINITY),
+            1.
+        );
+        let empty = unit_box(DensityGrid::new((1, 1, 1), vec![0.]), 1.);
+        assert_eq!(empty.transmittance(&ray, 0.0..Float::INFINITY), 1.);
+    }
+
+    #[test]
+    fn ratio_tracking_agrees_with_delta_tracking() {
+        fastrand::seed(2);
+        // thick at one end, clear at the other
+        let grid = DensityGrid::from_fn((8, 8, 8), |p| 4. * p.x() * p.y());
+        let medium = unit_box(grid, 1.);
+        for y in [0.1, 0.5, 0.9] {
+            let ray = across(y, 0.5);
+            let ratio = mean(20_000, || medium.transmittance(&ray, 0.0..Float::INFINITY));
+            let escaped = mean(20_000, || match medium.hit(&ray, 0.0..Float::INFINITY) {
+                Some(_) => 0.,
+                None => 1.,
+            });
+            assert!((ratio - escaped).abs() < 0.02, "{y}: {ratio} {escaped}");
+        }
+    }
+}
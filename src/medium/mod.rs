@@ -0,0 +1,9 @@
+mod constant;
+mod grid;
+mod grid_medium;
+mod noise;
+
+pub use constant::*;
+pub use grid::*;
+pub use grid_medium::*;
+pub use noise::*;
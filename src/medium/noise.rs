@@ -0,0 +1,82 @@
+use crate::{
+    float::{random, Float},
+    vec3::Vec3,
+};
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise, smooth and random looking, for procedural
+/// densities. Repeats every 256 units. Seed fastrand before `new` for the same
+/// noise every run.
+pub struct Perlin {
+    gradients: [Vec3; POINT_COUNT],
+    perm_x: [usize; POINT_COUNT],
+    perm_y: [usize; POINT_COUNT],
+    perm_z: [usize; POINT_COUNT],
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Perlin {
+    pub fn new() -> Self {
+        let permutation = || {
+            let mut perm = std::array::from_fn(|i| i);
+            fastrand::shuffle(&mut perm);
+            perm
+        };
+        Self {
+            gradients: std::array::from_fn(|_| Vec3::unit_vec_from_sample((random(), random()))),
+            perm_x: permutation(),
+            perm_y: permutation(),
+            perm_z: permutation(),
+        }
+    }
+
+    /// Noise at p, roughly in [-1, 1]
+    pub fn noise(&self, p: &Vec3) -> Float {
+        let (fx, fy, fz) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (u, v, w) = (p.x() - fx, p.y() - fy, p.z() - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        // hermite smoothing hides the grid
+        let smooth = |t: Float| t * t * (3. - 2. * t);
+        let (su, sv, sw) = (smooth(u), smooth(v), smooth(w));
+
+        let mut sum = 0.;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let wrap = |n: i64, d: i64| ((n + d) & (POINT_COUNT as i64 - 1)) as usize;
+                    let gradient = self.gradients[self.perm_x[wrap(i, di)]
+                        ^ self.perm_y[wrap(j, dj)]
+                        ^ self.perm_z[wrap(k, dk)]];
+                    let (di, dj, dk) = (di as Float, dj as Float, dk as Float);
+                    let weight = Vec3(u - di, v - dj, w - dk);
+                    sum += (di * su + (1. - di) * (1. - su))
+                        * (dj * sv + (1. - dj) * (1. - sv))
+                        * (dk * sw + (1. - dk) * (1. - sw))
+                        * gradient.dot(&weight);
+                }
+            }
+        }
+        sum
+    }
+
+    /// Noise summed over `depth` octaves, each twice the frequency and half
+    /// the weight of the last, made positive. Looks like smoke or marble
+    pub fn turbulence(&self, p: &Vec3, depth: u32) -> Float {
+        let mut sum = 0.;
+        let mut p = *p;
+        let mut weight = 1.;
+        for _ in 0..depth {
+            sum += weight * self.noise(&p);
+            weight *= 0.5;
+            p *= 2.;
+        }
+        sum.abs()
+    }
+}
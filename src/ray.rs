@@ -1,12 +1,21 @@
-use crate::vec3::*;
+use crate::{float::Float, vec3::*};
 
 pub struct Ray {
     pub origin: Pos,
     pub dir: Vec3,
+    // the moment the ray was sent, somewhere between shutter open and close
+    pub time: Float,
+}
+
+/// How far along a motion from time0 to time1 is at `time`, 0 before it
+/// starts and 1 after it ends, so bounding boxes of the start and end poses
+/// cover any time
+pub fn motion_progress(time: Float, time0: Float, time1: Float) -> Float {
+    ((time - time0) / (time1 - time0)).clamp(0., 1.)
 }
 
 impl Ray {
-    pub fn at(&self, t: f64) -> Pos {
+    pub fn at(&self, t: Float) -> Pos {
         self.origin + self.dir * t
     }
 }
@@ -0,0 +1,68 @@
+use super::{hash, hash_to_float, Sampler};
+use crate::float::{random, Float, ONE_MINUS_EPSILON};
+
+// one base per dimension
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// The Halton sequence, dimension d being the radical inverse of the sample
+/// index in the d-th prime base. Each pixel shifts every dimension by its own
+/// random offset (Cranley-Patterson rotation) so pixels don't share a pattern.
+/// Dimensions past the 32nd fall back to independent random numbers, by then
+/// the bases are large enough that the sequence is barely better anyway.
+#[derive(Default)]
+pub struct HaltonSampler {
+    pixel_seed: u64,
+    index: u64,
+    dimension: usize,
+}
+
+impl HaltonSampler {
+    fn next(&mut self) -> Float {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        match PRIMES.get(dimension) {
+            Some(&base) => {
+                let shift = hash_to_float(hash(&[self.pixel_seed, dimension as u64]));
+                (radical_inverse(base, self.index) + shift).fract()
+            }
+            None => random(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.pixel_seed = hash(&[pixel.0, pixel.1]);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        self.next()
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        (self.next(), self.next())
+    }
+}
+
+/// Mirrors the digits of `index` in `base` around the radix point
+fn radical_inverse(base: u64, mut index: u64) -> Float {
+    let inv_base = 1. / base as Float;
+    let mut reversed = 0;
+    let mut inv_base_n = 1.;
+
+    while index > 0 {
+        let next = index / base;
+        let digit = index - next * base;
+        reversed = reversed * base + digit;
+        inv_base_n *= inv_base;
+        index = next;
+    }
+
+    (reversed as Float * inv_base_n).min(ONE_MINUS_EPSILON)
+}
@@ -0,0 +1,17 @@
+use super::Sampler;
+use crate::float::{random, Float};
+
+/// Plain uniform random numbers for every dimension
+pub struct IndependentSampler;
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, _pixel: (u64, u64), _index: u64) {}
+
+    fn get_1d(&mut self) -> Float {
+        random()
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        (random(), random())
+    }
+}
@@ -0,0 +1,103 @@
+mod halton;
+mod independent;
+mod sobol;
+mod stratified;
+
+pub use halton::*;
+pub use independent::*;
+pub use sobol::*;
+pub use stratified::*;
+
+use crate::float::{Float, ONE_MINUS_EPSILON};
+
+/// Source of the random numbers for a path.
+///
+/// Each camera sample consumes dimensions in a fixed order: two for the
+/// position in the pixel, two for the position on the lens, then whatever
+/// each bounce's material asks for. Samplers other than `IndependentSampler`
+/// spread the values of each dimension evenly over the samples of a pixel,
+/// which converges faster than independent random numbers.
+pub trait Sampler {
+    /// Starts sample number `index` of a pixel, going back to the first dimension
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64);
+
+    /// The next dimension, in [0, 1)
+    fn get_1d(&mut self) -> Float;
+
+    /// The next two dimensions, in [0, 1)^2
+    fn get_2d(&mut self) -> (Float, Float);
+}
+
+/// Which sampler a render uses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplerKind {
+    #[default]
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    pub const ALL: [SamplerKind; 4] = [
+        SamplerKind::Independent,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplerKind::Independent => "independent",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+        }
+    }
+
+    /// A fresh sampler, one is needed per thread
+    pub fn create(&self, samples_per_pixel: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::default()),
+            SamplerKind::Sobol => Box::new(SobolSampler::default()),
+        }
+    }
+}
+
+impl std::str::FromStr for SamplerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SamplerKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = SamplerKind::ALL.iter().map(SamplerKind::name).collect();
+                format!(
+                    "unknown sampler '{s}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+// hashes some values into well mixed bits, used to decorrelate pixels and dimensions
+fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e3779b97f4a7c15, |acc, &value| {
+        mix_bits(acc ^ mix_bits(value))
+    })
+}
+
+// splitmix64 finalizer
+fn mix_bits(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+// top 53 bits of a hash as a float in [0, 1)
+fn hash_to_float(hash: u64) -> Float {
+    ((hash >> 11) as Float / (1u64 << 53) as Float).min(ONE_MINUS_EPSILON)
+}
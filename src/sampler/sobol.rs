@@ -0,0 +1,86 @@
+use super::{hash, Sampler};
+use crate::float::{Float, ONE_MINUS_EPSILON};
+
+/// Owen-scrambled Sobol points, padded to any number of dimensions.
+///
+/// Every 1D or 2D request uses the first two Sobol dimensions, which are well
+/// stratified together. The sample index is shuffled and the result is
+/// scrambled with seeds from the pixel and dimension, so separate requests are
+/// uncorrelated with each other (Burley, "Practical Hash-based Owen Scrambling").
+/// Works best with a power of two samples per pixel.
+#[derive(Default)]
+pub struct SobolSampler {
+    pixel_seed: u64,
+    index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    // the shuffled sample index and the two scramble seeds for the next request
+    fn next(&mut self) -> (u32, u32, u32) {
+        let seed = hash(&[self.pixel_seed, self.dimension]);
+        self.dimension += 1;
+
+        let index = nested_uniform_scramble(self.index, seed as u32);
+        (index, (seed >> 32) as u32, hash(&[seed]) as u32)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.pixel_seed = hash(&[pixel.0, pixel.1]);
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let (index, seed, _) = self.next();
+        to_float(nested_uniform_scramble(sobol_0(index), seed))
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        let (index, seed_x, seed_y) = self.next();
+        (
+            to_float(nested_uniform_scramble(sobol_0(index), seed_x)),
+            to_float(nested_uniform_scramble(sobol_1(index), seed_y)),
+        )
+    }
+}
+
+// first Sobol dimension, the van der Corput sequence
+fn sobol_0(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+// second Sobol dimension, its generator matrix is Pascal's triangle mod 2
+fn sobol_1(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+// Owen scrambling: flips each bit depending on a hash of all the bits above it
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+// hash where each bit only depends on the bits below it
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+fn to_float(x: u32) -> Float {
+    (x as Float / (1u64 << 32) as Float).min(ONE_MINUS_EPSILON)
+}
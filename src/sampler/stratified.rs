@@ -0,0 +1,101 @@
+use super::{hash, Sampler};
+use crate::float::{random, Float};
+
+/// Jittered stratification: each dimension is split into one stratum per
+/// pixel sample (a grid of strata for 2D), and every sample of the pixel gets
+/// a random point in a different stratum. Which sample gets which stratum is
+/// shuffled per pixel and dimension so the dimensions stay uncorrelated.
+pub struct StratifiedSampler {
+    samples_per_pixel: u64,
+    pixel_seed: u64,
+    index: u64,
+    dimension: u64,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u64) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            pixel_seed: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    // this sample's stratum out of `strata`, shuffled for the current dimension
+    fn stratum(&mut self, strata: u64) -> u64 {
+        let seed = hash(&[self.pixel_seed, self.dimension]);
+        permutation_element(self.index % strata, strata, seed)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel: (u64, u64), index: u64) {
+        self.pixel_seed = hash(&[pixel.0, pixel.1]);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> Float {
+        let strata = self.samples_per_pixel;
+        let stratum = self.stratum(strata);
+        self.dimension += 1;
+
+        (stratum as Float + random()) / strata as Float
+    }
+
+    fn get_2d(&mut self) -> (Float, Float) {
+        // as square a grid as possible with at least one stratum per sample
+        let x_strata = (self.samples_per_pixel as Float).sqrt().ceil() as u64;
+        let y_strata = self.samples_per_pixel.div_ceil(x_strata);
+        let stratum = self.stratum(x_strata * y_strata);
+        self.dimension += 2;
+
+        (
+            ((stratum % x_strata) as Float + random()) / x_strata as Float,
+            ((stratum / x_strata) as Float + random()) / y_strata as Float,
+        )
+    }
+}
+
+/// Element `i` of a pseudo-random permutation of 0..len chosen by `seed`,
+/// without storing the permutation (Kensler, "Correlated Multi-Jittered Sampling")
+fn permutation_element(i: u64, len: u64, seed: u64) -> u64 {
+    let (mut i, l, p) = (i as u32, len as u32, seed as u32);
+
+    // smallest all-ones mask covering len - 1
+    let mut w = l.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    // cycle walk until the hashed index lands back in range
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+
+        if i < l {
+            break;
+        }
+    }
+
+    (i.wrapping_add(p) % l) as u64
+}
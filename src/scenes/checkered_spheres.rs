@@ -0,0 +1,28 @@
+use crate::{
+    camera::CameraBuilder, color::Color, hit::*, materials::Principled, shapes::Sphere,
+    texture::Checker, vec3::*,
+};
+
+pub fn checkered_spheres<R>(
+    camera: CameraBuilder,
+    f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R,
+) -> R {
+    let checker = Principled::new(Checker::new(
+        0.32,
+        Color(0.2, 0.3, 0.1),
+        Color(0.9, 0.9, 0.9),
+    ))
+    .with_specular(0.);
+
+    let mut world = HitList::default();
+    world.push(Sphere::new(Pos(0., -10., 0.), 10., &checker));
+    world.push(Sphere::new(Pos(0., 10., 0.), 10., &checker));
+
+    let camera = camera
+        .with_vfov_degrees(20.)
+        .with_lookfrom(Pos(13., 2., 3.))
+        .with_lookat(Pos(0., 0., 0.))
+        .with_vup(Vec3(0., 1., 0.))
+        .with_defocus_angle(0.);
+    f(&world, camera)
+}
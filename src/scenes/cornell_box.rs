@@ -0,0 +1,117 @@
+use crate::{
+    camera::CameraBuilder,
+    color::Color,
+    hit::*,
+    materials::*,
+    medium::ConstantMedium,
+    shapes::{Cuboid, Quad},
+    transform::Transformed,
+    vec3::*,
+};
+
+pub fn cornell_box<R>(
+    camera: CameraBuilder,
+    f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R,
+) -> R {
+    let walls = Walls::new();
+    let light = DiffuseLight::new(Color(15., 15., 15.));
+
+    let mut world = HitList::default();
+    walls.push_into(&mut world);
+    world.push(Quad::new(
+        Pos(343., 554., 332.),
+        Vec3(-130., 0., 0.),
+        Vec3(0., 0., -105.),
+        &light,
+    ));
+    world.push(tall_box(&walls.white));
+    world.push(short_box(&walls.white));
+
+    f(&world, view(camera))
+}
+
+/// The Cornell box with its boxes made of smoke, one dark and one light,
+/// under a bigger and dimmer light
+pub fn cornell_smoke<R>(
+    camera: CameraBuilder,
+    f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R,
+) -> R {
+    let walls = Walls::new();
+    let light = DiffuseLight::new(Color(7., 7., 7.));
+    let dark = Isotropic {
+        albedo: Color(0., 0., 0.),
+    };
+    let bright = Isotropic {
+        albedo: Color(1., 1., 1.),
+    };
+
+    let mut world = HitList::default();
+    walls.push_into(&mut world);
+    world.push(Quad::new(
+        Pos(113., 554., 127.),
+        Vec3(330., 0., 0.),
+        Vec3(0., 0., 305.),
+        &light,
+    ));
+    world.push(ConstantMedium::new(tall_box(&walls.white), 0.01, &dark));
+    world.push(ConstantMedium::new(short_box(&walls.white), 0.01, &bright));
+
+    f(&world, view(camera))
+}
+
+struct Walls {
+    red: Lambertian,
+    white: Lambertian,
+    green: Lambertian,
+}
+
+impl Walls {
+    fn new() -> Self {
+        Self {
+            red: Lambertian {
+                albedo: Color(0.65, 0.05, 0.05),
+            },
+            white: Lambertian {
+                albedo: Color(0.73, 0.73, 0.73),
+            },
+            green: Lambertian {
+                albedo: Color(0.12, 0.45, 0.15),
+            },
+        }
+    }
+
+    // a 555 unit room open towards -z, facing in
+    fn push_into<'a>(&'a self, world: &mut HitList<'a>) {
+        let (x, y, z) = (Vec3(555., 0., 0.), Vec3(0., 555., 0.), Vec3(0., 0., 555.));
+        world.push(Quad::new(Pos(555., 0., 0.), z, y, &self.green));
+        world.push(Quad::new(Pos(0., 0., 0.), y, z, &self.red));
+        world.push(Quad::new(Pos(0., 0., 0.), z, x, &self.white));
+        world.push(Quad::new(Pos(555., 555., 555.), -z, -x, &self.white));
+        world.push(Quad::new(Pos(0., 0., 555.), y, x, &self.white));
+    }
+}
+
+fn tall_box(mat: &Lambertian) -> Transformed<Cuboid<'_>> {
+    Transformed::new(
+        Cuboid::new(Pos(0., 0., 0.), Pos(165., 330., 165.), mat),
+        Mat4::translation(Vec3(265., 0., 295.)) * Mat4::rotation_degrees(Vec3(0., 1., 0.), 15.),
+    )
+}
+
+fn short_box(mat: &Lambertian) -> Transformed<Cuboid<'_>> {
+    Transformed::new(
+        Cuboid::new(Pos(0., 0., 0.), Pos(165., 165., 165.), mat),
+        Mat4::translation(Vec3(130., 0., 65.)) * Mat4::rotation_degrees(Vec3(0., 1., 0.), -18.),
+    )
+}
+
+fn view(camera: CameraBuilder) -> CameraBuilder {
+    camera
+        .with_aspect_ratio(1.)
+        .with_vfov_degrees(40.)
+        .with_lookfrom(Pos(278., 278., -800.))
+        .with_lookat(Pos(278., 278., 0.))
+        .with_vup(Vec3(0., 1., 0.))
+        .with_defocus_angle(0.)
+        .with_background(Color(0., 0., 0.))
+}
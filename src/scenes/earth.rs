@@ -0,0 +1,103 @@
+use crate::{
+    camera::CameraBuilder,
+    color::Color,
+    float::{consts, Float},
+    hit::*,
+    import::read_png,
+    materials::Principled,
+    medium::Perlin,
+    shapes::Sphere,
+    texture::{ImageTexture, Texture},
+    tone_map::srgb_eotf,
+    vec3::*,
+};
+use std::{fs, io};
+
+// the book's earth map, read from the working directory. Only png can be
+// read, so the book's jpg has to be converted first.
+const EARTH_MAP: &str = "earthmap.png";
+
+/// The book's globe, painted with `earthmap.png` from the working directory,
+/// or with a planet made from noise if there's no such file
+pub fn earth<R>(camera: CameraBuilder, f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R) -> R {
+    let surface = earth_surface();
+    let globe = Sphere::new(Pos(0., 0., 0.), 2., &surface);
+
+    let camera = camera
+        .with_vfov_degrees(20.)
+        .with_lookfrom(Pos(0., 0., 12.))
+        .with_lookat(Pos(0., 0., 0.))
+        .with_vup(Vec3(0., 1., 0.))
+        .with_defocus_angle(0.);
+    f(&globe, camera)
+}
+
+/// The earth map if there is one, the procedural planet if not
+pub(super) fn earth_surface() -> Principled {
+    let surface = match earth_map() {
+        Ok(map) => Principled::new(map),
+        Err(err) => {
+            // a missing map is expected, a broken one is worth knowing about
+            if err.kind() != io::ErrorKind::NotFound {
+                eprintln!("Couldn't read {EARTH_MAP}, using a made up planet: {err}");
+            }
+            Principled::new(Planet::new())
+        }
+    };
+    surface.with_specular(0.)
+}
+
+fn earth_map() -> io::Result<ImageTexture> {
+    let mut image = read_png(&fs::read(EARTH_MAP)?)?.color;
+    // stored as sRGB
+    for pixel in image.pixels_mut() {
+        *pixel = Color(srgb_eotf(pixel.0), srgb_eotf(pixel.1), srgb_eotf(pixel.2));
+    }
+    Ok(ImageTexture::new(image))
+}
+
+/// Oceans, continents and ice caps, laid out by the sphere's uv so it moves
+/// with the sphere
+pub(super) struct Planet {
+    noise: Perlin,
+}
+
+impl Planet {
+    pub(super) fn new() -> Self {
+        Self {
+            noise: Perlin::new(),
+        }
+    }
+}
+
+impl Texture<Color> for Planet {
+    fn value(&self, hit_info: &HitInfo) -> Color {
+        // back from uv to a point on the unit sphere, so the noise doesn't
+        // stretch at the poles or tear at the seam
+        let (theta, phi) = (hit_info.v * consts::PI, hit_info.u * 2. * consts::PI);
+        let p = Vec3(
+            theta.sin() * phi.cos(),
+            -theta.cos(),
+            theta.sin() * phi.sin(),
+        );
+
+        let height: Float = [(1.5, 1.), (3., 0.5), (6., 0.25), (12., 0.125)]
+            .iter()
+            .map(|(frequency, weight)| weight * self.noise.noise(&(p * *frequency)))
+            .sum();
+        let latitude = p.y().abs();
+        let blend = |a: Color, b: Color, t: Float| a * (1. - t) + b * t;
+
+        if latitude + 0.1 * height > 0.88 {
+            Color(0.8, 0.82, 0.85)
+        } else if height < 0.05 {
+            let depth = ((0.05 - height) * 3.).min(1.);
+            blend(Color(0.05, 0.2, 0.35), Color(0.01, 0.04, 0.15), depth)
+        } else {
+            let (forest, desert) = (Color(0.08, 0.25, 0.05), Color(0.45, 0.35, 0.2));
+            // drier away from the equator and coasts
+            let dryness = ((height - 0.05) * 3. + (1. - latitude) * 0.3).min(1.);
+            blend(forest, desert, dryness)
+        }
+    }
+}
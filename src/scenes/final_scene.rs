@@ -0,0 +1,158 @@
+use super::earth::earth_surface;
+use crate::{
+    camera::CameraBuilder,
+    color::Color,
+    float::{random, Float},
+    hit::*,
+    materials::*,
+    medium::ConstantMedium,
+    shapes::{Mesh, Quad, Sphere, SphereSet, TriangleMesh},
+    texture::Marble,
+    transform::Transformed,
+    vec3::*,
+};
+
+/// Book two's cover: a floor of boxes, a moving sphere, glass, metal, fog,
+/// subsurface-like blue glass, a planet, marble and a rotated cluster of a
+/// thousand spheres
+pub fn final_scene<R>(
+    camera: CameraBuilder,
+    f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R,
+) -> R {
+    let ground = Lambertian {
+        albedo: Color(0.48, 0.83, 0.53),
+    };
+    let light = DiffuseLight::new(Color(7., 7., 7.));
+    let moving = Lambertian {
+        albedo: Color(0.7, 0.3, 0.1),
+    };
+    let glass = Dialectric::new(1.5);
+    let metal = Metal {
+        albedo: Color(0.8, 0.8, 0.9),
+        fuzz: 1.0,
+    };
+    let blue = Isotropic {
+        albedo: Color(0.2, 0.4, 0.9),
+    };
+    let mist = Isotropic {
+        albedo: Color(1., 1., 1.),
+    };
+    let planet = earth_surface();
+    let marble = Principled::new(Marble::new(0.2)).with_specular(0.);
+    let white = Lambertian {
+        albedo: Color(0.73, 0.73, 0.73),
+    };
+
+    // 400 boxes of random height in one mesh, much faster to hit than as
+    // separate objects
+    let floor = {
+        let (mut positions, mut triangles) = (Vec::new(), Vec::new());
+        let boxes_per_side = 20;
+        for i in 0..boxes_per_side {
+            for j in 0..boxes_per_side {
+                let w = 100.;
+                let x0 = -1000. + i as Float * w;
+                let z0 = -1000. + j as Float * w;
+                let y1 = 1. + 100. * random();
+                push_box(
+                    Pos(x0, 0., z0),
+                    Pos(x0 + w, y1, z0 + w),
+                    &mut positions,
+                    &mut triangles,
+                );
+            }
+        }
+        TriangleMesh::new(positions, triangles)
+    };
+
+    let cluster = SphereSet::new(
+        (0..1000)
+            .map(|_| {
+                let center = Pos(165. * random(), 165. * random(), 165. * random());
+                Sphere::new(center, 10., &white)
+            })
+            .collect(),
+    );
+
+    let mut world = HitList::default();
+    world.push(Mesh::new(&floor, &ground));
+    world.push(Quad::new(
+        Pos(123., 554., 147.),
+        Vec3(300., 0., 0.),
+        Vec3(0., 0., 265.),
+        &light,
+    ));
+    world.push(Sphere::moving(
+        Pos(400., 400., 200.),
+        Pos(430., 400., 200.),
+        50.,
+        &moving,
+    ));
+    world.push(Sphere::new(Pos(260., 150., 45.), 50., &glass));
+    world.push(Sphere::new(Pos(0., 150., 145.), 50., &metal));
+
+    // glass with blue fog inside
+    world.push(Sphere::new(Pos(360., 150., 145.), 70., &glass));
+    world.push(ConstantMedium::new(
+        Sphere::new(Pos(360., 150., 145.), 70., &glass),
+        0.2,
+        &blue,
+    ));
+    // thin mist over everything
+    world.push(ConstantMedium::new(
+        Sphere::new(Pos(0., 0., 0.), 5000., &glass),
+        0.0001,
+        &mist,
+    ));
+
+    world.push(Sphere::new(Pos(400., 200., 400.), 100., &planet));
+    world.push(Sphere::new(Pos(220., 280., 300.), 80., &marble));
+    world.push(Transformed::new(
+        cluster,
+        Mat4::translation(Vec3(-100., 270., 395.)) * Mat4::rotation_degrees(Vec3(0., 1., 0.), 15.),
+    ));
+
+    let camera = camera
+        .with_aspect_ratio(1.)
+        .with_vfov_degrees(40.)
+        .with_lookfrom(Pos(478., 278., -600.))
+        .with_lookat(Pos(278., 278., 0.))
+        .with_vup(Vec3(0., 1., 0.))
+        .with_defocus_angle(0.)
+        .with_shutter(0., 1.)
+        .with_background(Color(0., 0., 0.));
+    f(&world, camera)
+}
+
+// corners as bits: x in 1, y in 2 and z in 4, each face going around its
+// corners with the way it faces out
+const BOX_FACES: [([usize; 4], Vec3); 6] = [
+    ([0, 2, 6, 4], Vec3(-1., 0., 0.)),
+    ([1, 3, 7, 5], Vec3(1., 0., 0.)),
+    ([0, 1, 5, 4], Vec3(0., -1., 0.)),
+    ([2, 3, 7, 6], Vec3(0., 1., 0.)),
+    ([0, 1, 3, 2], Vec3(0., 0., -1.)),
+    ([4, 5, 7, 6], Vec3(0., 0., 1.)),
+];
+
+// adds an axis aligned box from min to max to a mesh, facing out
+fn push_box(min: Pos, max: Pos, positions: &mut Vec<Pos>, triangles: &mut Vec<[u32; 3]>) {
+    let first = positions.len() as u32;
+    positions.extend((0..8).map(|i| {
+        Pos(
+            if i & 1 == 0 { min.0 } else { max.0 },
+            if i & 2 == 0 { min.1 } else { max.1 },
+            if i & 4 == 0 { min.2 } else { max.2 },
+        )
+    }));
+
+    for (corners, out) in BOX_FACES {
+        let [a, b, c, d] = corners.map(|i| first + i as u32);
+        let p = |i: u32| positions[i as usize];
+        let facing = (p(b) - p(a)).cross(&(p(c) - p(a))).dot(&out);
+        match facing > 0. {
+            true => triangles.extend([[a, b, c], [a, c, d]]),
+            false => triangles.extend([[a, c, b], [a, d, c]]),
+        }
+    }
+}
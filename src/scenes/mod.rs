@@ -0,0 +1,129 @@
+//! The canonical scenes of the Ray Tracing in One Weekend books, picked by
+//! name. A scene's world borrows materials that live while it is being
+//! built, so instead of being returned it is handed to a callback along with
+//! a camera looking at it.
+
+mod checkered_spheres;
+mod cornell_box;
+mod earth;
+mod final_scene;
+mod perlin_spheres;
+mod random_spheres;
+
+pub use checkered_spheres::*;
+pub use cornell_box::*;
+pub use earth::*;
+pub use final_scene::*;
+pub use perlin_spheres::*;
+pub use random_spheres::*;
+
+use crate::{camera::CameraBuilder, hit::Hit};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scene {
+    /// The cover of book one, lots of small random spheres around three big
+    /// ones
+    #[default]
+    RandomSpheres,
+    /// Two boxes in a red and green room lit from the ceiling
+    CornellBox,
+    /// Two big checkered spheres touching
+    CheckeredSpheres,
+    /// A marble sphere on a marble ground
+    PerlinSpheres,
+    /// A globe painted with `earthmap.png` from the working directory, or a
+    /// made up planet without one
+    Earth,
+    /// The Cornell box with the boxes made of smoke
+    CornellSmoke,
+    /// The cover of book two, everything at once as a stress test
+    FinalScene,
+}
+
+impl Scene {
+    pub const ALL: [Scene; 7] = [
+        Scene::RandomSpheres,
+        Scene::CornellBox,
+        Scene::CheckeredSpheres,
+        Scene::PerlinSpheres,
+        Scene::Earth,
+        Scene::CornellSmoke,
+        Scene::FinalScene,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scene::RandomSpheres => "random-spheres",
+            Scene::CornellBox => "cornell-box",
+            Scene::CheckeredSpheres => "checkered-spheres",
+            Scene::PerlinSpheres => "perlin-spheres",
+            Scene::Earth => "earth",
+            Scene::CornellSmoke => "cornell-smoke",
+            Scene::FinalScene => "final-scene",
+        }
+    }
+
+    /// Builds the scene and calls `f` with its world and `camera` pointed at
+    /// it. Only the view is set, quality settings like the resolution and
+    /// samples are left to the caller.
+    pub fn with_world<R>(
+        &self,
+        camera: CameraBuilder,
+        f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R,
+    ) -> R {
+        match self {
+            Scene::RandomSpheres => random_spheres(camera, f),
+            Scene::CornellBox => cornell_box(camera, f),
+            Scene::CheckeredSpheres => checkered_spheres(camera, f),
+            Scene::PerlinSpheres => perlin_spheres(camera, f),
+            Scene::Earth => earth(camera, f),
+            Scene::CornellSmoke => cornell_smoke(camera, f),
+            Scene::FinalScene => final_scene(camera, f),
+        }
+    }
+}
+
+impl std::str::FromStr for Scene {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scene::ALL
+            .into_iter()
+            .find(|scene| scene.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Scene::ALL.iter().map(Scene::name).collect();
+                format!("unknown scene '{s}', expected one of: {}", names.join(", "))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Aov;
+
+    #[test]
+    fn every_scene_renders() {
+        for scene in Scene::ALL {
+            let camera = CameraBuilder::default()
+                .with_image_width(8)
+                .with_samples_per_pixel(2)
+                .with_max_bounces(4)
+                .with_aovs(Aov::ALL.to_vec());
+            let render = scene.with_world(camera, |world, camera| {
+                camera.build().render_parallel(&world)
+            });
+            let images = std::iter::once(&render.beauty).chain(render.aovs.iter().map(|(_, i)| i));
+            for image in images {
+                assert!(image.width() == 8 && image.height() > 0);
+                for pixel in image.pixels() {
+                    assert!(
+                        [pixel.0, pixel.1, pixel.2].iter().all(|c| c.is_finite()),
+                        "{} has a pixel {pixel:?}",
+                        scene.name()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{
+    camera::CameraBuilder, hit::*, materials::Principled, shapes::Sphere, texture::Marble, vec3::*,
+};
+
+pub fn perlin_spheres<R>(
+    camera: CameraBuilder,
+    f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R,
+) -> R {
+    let marble = Principled::new(Marble::new(4.)).with_specular(0.);
+
+    let mut world = HitList::default();
+    world.push(Sphere::new(Pos(0., -1000., 0.), 1000., &marble));
+    world.push(Sphere::new(Pos(0., 2., 0.), 2., &marble));
+
+    let camera = camera
+        .with_vfov_degrees(20.)
+        .with_lookfrom(Pos(13., 2., 3.))
+        .with_lookat(Pos(0., 0., 0.))
+        .with_vup(Vec3(0., 1., 0.))
+        .with_defocus_angle(0.);
+    f(&world, camera)
+}
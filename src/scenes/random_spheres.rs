@@ -0,0 +1,91 @@
+use crate::{
+    camera::CameraBuilder,
+    color::Color,
+    float::{random, Float},
+    hit::*,
+    materials::*,
+    shapes::Sphere,
+    vec3::*,
+};
+
+pub fn random_spheres<R>(
+    camera: CameraBuilder,
+    f: impl FnOnce(&(dyn Hit + Sync), CameraBuilder) -> R,
+) -> R {
+    // materials
+    let material_ground = Lambertian {
+        albedo: Color(0.5, 0.5, 0.5),
+    };
+    let mat1 = Dialectric::new(1.5);
+    let mat2 = Lambertian {
+        albedo: Color(0.4, 0.2, 0.1),
+    };
+    let mat3 = Metal {
+        albedo: Color(0.7, 0.6, 0.5),
+        fuzz: 0.0,
+    };
+
+    let material_list = {
+        let mut material_list: Vec<Box<dyn Material + Sync>> = vec![];
+
+        for _ in -11..11 {
+            for _ in -11..11 {
+                let choose_mat = random();
+
+                match choose_mat {
+                    0.0..0.8 => {
+                        let albedo = Color::random() * Color::random();
+                        material_list.push(Box::new(Lambertian { albedo }));
+                    }
+                    ..0.95 => {
+                        let albedo = Color::random_range(0.5, 1.);
+                        let fuzz = random() * 0.5;
+                        material_list.push(Box::new(Metal { albedo, fuzz }));
+                    }
+                    _ => {
+                        material_list.push(Box::new(Dialectric::new(1.5)));
+                    }
+                }
+            }
+        }
+
+        material_list
+    };
+
+    // world setup
+    let world = {
+        let mut world = HitList::default();
+
+        world.push(Sphere::new(Pos(0., -1000., 0.), 1000., &material_ground));
+        world.push(Sphere::new(Pos(0., 1., 0.), 1., &mat1));
+        world.push(Sphere::new(Pos(-4., 1., 0.), 1., &mat2));
+        world.push(Sphere::new(Pos(4., 1., 0.), 1., &mat3));
+
+        let mut i: usize = 0;
+        for a in -11..11 {
+            for b in -11..11 {
+                let radius = 0.2;
+                let center = Pos(
+                    a as Float + 0.9 * random(),
+                    radius,
+                    b as Float + 0.9 * random(),
+                );
+
+                world.push(Sphere::new(center, radius, &*material_list[i]));
+
+                i += 1;
+            }
+        }
+
+        world
+    };
+
+    let camera = camera
+        .with_vfov_degrees(20.)
+        .with_lookfrom(Pos(13., 2., 3.))
+        .with_lookat(Pos(0., 0., 0.))
+        .with_vup(Vec3(0., 1., 0.))
+        .with_defocus_angle_degrees(0.6)
+        .with_focus_dist(10.0);
+    f(&world, camera)
+}
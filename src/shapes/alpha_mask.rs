@@ -0,0 +1,86 @@
+use crate::{aabb::Aabb, float::Float, hit::*, ray::Ray, texture::*};
+use std::ops::Range;
+
+// a surface with more cut out layers than this in front of the hit is
+// treated as solid there, so rays can't loop forever
+const MAX_SKIPPED: usize = 64;
+
+/// How an alpha mask turns alpha into holes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+    /// Solid where alpha is at least the threshold, a hole elsewhere.
+    /// Leaves and fences.
+    Cutout(Float),
+    /// Hit with a chance of alpha, so half transparent areas average out to
+    /// blending. Decals and soft edges.
+    Stochastic,
+}
+
+/// Cuts holes into an object where an alpha texture says so. Rays go
+/// through the holes to whatever is behind, including the object itself.
+pub struct AlphaMask<H: Hit> {
+    pub object: H,
+    pub alpha: FloatTexture,
+    pub mode: AlphaMode,
+}
+
+impl<H: Hit> AlphaMask<H> {
+    /// Cut out where alpha is below a half, like glTF's default
+    pub fn new(object: H, alpha: impl Texture<Float> + Sync + 'static) -> Self {
+        Self {
+            object,
+            alpha: Box::new(alpha),
+            mode: AlphaMode::Cutout(0.5),
+        }
+    }
+
+    pub fn with_mode(self, mode: AlphaMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    fn is_solid(&self, ray: &Ray, hit_info: &HitInfo) -> bool {
+        let alpha = self.alpha.value(hit_info);
+        match self.mode {
+            AlphaMode::Cutout(threshold) => alpha >= threshold,
+            // hashed rather than random, so every test of the same ray
+            // against the same spot agrees
+            AlphaMode::Stochastic => alpha > hash_float(ray, hit_info.t),
+        }
+    }
+}
+
+impl<H: Hit> Hit for AlphaMask<H> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let mut interval = ray_t_interval;
+        for _ in 0..MAX_SKIPPED {
+            let hit_info = self.object.hit(ray, interval.clone())?;
+            if self.is_solid(ray, &hit_info) {
+                return Some(hit_info);
+            }
+            // on past the hole
+            interval.start = hit_info.t;
+        }
+        self.object.hit(ray, interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box()
+    }
+}
+
+// a number in [0, 1) from the ray and the distance along it
+fn hash_float(ray: &Ray, t: Float) -> Float {
+    let o = ray.origin;
+    let d = ray.dir;
+    let mut x: u64 = 0x9e3779b97f4a7c15;
+    for value in [o.0, o.1, o.2, d.0, d.1, d.2, t] {
+        // splitmix64 steps over the bits of each value, which are u32 with f32
+        #[allow(clippy::unnecessary_cast)]
+        let bits = value.to_bits() as u64;
+        x ^= bits.wrapping_add(0x9e3779b97f4a7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+    }
+    (x >> 11) as Float / (1u64 << 53) as Float
+}
@@ -0,0 +1,130 @@
+use super::{angle_u, centered_quadratic, in_interval, Frame, LocalHit};
+use crate::{
+    aabb::Aabb,
+    float::{consts, gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// The points within radius of a line segment: a cylinder with a half
+/// sphere on each end.
+/// u: angle around the axis, scaled to [0, 1]
+/// v: distance along the outline from the bottom pole to the top pole,
+/// scaled to [0, 1]
+pub struct Capsule<'a> {
+    frame: Frame,
+    pub radius: Float,
+    // length of the segment
+    pub height: Float,
+
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Capsule<'a> {
+    /// Capsule around the segment from a to b
+    pub fn new(a: Pos, b: Pos, radius: Float, mat: &'a (dyn Material + Sync)) -> Self {
+        Self {
+            frame: Frame::new(a, b - a),
+            radius,
+            height: (b - a).length(),
+            mat,
+        }
+    }
+
+    // v of a point on the surface
+    fn outline_v(&self, p: &Vec3) -> Float {
+        let (r, height) = (self.radius.abs(), self.height);
+        let quarter = consts::FRAC_PI_2 * r;
+        let along = match p.2 {
+            z if z < 0. => (-z / r).clamp(-1., 1.).acos() * r,
+            z if z > height => quarter + height + ((z - height) / r).clamp(-1., 1.).asin() * r,
+            z => quarter + z,
+        };
+        along / (2. * quarter + height)
+    }
+
+    // up along the outline, whose whole length v covers
+    fn outline_dpdv(&self, p: &Vec3, normal: &Vec3) -> Vec3 {
+        let r = self.radius.abs();
+        let around = Vec3(-p.1, p.0, 0.);
+        match around.near_zero() {
+            true => Vec3(0., 0., 0.),
+            false => normal.cross(&around).unit_vec() * (consts::PI * r + self.height),
+        }
+    }
+}
+
+impl Hit for Capsule<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let (o, d) = self.frame.ray_to_local(ray);
+        let (r, height) = (self.radius.abs(), self.height);
+        let accept = |t: Float| in_interval(t, &o, &d, &ray_t_interval);
+
+        // the side, then the end spheres, each only where it's the outside
+        let (o_xy, d_xy) = (Vec3(o.0, o.1, 0.), Vec3(d.0, d.1, 0.));
+        let side = centered_quadratic(&o_xy, &d_xy, r)
+            .into_iter()
+            .flat_map(|(t0, t1)| [t0, t1])
+            .find(|&t| accept(t) && (0. ..=height).contains(&(o.2 + d.2 * t)));
+        let end = |z: Float, outside: fn(Float) -> bool| {
+            let center = Vec3(0., 0., z);
+            centered_quadratic(&(o - center), &d, r)
+                .into_iter()
+                .flat_map(|(t0, t1)| [t0, t1])
+                .find(|&t| accept(t) && outside(o.2 + d.2 * t - z))
+                .map(|t| (t, center))
+        };
+        let bottom = end(0., |z| z < 0.);
+        let top = end(height, |z| z > 0.);
+
+        let nearest_end = match (bottom, top) {
+            (Some(b), Some(t)) => Some(if b.0 < t.0 { b } else { t }),
+            (b, t) => b.or(t),
+        };
+        let hit = match (side, nearest_end) {
+            (Some(t), end) if end.is_none_or(|(end_t, _)| t <= end_t) => {
+                // pushed back onto the side like the sphere's hit point
+                let z = o.2 + d.2 * t;
+                let xy = o_xy + d_xy * t;
+                let xy = xy * (r / xy.length());
+                let p = Vec3(xy.0, xy.1, z);
+                LocalHit {
+                    t,
+                    p,
+                    p_error: Vec3(xy.0.abs(), xy.1.abs(), o.2.abs() + (d.2 * t).abs()) * gamma(5),
+                    normal: xy / r,
+                    u: angle_u(&p),
+                    v: self.outline_v(&p),
+                    dpdv: self.outline_dpdv(&p, &(xy / r)),
+                }
+            }
+            (_, Some((t, center))) => {
+                // and onto the end sphere
+                let out = o + d * t - center;
+                let out = out * (r / out.length());
+                let p = center + out;
+                LocalHit {
+                    t,
+                    p,
+                    p_error: out.abs() * gamma(5) + (center.abs() + out.abs()) * gamma(1),
+                    normal: out / r,
+                    u: angle_u(&p),
+                    v: self.outline_v(&p),
+                    dpdv: self.outline_dpdv(&p, &(out / r)),
+                }
+            }
+            _ => return None,
+        };
+
+        Some(self.frame.hit_info(ray, hit, self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = self.radius.abs();
+        self.frame
+            .bounds(Vec3(-r, -r, -r), Vec3(r, r, self.height + r))
+    }
+}
@@ -0,0 +1,112 @@
+use super::{angle_u, cap_dpdv, in_interval, quadratic, Frame, LocalHit};
+use crate::{
+    aabb::Aabb,
+    float::{gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// A cone closed by a flat base.
+/// Side: u is the angle around the axis, v goes from 0 at the base to 1 at the apex.
+/// Base: u is the angle, v the distance from the axis, 1 at the edge.
+pub struct Cone<'a> {
+    frame: Frame,
+    // of the base
+    pub radius: Float,
+    pub height: Float,
+
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Cone<'a> {
+    /// Cone from the center of its base up to the apex
+    pub fn new(base: Pos, apex: Pos, radius: Float, mat: &'a (dyn Material + Sync)) -> Self {
+        Self {
+            frame: Frame::new(base, apex - base),
+            radius,
+            height: (apex - base).length(),
+            mat,
+        }
+    }
+}
+
+impl Hit for Cone<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let (o, d) = self.frame.ray_to_local(ray);
+        let (r, height) = (self.radius.abs(), self.height);
+        let accept = |t: Float| in_interval(t, &o, &d, &ray_t_interval);
+        let mut closest: Option<LocalHit> = None;
+
+        // x^2 + y^2 = (k (h - z))^2, which is a double cone, so only the
+        // part between the base and the apex counts
+        let k = r / height;
+        let w = height - o.2;
+        let a = d.0 * d.0 + d.1 * d.1 - k * k * d.2 * d.2;
+        let b = 2. * (o.0 * d.0 + o.1 * d.1 + k * k * w * d.2);
+        let c = o.0 * o.0 + o.1 * o.1 - k * k * w * w;
+        if let Some((t0, t1)) = quadratic(a, b, c) {
+            for t in [t0, t1] {
+                let z = o.2 + d.2 * t;
+                if !accept(t) || !(0. ..=height).contains(&z) {
+                    continue;
+                }
+                // pushed back onto the side at this height
+                let rho = k * (height - z);
+                let xy = Vec3(o.0 + d.0 * t, o.1 + d.1 * t, 0.);
+                let xy = match xy.length() > 0. {
+                    true => xy * (rho / xy.length()),
+                    false => xy,
+                };
+                let p = Vec3(xy.0, xy.1, z);
+                // the gradient of the implicit function, straight up at the apex
+                let normal = match rho > 0. {
+                    true => Vec3(xy.0, xy.1, k * rho),
+                    false => Vec3(0., 0., 1.),
+                };
+                closest = Some(LocalHit {
+                    t,
+                    p,
+                    p_error: Vec3(xy.0.abs(), xy.1.abs(), o.2.abs() + (d.2 * t).abs()) * gamma(7),
+                    normal,
+                    u: angle_u(&p),
+                    v: z / height,
+                    // up the side, which moves in by k
+                    dpdv: match rho > 0. {
+                        true => Vec3(-k * xy.0 / rho, -k * xy.1 / rho, 1.) * height,
+                        false => Vec3(0., 0., height),
+                    },
+                });
+                break;
+            }
+        }
+
+        // base
+        let t = -o.2 / d.2;
+        if accept(t) && !closest.as_ref().is_some_and(|c| c.t <= t) {
+            let p = Vec3(o.0 + d.0 * t, o.1 + d.1 * t, 0.);
+            let rho_squared = p.0 * p.0 + p.1 * p.1;
+            if rho_squared <= r * r {
+                closest = Some(LocalHit {
+                    t,
+                    p,
+                    p_error: Vec3(o.0.abs() + (d.0 * t).abs(), o.1.abs() + (d.1 * t).abs(), 0.)
+                        * gamma(3),
+                    normal: Vec3(0., 0., -1.),
+                    u: angle_u(&p),
+                    v: rho_squared.sqrt() / r,
+                    dpdv: cap_dpdv(&p, r),
+                });
+            }
+        }
+
+        closest.map(|hit| self.frame.hit_info(ray, hit, self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = self.radius.abs();
+        self.frame.bounds(Vec3(-r, -r, 0.), Vec3(r, r, self.height))
+    }
+}
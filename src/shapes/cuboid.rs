@@ -0,0 +1,86 @@
+use super::{Quad, SampleArea};
+use crate::{aabb::Aabb, float::Float, hit::*, materials::Material, ray::*, vec3::*};
+use std::ops::Range;
+
+/// An axis-aligned box made of six quads with outward normals. Rotate or move
+/// it with `Transformed`. (Not called Box so it doesn't shadow std's.)
+pub struct Cuboid<'a> {
+    faces: [Quad<'a>; 6],
+    bounds: Aabb,
+}
+
+impl<'a> Cuboid<'a> {
+    /// Box with two opposite corners at a and b
+    pub fn new(a: Pos, b: Pos, mat: &'a (dyn Material + Sync)) -> Self {
+        let bounds = Aabb::new(a, b);
+        let (min, max) = (bounds.min, bounds.max);
+        let dx = Vec3(max.x() - min.x(), 0., 0.);
+        let dy = Vec3(0., max.y() - min.y(), 0.);
+        let dz = Vec3(0., 0., max.z() - min.z());
+
+        // each u x v points out of the box
+        let faces = [
+            Quad::new(Pos(min.x(), min.y(), max.z()), dx, dy, mat), // front
+            Quad::new(Pos(max.x(), min.y(), max.z()), -dz, dy, mat), // right
+            Quad::new(Pos(max.x(), min.y(), min.z()), -dx, dy, mat), // back
+            Quad::new(Pos(min.x(), min.y(), min.z()), dz, dy, mat), // left
+            Quad::new(Pos(min.x(), max.y(), max.z()), dx, -dz, mat), // top
+            Quad::new(Pos(min.x(), min.y(), min.z()), dx, dz, mat), // bottom
+        ];
+        Self { faces, bounds }
+    }
+
+    pub fn faces(&self) -> &[Quad<'a>; 6] {
+        &self.faces
+    }
+}
+
+impl Hit for Cuboid<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        self.faces
+            .iter()
+            .filter_map(|face| face.hit(ray, ray_t_interval.clone()))
+            .min_by(|info1, info2| info1.t.total_cmp(&info2.t))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl SampleArea for Cuboid<'_> {
+    fn area(&self) -> Float {
+        self.faces.iter().map(|face| face.area()).sum()
+    }
+
+    fn sample_point(&self, (s, t): (Float, Float)) -> (Pos, Normal) {
+        // pick a face by its share of the area, and reuse the rest of s
+        // for the point on it
+        let mut s = s * self.area();
+        for face in &self.faces[..5] {
+            if s < face.area() {
+                return face.sample_point((s / face.area(), t));
+            }
+            s -= face.area();
+        }
+        let last = &self.faces[5];
+        last.sample_point(((s / last.area()).min(1.), t))
+    }
+
+    // the direction could also have come from the face on the other side
+    fn sample(&self, origin: &Pos, u: (Float, Float)) -> Option<(Vec3, Float)> {
+        let (pos, _) = self.sample_point(u);
+        let dir = pos - origin;
+        let pdf = self.pdf(origin, &dir);
+        (pdf > 0.).then_some((dir, pdf))
+    }
+
+    fn pdf(&self, origin: &Pos, dir: &Vec3) -> Float {
+        // a line through the box crosses two faces, either could have been picked
+        let area = self.area();
+        self.faces
+            .iter()
+            .map(|face| face.pdf(origin, dir) * face.area() / area)
+            .sum()
+    }
+}
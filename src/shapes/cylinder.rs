@@ -0,0 +1,98 @@
+use super::{angle_u, cap_dpdv, centered_quadratic, in_interval, Frame, LocalHit};
+use crate::{
+    aabb::Aabb,
+    float::{gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// A cylinder closed by flat caps at both ends.
+/// Side: u is the angle around the axis, v goes from 0 at the base to 1 at the top.
+/// Caps: u is the angle, v the distance from the axis, 1 at the edge.
+pub struct Cylinder<'a> {
+    frame: Frame,
+    pub radius: Float,
+    pub height: Float,
+
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Cylinder<'a> {
+    /// Cylinder along the line from the center of the base to the center of the top
+    pub fn new(base: Pos, top: Pos, radius: Float, mat: &'a (dyn Material + Sync)) -> Self {
+        Self {
+            frame: Frame::new(base, top - base),
+            radius,
+            height: (top - base).length(),
+            mat,
+        }
+    }
+}
+
+impl Hit for Cylinder<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let (o, d) = self.frame.ray_to_local(ray);
+        let (r, height) = (self.radius.abs(), self.height);
+        let accept = |t: Float| in_interval(t, &o, &d, &ray_t_interval);
+        let mut closest: Option<LocalHit> = None;
+
+        // the side is a circle in xy
+        let (o_xy, d_xy) = (Vec3(o.0, o.1, 0.), Vec3(d.0, d.1, 0.));
+        if let Some((t0, t1)) = centered_quadratic(&o_xy, &d_xy, r) {
+            for t in [t0, t1] {
+                let z = o.2 + d.2 * t;
+                if !accept(t) || !(0. ..=height).contains(&z) {
+                    continue;
+                }
+                // pushed back onto the side like the sphere's hit point
+                let xy = o_xy + d_xy * t;
+                let xy = xy * (r / xy.length());
+                let p = Vec3(xy.0, xy.1, z);
+                closest = Some(LocalHit {
+                    t,
+                    p,
+                    p_error: Vec3(xy.0.abs(), xy.1.abs(), o.2.abs() + (d.2 * t).abs()) * gamma(5),
+                    normal: xy / r,
+                    u: angle_u(&p),
+                    v: z / height,
+                    dpdv: Vec3(0., 0., height),
+                });
+                break;
+            }
+        }
+
+        // caps
+        for (cap_z, normal_z) in [(0., -1.), (height, 1.)] {
+            let t = (cap_z - o.2) / d.2;
+            if !accept(t) || closest.as_ref().is_some_and(|c| c.t <= t) {
+                continue;
+            }
+            let p = Vec3(o.0 + d.0 * t, o.1 + d.1 * t, cap_z);
+            let rho_squared = p.0 * p.0 + p.1 * p.1;
+            if rho_squared > r * r {
+                continue;
+            }
+            closest = Some(LocalHit {
+                t,
+                p,
+                // set exactly on the cap, off only along it
+                p_error: Vec3(o.0.abs() + (d.0 * t).abs(), o.1.abs() + (d.1 * t).abs(), 0.)
+                    * gamma(3),
+                normal: Vec3(0., 0., normal_z),
+                u: angle_u(&p),
+                v: rho_squared.sqrt() / r,
+                dpdv: cap_dpdv(&p, r),
+            });
+        }
+
+        closest.map(|hit| self.frame.hit_info(ray, hit, self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = self.radius.abs();
+        self.frame.bounds(Vec3(-r, -r, 0.), Vec3(r, r, self.height))
+    }
+}
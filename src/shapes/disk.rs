@@ -0,0 +1,112 @@
+use super::{planar_point, plane_t, SampleArea};
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    float::{consts, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// A flat disk facing the direction of its normal.
+/// u: angle around the center, scaled to [0, 1]
+/// v: distance from the center, 0 at the center and 1 at the edge
+pub struct Disk<'a> {
+    pub center: Pos,
+    pub radius: Float,
+    normal: Normal,
+    // unit vectors along the disk, u starts along the tangent
+    tangent: Vec3,
+    bitangent: Vec3,
+
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Disk<'a> {
+    pub fn new(center: Pos, normal: Vec3, radius: Float, mat: &'a (dyn Material + Sync)) -> Self {
+        let normal = Normal::new(normal);
+        let (tangent, bitangent) = normal.to_vec().orthonormal_basis();
+        Self {
+            center,
+            radius,
+            normal,
+            tangent,
+            bitangent,
+            mat,
+        }
+    }
+
+    pub fn normal(&self) -> Normal {
+        self.normal
+    }
+}
+
+impl Hit for Disk<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let t = plane_t(&self.center, &self.normal, ray);
+        if !(ray_t_interval.start < t && t < ray_t_interval.end) {
+            return None;
+        }
+
+        let p = ray.at(t) - self.center;
+        let (a, b) = (p.dot(&self.tangent), p.dot(&self.bitangent));
+        let dist_squared = a * a + b * b;
+        if dist_squared > self.radius * self.radius {
+            return None;
+        }
+
+        let (pos, pos_error) = planar_point(&self.center, self.tangent * a, self.bitangent * b);
+        let front_face = self.normal.dot(&ray.dir) < 0.;
+        let normal = if front_face {
+            self.normal
+        } else {
+            -self.normal
+        };
+        // u goes around, v out from the center
+        let (around, out) = (
+            self.bitangent * a - self.tangent * b,
+            self.tangent * a + self.bitangent * b,
+        );
+        let rho = dist_squared.sqrt();
+        Some(HitInfo {
+            pos,
+            pos_error,
+            normal,
+            geometric_normal: normal,
+            t,
+            front_face,
+            u: (b.atan2(a) / (2. * consts::PI)).rem_euclid(1.),
+            v: rho / self.radius,
+            dpdu: around * (2. * consts::PI),
+            dpdv: match rho > 0. {
+                true => out * (self.radius / rho),
+                false => Vec3(0., 0., 0.),
+            },
+            vertex_color: Color(1., 1., 1.),
+            object_id: 0,
+            mat: self.mat,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // the disk reaches r * sqrt(1 - n_i^2) from the center along axis i
+        let n = self.normal.to_vec();
+        let reach = |n_i: Float| self.radius * (1. - n_i * n_i).max(0.).sqrt();
+        let r = Vec3(reach(n.x()), reach(n.y()), reach(n.z()));
+        Aabb::new(self.center - r, self.center + r)
+    }
+}
+
+impl SampleArea for Disk<'_> {
+    fn area(&self) -> Float {
+        consts::PI * self.radius * self.radius
+    }
+
+    fn sample_point(&self, u: (Float, Float)) -> (Pos, Normal) {
+        let p = Vec3::in_unit_disk_from_sample(u) * self.radius;
+        let pos = self.center + self.tangent * p.x() + self.bitangent * p.y();
+        (pos, self.normal)
+    }
+}
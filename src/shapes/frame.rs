@@ -0,0 +1,128 @@
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    float::{consts, gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+
+/// Coordinates of a shape that's simplest to hit around its own axis: an
+/// origin and an orthonormal basis with z along the axis. Only rotates and
+/// moves, so ray parameters t are the same in both spaces.
+pub(crate) struct Frame {
+    pub origin: Pos,
+    x: Vec3,
+    y: Vec3,
+    z: Vec3,
+}
+
+/// A hit found in frame coordinates
+pub(crate) struct LocalHit {
+    pub t: Float,
+    // point, its error bound and the outward normal, in frame coordinates
+    pub p: Vec3,
+    pub p_error: Vec3,
+    pub normal: Vec3,
+    // u is always the angle around z, so only v's derivative differs
+    pub u: Float,
+    pub v: Float,
+    pub dpdv: Vec3,
+}
+
+impl Frame {
+    /// A zero axis falls back to +z
+    pub fn new(origin: Pos, axis: Vec3) -> Self {
+        let z = match axis.length_squared() > 0. {
+            true => axis.unit_vec(),
+            false => Vec3(0., 0., 1.),
+        };
+        let (x, y) = z.orthonormal_basis();
+        Self { origin, x, y, z }
+    }
+
+    /// Origin and direction of the ray in frame coordinates
+    pub fn ray_to_local(&self, ray: &Ray) -> (Vec3, Vec3) {
+        let to_local = |v: &Vec3| Vec3(self.x.dot(v), self.y.dot(v), self.z.dot(v));
+        (to_local(&(ray.origin - self.origin)), to_local(&ray.dir))
+    }
+
+    fn vec_to_world(&self, v: &Vec3) -> Vec3 {
+        self.x * v.0 + self.y * v.1 + self.z * v.2
+    }
+
+    /// World bounds of a box in frame coordinates
+    pub fn bounds(&self, min: Vec3, max: Vec3) -> Aabb {
+        Aabb::new(Pos::ORIGIN + min, Pos::ORIGIN + max)
+            .corners()
+            .iter()
+            .map(|corner| self.origin + self.vec_to_world(&corner.to_vec()))
+            .fold(Aabb::EMPTY, |acc, corner| {
+                acc.union(&Aabb::new(corner, corner))
+            })
+    }
+
+    pub fn hit_info<'a>(
+        &self,
+        ray: &Ray,
+        hit: LocalHit,
+        mat: &'a (dyn Material + Sync),
+    ) -> HitInfo<'a> {
+        let p = hit.p;
+        let pos = self.origin + self.vec_to_world(&p);
+        // the local error turned into world axes, then the rounding of the
+        // sum above
+        let e = hit.p_error;
+        let carried = self.x.abs() * e.0 + self.y.abs() * e.1 + self.z.abs() * e.2;
+        let rounding = (self.origin.to_vec().abs()
+            + (self.x * p.0).abs()
+            + (self.y * p.1).abs()
+            + (self.z * p.2).abs())
+            * gamma(4);
+        let pos_error = carried * (1. + gamma(4)) + rounding;
+
+        let out_normal = Normal::new(self.vec_to_world(&hit.normal));
+        let front_face = out_normal.dot(&ray.dir) < 0.;
+        let normal = if front_face { out_normal } else { -out_normal };
+        let dpdu = Vec3(-p.1, p.0, 0.) * (2. * consts::PI);
+        HitInfo {
+            pos,
+            pos_error,
+            normal,
+            geometric_normal: normal,
+            t: hit.t,
+            front_face,
+            u: hit.u,
+            v: hit.v,
+            dpdu: self.vec_to_world(&dpdu),
+            dpdv: self.vec_to_world(&hit.dpdv),
+            vertex_color: Color(1., 1., 1.),
+            object_id: 0,
+            mat,
+        }
+    }
+}
+
+/// Whether a root t of a ray in frame coordinates is in the interval, and
+/// further from its start than the rounding error of roots near the origin,
+/// so rays leaving the surface don't hit it again
+pub(crate) fn in_interval(t: Float, o: &Vec3, d: &Vec3, interval: &std::ops::Range<Float>) -> bool {
+    let t_error = gamma(16) * o.length() / d.length();
+    interval.start < t - t_error && t < interval.end
+}
+
+/// dp/dv of a cap of radius r in the xy plane, where v is the distance from
+/// the center over r
+pub(crate) fn cap_dpdv(p: &Vec3, r: Float) -> Vec3 {
+    let xy = Vec3(p.0, p.1, 0.);
+    match xy.near_zero() {
+        true => Vec3(0., 0., 0.),
+        false => xy.unit_vec() * r,
+    }
+}
+
+/// Angle of a point around the z axis, scaled to [0, 1)
+pub(crate) fn angle_u(p: &Vec3) -> Float {
+    (p.y().atan2(p.x()) / (2. * consts::PI)).rem_euclid(1.)
+}
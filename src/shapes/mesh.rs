@@ -0,0 +1,525 @@
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    float::{gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::Ray,
+    vec3::*,
+};
+use std::ops::Range;
+
+// triangles per leaf of the bounding volume hierarchy
+const LEAF_SIZE: usize = 4;
+
+/// Triangles sharing a list of vertices, with optional per-vertex normals
+/// for smooth shading, texture coordinates and colors. Holds a bounding volume
+/// hierarchy over the triangles, so big meshes stay fast to hit. Drawn by
+/// giving it a material with `Mesh`.
+pub struct TriangleMesh {
+    positions: Vec<Pos>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(Float, Float)>>,
+    colors: Option<Vec<Color>>,
+    triangles: Vec<[u32; 3]>,
+    nodes: Vec<Node>,
+}
+
+// a node of the hierarchy, flattened depth first so the first child of an
+// inner node comes right after it
+struct Node {
+    bounds: Aabb,
+    // leaves hold triangles start..start + count, inner nodes have count 0
+    // and start is their second child
+    start: u32,
+    count: u32,
+}
+
+impl TriangleMesh {
+    /// Triangles as indices into the positions, counterclockwise seen from
+    /// the front
+    pub fn new(positions: Vec<Pos>, triangles: Vec<[u32; 3]>) -> Self {
+        assert!(
+            triangles
+                .iter()
+                .flatten()
+                .all(|&i| (i as usize) < positions.len()),
+            "triangle vertex index out of range"
+        );
+        let mut mesh = Self {
+            positions,
+            normals: None,
+            uvs: None,
+            colors: None,
+            triangles,
+            nodes: Vec::new(),
+        };
+        mesh.build();
+        mesh
+    }
+
+    /// One normal per position, interpolated over the triangles
+    pub fn with_normals(self, normals: Vec<Vec3>) -> Self {
+        assert_eq!(
+            normals.len(),
+            self.positions.len(),
+            "one normal per position"
+        );
+        Self {
+            normals: Some(normals),
+            ..self
+        }
+    }
+
+    /// One uv per position, with v = 0 at the bottom of textures
+    pub fn with_uvs(self, uvs: Vec<(Float, Float)>) -> Self {
+        assert_eq!(uvs.len(), self.positions.len(), "one uv per position");
+        Self {
+            uvs: Some(uvs),
+            ..self
+        }
+    }
+
+    /// One linear color per position, for the `VertexColor` texture
+    pub fn with_colors(self, colors: Vec<Color>) -> Self {
+        assert_eq!(colors.len(), self.positions.len(), "one color per position");
+        Self {
+            colors: Some(colors),
+            ..self
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |node| node.bounds)
+    }
+
+    fn triangle_bounds(&self, triangle: &[u32; 3]) -> Aabb {
+        let [a, b, c] = triangle.map(|i| self.positions[i as usize]);
+        Aabb::new(a, b).union(&Aabb::new(c, c))
+    }
+
+    fn build(&mut self) {
+        if self.triangles.is_empty() {
+            return;
+        }
+        let mut triangles = std::mem::take(&mut self.triangles);
+        let mut nodes = Vec::with_capacity(2 * triangles.len() / LEAF_SIZE + 1);
+        self.build_node(&mut triangles, 0, &mut nodes);
+        self.triangles = triangles;
+        self.nodes = nodes;
+    }
+
+    // splits the triangles at the median along the longest axis of their
+    // centers until few enough are left
+    fn build_node(&self, triangles: &mut [[u32; 3]], offset: usize, nodes: &mut Vec<Node>) {
+        let bounds = triangles
+            .iter()
+            .fold(Aabb::EMPTY, |acc, t| acc.union(&self.triangle_bounds(t)));
+        let index = nodes.len();
+        nodes.push(Node {
+            bounds,
+            start: offset as u32,
+            count: triangles.len() as u32,
+        });
+        if triangles.len() <= LEAF_SIZE {
+            return;
+        }
+
+        let center = |t: &[u32; 3]| {
+            let b = self.triangle_bounds(t);
+            (b.min.to_vec() + b.max.to_vec()) * 0.5
+        };
+        let centers = triangles.iter().fold(Aabb::EMPTY, |acc, t| {
+            let c = Pos::ORIGIN + center(t);
+            acc.union(&Aabb::new(c, c))
+        });
+        let extent = centers.max - centers.min;
+        let axis = match (extent.0, extent.1, extent.2) {
+            (x, y, z) if x >= y && x >= z => 0,
+            (_, y, z) if y >= z => 1,
+            _ => 2,
+        };
+        let key = |t: &[u32; 3]| {
+            let c = center(t);
+            [c.0, c.1, c.2][axis]
+        };
+
+        let mid = triangles.len() / 2;
+        triangles.select_nth_unstable_by(mid, |a, b| key(a).total_cmp(&key(b)));
+        let (left, right) = triangles.split_at_mut(mid);
+        self.build_node(left, offset, nodes);
+        let second = nodes.len();
+        self.build_node(right, offset + mid, nodes);
+        nodes[index].start = second as u32;
+        nodes[index].count = 0;
+    }
+
+    // the nearest triangle hit in the interval, with its barycentric
+    // coordinates for the second and third vertex
+    fn intersect(&self, ray: &Ray, interval: Range<Float>) -> Option<(usize, Float, Float, Float)> {
+        let mut closest: Option<(usize, Float, Float, Float)> = None;
+        let mut end = interval.end;
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.bounds.ray_interval(ray, interval.start..end).is_none() {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.start as usize);
+                stack.push(index + 1);
+                continue;
+            }
+            let leaf = node.start as usize..(node.start + node.count) as usize;
+            for i in leaf {
+                if let Some((t, b1, b2)) = self.intersect_triangle(i, ray, interval.start..end) {
+                    end = t;
+                    closest = Some((i, t, b1, b2));
+                }
+            }
+        }
+        closest
+    }
+
+    // Möller-Trumbore
+    fn intersect_triangle(
+        &self,
+        i: usize,
+        ray: &Ray,
+        interval: Range<Float>,
+    ) -> Option<(Float, Float, Float)> {
+        let [p0, p1, p2] = self.triangles[i].map(|v| self.positions[v as usize]);
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        let p = ray.dir.cross(&e2);
+        let det = e1.dot(&p);
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+        let inv_det = 1. / det;
+        let to_origin = ray.origin - p0;
+        let b1 = to_origin.dot(&p) * inv_det;
+        if !(0. ..=1.).contains(&b1) {
+            return None;
+        }
+        let q = to_origin.cross(&e1);
+        let b2 = ray.dir.dot(&q) * inv_det;
+        if b2 < 0. || b1 + b2 > 1. {
+            return None;
+        }
+        let t = e2.dot(&q) * inv_det;
+
+        // t near zero is mostly rounding error when the ray starts on the
+        // triangle, don't let a ray leaving it hit it again
+        let t_error = gamma(8) * to_origin.length() * e1.length() * e2.length() * inv_det.abs();
+        (interval.start < t - t_error && t < interval.end).then_some((t, b1, b2))
+    }
+
+    fn hit_info<'a>(
+        &self,
+        ray: &Ray,
+        (i, t, b1, b2): (usize, Float, Float, Float),
+        mat: &'a (dyn Material + Sync),
+    ) -> HitInfo<'a> {
+        let vertices = self.triangles[i].map(|v| v as usize);
+        let [p0, p1, p2] = vertices.map(|v| self.positions[v]);
+        let b0 = 1. - b1 - b2;
+
+        // the barycentric point is far more accurate than ray.at(t) (pbrt 6.8.5)
+        let (a, b, c) = (p0.to_vec() * b0, p1.to_vec() * b1, p2.to_vec() * b2);
+        let pos = Pos::ORIGIN + (a + b + c);
+        let pos_error = (a.abs() + b.abs() + c.abs()) * gamma(7);
+
+        let out_normal = Normal::new((p1 - p0).cross(&(p2 - p0)));
+        let front_face = out_normal.dot(&ray.dir) < 0.;
+        let geometric_normal = if front_face { out_normal } else { -out_normal };
+
+        // interpolated normals, unless they turn away from the real surface
+        let normal = match &self.normals {
+            Some(normals) => {
+                let [n0, n1, n2] = vertices.map(|v| normals[v]);
+                let n = n0 * b0 + n1 * b1 + n2 * b2;
+                let n = if front_face { n } else { -n };
+                match n.near_zero() || n.dot(&geometric_normal.to_vec()) <= 0. {
+                    true => geometric_normal,
+                    false => Normal::new(n),
+                }
+            }
+            None => geometric_normal,
+        };
+
+        let [uv0, uv1, uv2] = match &self.uvs {
+            Some(uvs) => vertices.map(|v| uvs[v]),
+            None => [(0., 0.), (1., 0.), (0., 1.)],
+        };
+        let u = uv0.0 * b0 + uv1.0 * b1 + uv2.0 * b2;
+        let v = uv0.1 * b0 + uv1.1 * b1 + uv2.1 * b2;
+
+        let vertex_color = match &self.colors {
+            Some(colors) => {
+                let [c0, c1, c2] = vertices.map(|v| colors[v]);
+                c0 * b0 + c1 * b1 + c2 * b2
+            }
+            None => Color(1., 1., 1.),
+        };
+
+        // solving p - p2 for the uv differences (pbrt 6.5.3)
+        let (duv02, duv12) = (
+            (uv0.0 - uv2.0, uv0.1 - uv2.1),
+            (uv1.0 - uv2.0, uv1.1 - uv2.1),
+        );
+        let (dp02, dp12) = (p0 - p2, p1 - p2);
+        let det = duv02.0 * duv12.1 - duv02.1 * duv12.0;
+        let (dpdu, dpdv) = match det.abs() > 1e-12 {
+            true => (
+                (dp02 * duv12.1 - dp12 * duv02.1) / det,
+                (dp12 * duv02.0 - dp02 * duv12.0) / det,
+            ),
+            false => out_normal.to_vec().orthonormal_basis(),
+        };
+
+        HitInfo {
+            pos,
+            pos_error,
+            normal,
+            geometric_normal,
+            t,
+            front_face,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            vertex_color,
+            object_id: 0,
+            mat,
+        }
+    }
+}
+
+/// What a ray going straight down onto (x, y) from z = 1 sees of a mesh, for
+/// the tests of the formats that read them
+#[cfg(test)]
+pub(crate) struct Seen {
+    pub pos: Pos,
+    pub normal: Vec3,
+    pub uv: (Float, Float),
+    pub color: Color,
+    pub front_face: bool,
+}
+
+#[cfg(test)]
+pub(crate) fn look_down(mesh: &TriangleMesh, x: Float, y: Float) -> Option<Seen> {
+    let mat = crate::materials::Lambertian {
+        albedo: Color(0.5, 0.5, 0.5),
+    };
+    let ray = Ray {
+        origin: Pos(x, y, 1.),
+        dir: Vec3(0., 0., -1.),
+        time: 0.,
+    };
+    let mesh = Mesh::new(mesh, &mat);
+    let hit_info = mesh.hit(&ray, 0.0..10.)?;
+    Some(Seen {
+        pos: hit_info.pos,
+        normal: hit_info.normal.to_vec(),
+        uv: (hit_info.u, hit_info.v),
+        color: hit_info.vertex_color,
+        front_face: hit_info.front_face,
+    })
+}
+
+/// A triangle mesh drawn with a material. The mesh is borrowed, so one mesh
+/// can be placed several times.
+pub struct Mesh<'a> {
+    pub mesh: &'a TriangleMesh,
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Mesh<'a> {
+    pub fn new(mesh: &'a TriangleMesh, mat: &'a (dyn Material + Sync)) -> Self {
+        Self { mesh, mat }
+    }
+}
+
+impl Hit for Mesh<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let closest = self.mesh.intersect(ray, ray_t_interval)?;
+        Some(self.mesh.hit_info(ray, closest, self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.mesh.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::float::random;
+
+    fn near(a: Float, b: Float) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    fn random_pos(size: Float) -> Pos {
+        Pos::ORIGIN + Vec3(random(), random(), random()) * size
+    }
+
+    // the nearest hit by trying every triangle, without the hierarchy
+    fn brute_force(mesh: &TriangleMesh, ray: &Ray) -> Option<(usize, Float)> {
+        (0..mesh.triangle_count())
+            .filter_map(|i| {
+                let (t, _, _) = mesh.intersect_triangle(i, ray, 0.0..Float::INFINITY)?;
+                Some((i, t))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    #[test]
+    fn hierarchy_finds_the_nearest_triangle() {
+        fastrand::seed(5);
+        // a soup of small triangles all over a box, some rays start inside it
+        let mut positions = Vec::new();
+        for _ in 0..500 {
+            let corner = random_pos(10.);
+            positions.extend([
+                corner,
+                corner + random_pos(1.).to_vec(),
+                corner + random_pos(1.).to_vec(),
+            ]);
+        }
+        let triangles = (0..500).map(|i| [3 * i, 3 * i + 1, 3 * i + 2]).collect();
+        let mesh = TriangleMesh::new(positions, triangles);
+
+        // leaves are small and every node's box holds its triangles
+        for (index, node) in mesh.nodes.iter().enumerate() {
+            let (start, count) = (node.start as usize, node.count as usize);
+            let triangles = match count {
+                0 => continue,
+                _ => &mesh.triangles[start..start + count],
+            };
+            assert!(count <= LEAF_SIZE, "{index}");
+            for t in triangles {
+                let b = mesh.triangle_bounds(t);
+                assert_eq!(node.bounds.union(&b).min, node.bounds.min);
+                assert_eq!(node.bounds.union(&b).max, node.bounds.max);
+            }
+        }
+
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let ray = Ray {
+                origin: random_pos(14.) - Vec3(2., 2., 2.),
+                dir: Vec3::rand_unit_vec(),
+                time: 0.,
+            };
+            let found = mesh.intersect(&ray, 0.0..Float::INFINITY);
+            let expected = brute_force(&mesh, &ray);
+            assert_eq!(found.map(|(i, t, _, _)| (i, t)), expected);
+            hits += usize::from(found.is_some());
+        }
+        assert!(hits > 200, "{hits}");
+    }
+
+    #[test]
+    fn barycentrics_and_edges() {
+        let mesh = TriangleMesh::new(
+            vec![Pos(0., 0., 0.), Pos(2., 0., 0.), Pos(0., 2., 0.)],
+            vec![[0, 1, 2]],
+        );
+        let down = |x: Float, y: Float| Ray {
+            origin: Pos(x, y, 1.),
+            dir: Vec3(0., 0., -1.),
+            time: 0.,
+        };
+
+        let (t, b1, b2) = mesh
+            .intersect_triangle(0, &down(0.4, 0.6), 0.0..10.)
+            .unwrap();
+        assert!(near(t, 1.) && near(b1, 0.2) && near(b2, 0.3));
+        // the corners and edges are part of it
+        for (x, y) in [(0., 0.), (2., 0.), (0., 2.), (1., 0.), (0., 1.), (1., 1.)] {
+            assert!(mesh.intersect_triangle(0, &down(x, y), 0.0..10.).is_some());
+        }
+        for (x, y) in [(-0.01, 1.), (1., -0.01), (1.01, 1.), (3., 3.)] {
+            assert!(mesh.intersect_triangle(0, &down(x, y), 0.0..10.).is_none());
+        }
+        // only within the interval
+        assert!(mesh
+            .intersect_triangle(0, &down(0.5, 0.5), 0.0..0.9)
+            .is_none());
+        assert!(mesh
+            .intersect_triangle(0, &down(0.5, 0.5), 1.1..10.)
+            .is_none());
+        // parallel to it, det is zero
+        let along = Ray {
+            origin: Pos(-1., 0.5, 0.),
+            dir: Vec3(1., 0., 0.),
+            time: 0.,
+        };
+        assert!(mesh.intersect_triangle(0, &along, 0.0..10.).is_none());
+
+        // from below it's the back
+        let seen = look_down(&mesh, 0.5, 0.5).unwrap();
+        assert!(seen.front_face && near(seen.normal.2, 1.));
+        let up = Ray {
+            origin: Pos(0.5, 0.5, -1.),
+            dir: Vec3(0., 0., 1.),
+            time: 0.,
+        };
+        let mat = crate::materials::Lambertian {
+            albedo: Color(0.5, 0.5, 0.5),
+        };
+        let object = Mesh::new(&mesh, &mat);
+        let hit_info = object.hit(&up, 0.0..10.).unwrap();
+        assert!(!hit_info.front_face && near(hit_info.normal.to_vec().2, -1.));
+    }
+
+    #[test]
+    fn rays_leaving_a_triangle_dont_hit_it_again() {
+        fastrand::seed(6);
+        let mat = crate::materials::Lambertian {
+            albedo: Color(0.5, 0.5, 0.5),
+        };
+        let mut hits = 0;
+        for _ in 0..2000 {
+            // far from the origin, where positions round the most
+            let offset = random_pos(1000.).to_vec();
+            let mesh = TriangleMesh::new(
+                vec![
+                    random_pos(1.) + offset,
+                    random_pos(1.) + offset,
+                    random_pos(1.) + offset,
+                ],
+                vec![[0, 1, 2]],
+            );
+            let object = Mesh::new(&mesh, &mat);
+            let target = mesh.positions[0]
+                + (mesh.positions[1] - mesh.positions[0]) * 0.3
+                + (mesh.positions[2] - mesh.positions[0]) * 0.3;
+            let origin = target + Vec3::rand_unit_vec() * 5.;
+            let ray = Ray {
+                origin,
+                dir: target - origin,
+                time: 0.,
+            };
+            let Some(hit_info) = object.hit(&ray, 0.0..Float::INFINITY) else {
+                continue;
+            };
+            hits += 1;
+            let bounce = hit_info.spawn_ray(Vec3::random_on_hemisphere(&hit_info.normal), 0.);
+            assert!(object.hit(&bounce, 0.0..Float::INFINITY).is_none());
+        }
+        assert!(hits > 1900, "{hits}");
+    }
+}
@@ -1,3 +1,226 @@
+mod alpha_mask;
+mod capsule;
+mod cone;
+mod cuboid;
+mod cylinder;
+mod disk;
+mod frame;
+mod mesh;
+mod plane;
+mod quad;
+mod roots;
 mod sphere;
+mod sphere_set;
+mod torus;
 
+pub use alpha_mask::*;
+pub use capsule::*;
+pub use cone::*;
+pub use cuboid::*;
+pub use cylinder::*;
+pub use disk::*;
+pub use mesh::*;
+pub use plane::*;
+pub use quad::*;
 pub use sphere::*;
+pub use sphere_set::*;
+pub use torus::*;
+
+use frame::*;
+use roots::*;
+
+use crate::{
+    float::Float,
+    hit::Hit,
+    ray::Ray,
+    vec3::{Normal, Pos, Vec3},
+};
+
+/// Shapes that points can be picked on uniformly by area, so they can be
+/// sampled directly as area lights
+pub trait SampleArea: Hit {
+    fn area(&self) -> Float;
+
+    /// A uniformly distributed point on the surface from a point in [0, 1)^2,
+    /// and the normal there
+    fn sample_point(&self, u: (Float, Float)) -> (Pos, Normal);
+
+    /// A direction from origin towards a point on the surface, and its pdf
+    /// by solid angle. None if the point is seen exactly edge-on
+    fn sample(&self, origin: &Pos, u: (Float, Float)) -> Option<(Vec3, Float)> {
+        let (pos, normal) = self.sample_point(u);
+        let dir = pos - origin;
+        let pdf = solid_angle_pdf(self.area(), &normal, &dir);
+        (pdf.is_finite() && pdf > 0.).then_some((dir, pdf))
+    }
+
+    /// The pdf by solid angle of `sample` picking dir from origin, 0 if the
+    /// direction misses the surface
+    fn pdf(&self, origin: &Pos, dir: &Vec3) -> Float {
+        let ray = Ray {
+            origin: *origin,
+            dir: *dir,
+            time: 0.,
+        };
+        match self.hit(&ray, 0.0..Float::INFINITY) {
+            Some(hit_info) => {
+                let pdf = solid_angle_pdf(self.area(), &hit_info.normal, &(dir * hit_info.t));
+                match pdf.is_finite() {
+                    true => pdf,
+                    false => 0.,
+                }
+            }
+            None => 0.,
+        }
+    }
+}
+
+// uniform area pdf converted to solid angle at the start of `to_point`:
+// distance^2 / (|cos| * area)
+fn solid_angle_pdf(area: Float, normal: &Normal, to_point: &Vec3) -> Float {
+    let dist_squared = to_point.length_squared();
+    let cos = normal.dot(to_point).abs() / dist_squared.sqrt();
+    dist_squared / (cos * area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, float::consts::PI, materials::Lambertian};
+
+    const GRAY: Lambertian = Lambertian {
+        albedo: Color(0.5, 0.5, 0.5),
+    };
+
+    // midpoints of an n by n grid over [0, 1)^2
+    fn grid(n: usize) -> impl Iterator<Item = (Float, Float)> {
+        (0..n * n).map(move |i| {
+            let cell = |k: usize| (k as Float + 0.5) / n as Float;
+            (cell(i % n), cell(i / n))
+        })
+    }
+
+    fn near(a: Float, b: Float, tolerance: Float) -> bool {
+        (a - b).abs() <= tolerance * b.abs()
+    }
+
+    // the solid angle a shape covers seen from origin is the mean of 1 / pdf
+    // over the directions it samples, which also have to agree with pdf()
+    fn solid_angle(shape: &impl SampleArea, origin: &Pos) -> Float {
+        let samples: Vec<_> = grid(200).filter_map(|u| shape.sample(origin, u)).collect();
+        for (dir, pdf) in samples.iter().step_by(97) {
+            assert!(near(shape.pdf(origin, dir), *pdf, 1e-3), "{pdf}");
+        }
+        samples.iter().map(|(_, pdf)| 1. / pdf).sum::<Float>() / (200 * 200) as Float
+    }
+
+    // the pdf over every direction adds up to one
+    fn total_pdf(shape: &impl SampleArea, origin: &Pos) -> Float {
+        let n = 400;
+        grid(n)
+            .map(|u| shape.pdf(origin, &Vec3::unit_vec_from_sample(u)))
+            .sum::<Float>()
+            * (4. * PI / (n * n) as Float)
+    }
+
+    // the solid angle of a w by h rectangle seen from a point at distance d
+    // above one of its corners
+    fn corner_solid_angle(w: Float, h: Float, d: Float) -> Float {
+        (w * h / (d * (w * w + h * h + d * d).sqrt())).atan()
+    }
+
+    #[test]
+    fn quad() {
+        let quad = Quad::new(
+            Pos(-1., -1.5, 0.),
+            Vec3(2., 0., 0.),
+            Vec3(0., 3., 0.),
+            &GRAY,
+        );
+        assert!(near(quad.area(), 6., 1e-6));
+        for u in grid(10) {
+            let (pos, normal) = quad.sample_point(u);
+            assert!(pos.x().abs() <= 1. && pos.y().abs() <= 1.5 && pos.z() == 0.);
+            assert!(near(normal.to_vec().z(), 1., 1e-6));
+        }
+
+        // straight above the middle, the pdf is distance^2 / area
+        let above = Pos(0., 0., 2.);
+        assert!(near(quad.pdf(&above, &Vec3(0., 0., -1.)), 4. / 6., 1e-6));
+        assert_eq!(quad.pdf(&above, &Vec3(0., 0., 1.)), 0.);
+        assert_eq!(quad.pdf(&above, &Vec3(5., 0., -1.)), 0.);
+
+        let expected = 4. * corner_solid_angle(1., 1.5, 2.);
+        assert!(near(solid_angle(&quad, &above), expected, 1e-3));
+        let over_corner = Pos(-1., -1.5, 0.5);
+        let expected = corner_solid_angle(2., 3., 0.5);
+        assert!(near(solid_angle(&quad, &over_corner), expected, 1e-2));
+
+        assert!(near(total_pdf(&quad, &above), 1., 2e-2));
+        // seen edge on there's nothing to pick
+        assert!(quad.sample(&Pos(5., 0., 0.), (0.5, 0.5)).is_none());
+    }
+
+    #[test]
+    fn disk() {
+        let disk = Disk::new(Pos(1., 2., 3.), Vec3(0., 1., 0.), 2., &GRAY);
+        assert!(near(disk.area(), 4. * PI, 1e-6));
+        for u in grid(10) {
+            let (pos, normal) = disk.sample_point(u);
+            let offset = pos - Pos(1., 2., 3.);
+            assert!(offset.length() <= 2. + 1e-6 && offset.y().abs() < 1e-6);
+            assert!(near(normal.to_vec().y(), 1., 1e-6));
+        }
+
+        // on the axis the solid angle is 2 pi (1 - cos) of the rim's angle
+        for h in [0.5, 3., 10.] {
+            let origin = Pos(1., 2. + h, 3.);
+            let expected = 2. * PI * (1. - h / (h * h + 4.).sqrt());
+            assert!(near(solid_angle(&disk, &origin), expected, 1e-2), "{h}");
+            assert!(near(
+                disk.pdf(&origin, &Vec3(0., -1., 0.)),
+                h * h / disk.area(),
+                1e-6
+            ));
+        }
+        // from below too, the disk is two sided
+        let below = Pos(1., -1., 3.);
+        let expected = 2. * PI * (1. - 3. / (13 as Float).sqrt());
+        assert!(near(solid_angle(&disk, &below), expected, 1e-2));
+        assert!(near(total_pdf(&disk, &below), 1., 2e-2));
+    }
+
+    #[test]
+    fn cuboid() {
+        let cuboid = Cuboid::new(Pos(0., 0., 0.), Pos(1., 2., 3.), &GRAY);
+        assert!(near(cuboid.area(), 22., 1e-6));
+
+        // every face gets its share of the points
+        let mut on_face = [0; 6];
+        for i in 0..10_000 {
+            let (pos, _) = cuboid.sample_point(((i as Float + 0.5) / 10_000., 0.5));
+            let face = [
+                pos.x() == 0.,
+                pos.x() == 1.,
+                pos.y() == 0.,
+                pos.y() == 2.,
+                pos.z() == 0.,
+                pos.z() == 3.,
+            ];
+            on_face[face.iter().position(|&on| on).unwrap()] += 1;
+        }
+        for (count, area) in on_face.iter().zip([6., 6., 3., 3., 2., 2.]) {
+            assert!(near(*count as Float, 10_000. * area / 22., 1e-2));
+        }
+
+        // in front of the middle of a face the box's outline is that face
+        let origin = Pos(0.5, 1., 5.);
+        let expected = 4. * corner_solid_angle(0.5, 1., 2.);
+        assert!(near(solid_angle(&cuboid, &origin), expected, 1e-2));
+        // a line through it crosses two faces, either could have been picked: each adds
+        // distance^2 / the whole area
+        let straight = cuboid.pdf(&origin, &Vec3(0., 0., -1.));
+        assert!(near(straight, (2. * 2. + 5. * 5.) / 22., 1e-6));
+        assert!(near(total_pdf(&cuboid, &origin), 1., 2e-2));
+    }
+}
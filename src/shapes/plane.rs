@@ -0,0 +1,109 @@
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    float::{gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// An infinite plane through a point. Texture coordinates repeat every unit
+/// along two directions in the plane.
+pub struct Plane<'a> {
+    pub point: Pos,
+    normal: Normal,
+    // unit vectors along the plane, for the texture coordinates
+    tangent: Vec3,
+    bitangent: Vec3,
+
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Plane<'a> {
+    /// The front face is the side the normal points to
+    pub fn new(point: Pos, normal: Vec3, mat: &'a (dyn Material + Sync)) -> Self {
+        let normal = Normal::new(normal);
+        let (tangent, bitangent) = normal.to_vec().orthonormal_basis();
+        Self {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            mat,
+        }
+    }
+
+    pub fn normal(&self) -> Normal {
+        self.normal
+    }
+}
+
+impl Hit for Plane<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let t = plane_t(&self.point, &self.normal, ray);
+        // also rejects rays parallel to the plane, t is infinite or NaN
+        if !(ray_t_interval.start < t && t < ray_t_interval.end) {
+            return None;
+        }
+
+        let p = ray.at(t) - self.point;
+        let (a, b) = (p.dot(&self.tangent), p.dot(&self.bitangent));
+        let (pos, pos_error) = planar_point(&self.point, self.tangent * a, self.bitangent * b);
+        let front_face = self.normal.dot(&ray.dir) < 0.;
+        let normal = if front_face {
+            self.normal
+        } else {
+            -self.normal
+        };
+        Some(HitInfo {
+            pos,
+            pos_error,
+            normal,
+            geometric_normal: normal,
+            t,
+            front_face,
+            u: a.rem_euclid(1.),
+            v: b.rem_euclid(1.),
+            dpdu: self.tangent,
+            dpdv: self.bitangent,
+            vertex_color: Color(1., 1., 1.),
+            object_id: 0,
+            mat: self.mat,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // infinite, except along the axis of an axis-aligned plane
+        let n = self.normal.to_vec();
+        let extent = |n_axis: Float, p_axis: Float| match n_axis.abs() == 1. {
+            true => (p_axis, p_axis),
+            false => (Float::NEG_INFINITY, Float::INFINITY),
+        };
+        let (x, y, z) = (
+            extent(n.x(), self.point.x()),
+            extent(n.y(), self.point.y()),
+            extent(n.z(), self.point.z()),
+        );
+        Aabb::new(Pos(x.0, y.0, z.0), Pos(x.1, y.1, z.1))
+    }
+}
+
+/// Ray parameter where the ray crosses the plane through point with normal n.
+/// Infinite or NaN for rays parallel to the plane
+pub(crate) fn plane_t(point: &Pos, normal: &Normal, ray: &Ray) -> Float {
+    // from the difference of the points rather than n . point - n . origin,
+    // which cancels badly far from the world origin
+    normal.dot(&(point - ray.origin)) / normal.dot(&ray.dir)
+}
+
+/// A point on a flat surface and its rounding error, built as origin + a + b
+/// from its coordinates along the surface. Unlike ray.at(t) it's only off by
+/// the error of the sum, the coordinates' own error just moves it along the
+/// surface.
+pub(crate) fn planar_point(origin: &Pos, a: Vec3, b: Vec3) -> (Pos, Vec3) {
+    let pos = origin + a + b;
+    let pos_error = (origin.to_vec().abs() + a.abs() + b.abs()) * gamma(3);
+    (pos, pos_error)
+}
@@ -0,0 +1,93 @@
+use super::{planar_point, plane_t, SampleArea};
+use crate::{aabb::Aabb, color::Color, float::Float, hit::*, materials::Material, ray::*, vec3::*};
+use std::ops::Range;
+
+/// A parallelogram with a corner at q and sides u and v.
+/// The texture coordinates go from 0 to 1 along u and v.
+pub struct Quad<'a> {
+    pub q: Pos,
+    pub u: Vec3,
+    pub v: Vec3,
+    // u x v normalized, the front face is the side it points to
+    normal: Normal,
+    // u x v / |u x v|^2, for projecting onto u and v
+    w: Vec3,
+    area: Float,
+
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Quad<'a> {
+    pub fn new(q: Pos, u: Vec3, v: Vec3, mat: &'a (dyn Material + Sync)) -> Self {
+        let n = u.cross(&v);
+        Self {
+            q,
+            u,
+            v,
+            normal: Normal::new(n),
+            w: n / n.length_squared(),
+            area: n.length(),
+            mat,
+        }
+    }
+
+    pub fn normal(&self) -> Normal {
+        self.normal
+    }
+}
+
+impl Hit for Quad<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let t = plane_t(&self.q, &self.normal, ray);
+        // also rejects rays parallel to the quad, t is infinite or NaN
+        if !(ray_t_interval.start < t && t < ray_t_interval.end) {
+            return None;
+        }
+
+        // coordinates of the hit along the sides
+        let p = ray.at(t) - self.q;
+        let alpha = self.w.dot(&p.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&p));
+        if !((0. ..=1.).contains(&alpha) && (0. ..=1.).contains(&beta)) {
+            return None;
+        }
+
+        let (pos, pos_error) = planar_point(&self.q, self.u * alpha, self.v * beta);
+        let front_face = self.normal.dot(&ray.dir) < 0.;
+        let normal = if front_face {
+            self.normal
+        } else {
+            -self.normal
+        };
+        Some(HitInfo {
+            pos,
+            pos_error,
+            normal,
+            geometric_normal: normal,
+            t,
+            front_face,
+            u: alpha,
+            v: beta,
+            dpdu: self.u,
+            dpdv: self.v,
+            vertex_color: Color(1., 1., 1.),
+            object_id: 0,
+            mat: self.mat,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.q, self.q + self.u + self.v)
+            .union(&Aabb::new(self.q + self.u, self.q + self.v))
+    }
+}
+
+impl SampleArea for Quad<'_> {
+    fn area(&self) -> Float {
+        self.area
+    }
+
+    fn sample_point(&self, (s, t): (Float, Float)) -> (Pos, Normal) {
+        (self.q + self.u * s + self.v * t, self.normal)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{float::Float, vec3::Vec3};
+
+/// Roots of a x^2 + b x + c in ascending order. Neither is computed as a
+/// difference of similar numbers, which would lose the small root.
+pub(crate) fn quadratic(a: Float, b: Float, c: Float) -> Option<(Float, Float)> {
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. || discriminant.is_nan() {
+        return None;
+    }
+    let q = -0.5 * (b + Float::copysign(discriminant.sqrt(), b));
+    let (t0, t1) = (q / a, c / q);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Roots of |o + t d| = radius in ascending order, using the same
+/// cancellation-free discriminant as `Sphere::hit`
+pub(crate) fn centered_quadratic(o: &Vec3, d: &Vec3, radius: Float) -> Option<(Float, Float)> {
+    let a = d.length_squared();
+    let h = -d.dot(o);
+    let l = o + d * (h / a);
+    let discriminant = a * (radius * radius - l.length_squared());
+    // NaN for rays without direction
+    if discriminant < 0. || discriminant.is_nan() {
+        return None;
+    }
+    let q = h + Float::copysign(discriminant.sqrt(), h);
+    let c = o.length_squared() - radius * radius;
+    let (t0, t1) = (q / a, c / q);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Real roots in (lo, hi] of the polynomial coeffs[0] + coeffs[1] x + ...
+/// of degree 1 to 4, in ascending order. Returns how many were written.
+///
+/// The roots of the derivative split the range into pieces where the
+/// polynomial only goes up or only goes down, so each has at most one root,
+/// found by bisection sped up with newton steps. Slower than solving the
+/// quartic in closed form, but doesn't fall apart numerically.
+pub(crate) fn polynomial_roots(
+    coeffs: &[Float],
+    lo: Float,
+    hi: Float,
+    roots: &mut [Float],
+) -> usize {
+    let degree = coeffs.len() - 1;
+    if degree == 1 {
+        let root = -coeffs[0] / coeffs[1];
+        return match lo < root && root <= hi {
+            true => {
+                roots[0] = root;
+                1
+            }
+            false => 0,
+        };
+    }
+
+    let mut derivative = [0.; 4];
+    for i in 1..=degree {
+        derivative[i - 1] = coeffs[i] * i as Float;
+    }
+    let mut extremes = [0.; 4];
+    let n = polynomial_roots(&derivative[..degree], lo, hi, &mut extremes);
+
+    let mut count = 0;
+    let mut start = lo;
+    for end in extremes[..n].iter().copied().chain([hi]) {
+        if let Some(root) = monotonic_root(coeffs, start, end) {
+            roots[count] = root;
+            count += 1;
+        }
+        start = end;
+    }
+    count
+}
+
+// value and derivative by Horner's method
+fn eval(coeffs: &[Float], x: Float) -> (Float, Float) {
+    coeffs
+        .iter()
+        .rev()
+        .fold((0., 0.), |(f, df), c| (f * x + c, df * x + f))
+}
+
+// the root in (a, b] of a polynomial that's monotonic there, if it has one
+fn monotonic_root(coeffs: &[Float], mut a: Float, mut b: Float) -> Option<Float> {
+    let (fa, _) = eval(coeffs, a);
+    let (fb, _) = eval(coeffs, b);
+    if fb == 0. {
+        return Some(b);
+    }
+    // a root at a belongs to the previous piece
+    if fa == 0. || (fa < 0.) == (fb < 0.) {
+        return None;
+    }
+
+    let mut x = 0.5 * (a + b);
+    for _ in 0..64 {
+        let (f, df) = eval(coeffs, x);
+        if f == 0. {
+            break;
+        }
+        // keep the root bracketed
+        if (f < 0.) == (fa < 0.) {
+            a = x;
+        } else {
+            b = x;
+        }
+        let newton = x - f / df;
+        let next = match a < newton && newton < b {
+            true => newton,
+            false => 0.5 * (a + b),
+        };
+        if next == x || !(a < next && next < b) {
+            break;
+        }
+        x = next;
+    }
+    Some(x)
+}
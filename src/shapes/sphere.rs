@@ -1,10 +1,24 @@
-use crate::{hit::*, materials::Material, ray::*, vec3::*};
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    float::{consts, gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
 use std::ops::Range;
 
 pub struct Sphere<'a> {
+    // center at time0
     pub center: Pos,
+    // how far the center moves between time0 and time1
+    pub motion: Vec3,
+    // the center stays put before time0 and after time1
+    pub time0: Float,
+    pub time1: Float,
     // radius should be positive
-    pub radius: f64,
+    pub radius: Float,
 
     // material
     // could be seperated - geometry and material seperate
@@ -12,53 +26,152 @@ pub struct Sphere<'a> {
 }
 
 impl<'a> Sphere<'a> {
-    pub fn new(center: Pos, radius: f64, mat: &'a (dyn Material + Sync)) -> Self {
+    pub fn new(center: Pos, radius: Float, mat: &'a (dyn Material + Sync)) -> Self {
         Self {
             center,
+            motion: Vec3(0., 0., 0.),
+            time0: 0.,
+            time1: 1.,
             radius,
             mat,
         }
     }
+
+    /// A sphere moving in a straight line from center_start at time 0
+    /// to center_end at time 1, see `with_motion_times` for others
+    pub fn moving(
+        center_start: Pos,
+        center_end: Pos,
+        radius: Float,
+        mat: &'a (dyn Material + Sync),
+    ) -> Self {
+        Self {
+            motion: center_end - center_start,
+            ..Self::new(center_start, radius, mat)
+        }
+    }
+
+    /// Moves between time0 and time1 instead of 0 and 1
+    pub fn with_motion_times(self, time0: Float, time1: Float) -> Self {
+        assert!(time0 < time1, "motion has to end after it starts");
+        Self {
+            time0,
+            time1,
+            ..self
+        }
+    }
+
+    pub fn center_at(&self, time: Float) -> Pos {
+        self.center + self.motion * motion_progress(time, self.time0, self.time1)
+    }
+
+    /// Texture coordinates of a point on the unit sphere.
+    /// u: angle around the y axis from x = -1, scaled to [0, 1]
+    /// v: angle from y = -1 to y = +1, scaled to [0, 1]
+    fn uv(point: &Vec3) -> (Float, Float) {
+        use consts::PI;
+
+        let theta = (-point.y()).acos();
+        let phi = (-point.z()).atan2(point.x()) + PI;
+        (phi / (2. * PI), theta / PI)
+    }
+
+    // derivatives of the point radius * n with the uv above, dpdv is zero
+    // at the poles
+    fn partials(n: &Vec3, radius: Float) -> (Vec3, Vec3) {
+        use consts::PI;
+
+        let dpdu = Vec3(n.z(), 0., -n.x()) * (2. * PI * radius);
+        let sin_theta = (n.x() * n.x() + n.z() * n.z()).sqrt();
+        let dpdv = match sin_theta > 0. {
+            true => {
+                let k = -n.y() / sin_theta;
+                Vec3(n.x() * k, sin_theta, n.z() * k) * (PI * radius)
+            }
+            false => Vec3(0., 0., 0.),
+        };
+        (dpdu, dpdv)
+    }
 }
 
 impl Hit for Sphere<'_> {
-    fn hit(&self, ray: &Ray, ray_t_interval: Range<f64>) -> Option<HitInfo> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
         // quadratic formula
         // simplified when b = -2h
 
-        let oc = self.center - ray.origin;
+        let center = self.center_at(ray.time);
+        let oc = center - ray.origin;
 
         let a = ray.dir.length_squared();
         let h = ray.dir.dot(&oc);
-        let c = oc.length_squared() - self.radius.powi(2);
 
-        let discriminant = h * h - a * c;
+        // h^2 - ac rearranged to a(r^2 - l^2), with l the distance from the
+        // center to the line of the ray. doesn't cancel catastrophically like
+        // h^2 - ac does for rays passing close to the surface (pbrt-v4 6.2.2)
+        let l = oc - ray.dir * (h / a);
+        let discriminant = a * (self.radius * self.radius - l.length_squared());
 
         if discriminant < 0. {
             return None;
         }
 
         // calculate nearest intersection
+        // that counts as past the start of the interval only if it's further than
+        // its rounding error, so rays leaving the surface don't hit it again
         let sqrt_d = discriminant.sqrt();
+        let t_error = gamma(8) * (h.abs() + sqrt_d) / a;
+        let in_interval = |t: Float| ray_t_interval.start < t - t_error && t < ray_t_interval.end;
         let mut t = (h - sqrt_d) / a;
-        if !ray_t_interval.contains(&t) {
+        if !in_interval(t) {
             t = (h + sqrt_d) / a;
-            if !ray_t_interval.contains(&t) {
+            if !in_interval(t) {
                 return None;
             }
         }
 
-        let pos = ray.at(t);
-        let out_normal = (pos - self.center) / self.radius;
+        Some(self.hit_info_at(ray, t))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vec3(self.radius, self.radius, self.radius);
+        let start = Aabb::new(self.center - r, self.center + r);
+        start.union(&start.translate(&self.motion))
+    }
+}
+
+impl Sphere<'_> {
+    /// Hit info for a ray already known to hit the sphere at t
+    pub(crate) fn hit_info_at(&self, ray: &Ray, t: Float) -> HitInfo<'_> {
+        let center = self.center_at(ray.time);
+        // ray.at(t) inherits all the error of t, reprojecting onto the surface
+        // gets much closer
+        let out = ray.at(t) - center;
+        let out = out * (self.radius.abs() / out.length());
+        let pos = center + out;
+        // error of the reprojection, then of adding the center back (pbrt 6.8.5)
+        let pos_error = out.abs() * gamma(5) + (center.to_vec().abs() + out.abs()) * gamma(1);
+
+        // divided by the radius so a negative radius flips the normal inwards
+        let out_normal = Normal::new(out / self.radius);
         let front_face = out_normal.dot(&ray.dir) < 0.;
         let normal = if front_face { out_normal } else { -out_normal };
-        Some(HitInfo {
+        let (u, v) = Sphere::uv(&out_normal.to_vec());
+        let (dpdu, dpdv) = Sphere::partials(&out_normal.to_vec(), self.radius);
+        HitInfo {
             pos,
+            pos_error,
             normal,
+            geometric_normal: normal,
             t,
             front_face,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            vertex_color: Color(1., 1., 1.),
+            object_id: 0,
             mat: self.mat,
-        })
+        }
     }
 }
 
@@ -73,6 +186,57 @@ impl Hit for Sphere<'_> {
 //         radius: 0.5,
 //     };
 
-//     let hit_info = sphere.hit(&ray, 0.0..f64::INFINITY).unwrap();
+//     let hit_info = sphere.hit(&ray, 0.0..Float::INFINITY).unwrap();
 //     assert!(hit_info.t == 0.5);
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Lambertian;
+
+    const GRAY: Lambertian = Lambertian {
+        albedo: Color(0.5, 0.5, 0.5),
+    };
+
+    fn x_at(sphere: &Sphere, time: Float) -> Option<Float> {
+        // straight down the y axis at x = 0, only hits while the sphere is
+        // near the origin
+        let ray = Ray {
+            origin: Pos(0., 10., 0.),
+            dir: Vec3(0., -1., 0.),
+            time,
+        };
+        sphere
+            .hit(&ray, 0.0..Float::INFINITY)
+            .map(|_| sphere.center_at(time).x())
+    }
+
+    #[test]
+    fn motion_times() {
+        let sphere =
+            Sphere::moving(Pos(-4., 0., 0.), Pos(4., 0., 0.), 0.5, &GRAY).with_motion_times(3., 7.);
+        assert_eq!(sphere.center_at(0.).x(), -4.);
+        assert_eq!(sphere.center_at(3.).x(), -4.);
+        assert_eq!(sphere.center_at(5.).x(), 0.);
+        assert_eq!(sphere.center_at(7.).x(), 4.);
+        assert_eq!(sphere.center_at(100.).x(), 4.);
+        // halfway through the motion it's at the origin, not at time 0.5
+        assert_eq!(x_at(&sphere, 5.), Some(0.));
+        assert_eq!(x_at(&sphere, 0.5), None);
+
+        // and the box covers it wherever it is
+        let bounds = sphere.bounding_box();
+        for time in [-1., 3., 4.2, 5., 6.9, 7., 20.] {
+            let c = sphere.center_at(time);
+            assert!(bounds.min.x() <= c.x() - 0.5 && c.x() + 0.5 <= bounds.max.x());
+        }
+    }
+
+    #[test]
+    fn default_motion_times() {
+        let sphere = Sphere::moving(Pos(0., 0., 0.), Pos(2., 0., 0.), 0.5, &GRAY);
+        assert_eq!(sphere.center_at(0.5).x(), 1.);
+        assert_eq!(sphere.center_at(2.).x(), 2.);
+    }
+}
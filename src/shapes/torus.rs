@@ -0,0 +1,272 @@
+use super::{angle_u, centered_quadratic, polynomial_roots, Frame, LocalHit};
+use crate::{
+    aabb::Aabb,
+    float::{consts, gamma, Float},
+    hit::*,
+    materials::Material,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// A ring: the points at minor_radius from a circle of major_radius.
+/// u: angle around the axis, scaled to [0, 1]
+/// v: angle around the tube, from the outer equator, scaled to [0, 1]
+pub struct Torus<'a> {
+    frame: Frame,
+    pub major_radius: Float,
+    pub minor_radius: Float,
+
+    pub mat: &'a (dyn Material + Sync),
+}
+
+impl<'a> Torus<'a> {
+    /// Torus around an axis through the center
+    pub fn new(
+        center: Pos,
+        axis: Vec3,
+        major_radius: Float,
+        minor_radius: Float,
+        mat: &'a (dyn Material + Sync),
+    ) -> Self {
+        Self {
+            frame: Frame::new(center, axis),
+            major_radius,
+            minor_radius,
+            mat,
+        }
+    }
+}
+
+impl Torus<'_> {
+    // the quartic is ((rho - R)^2 + z^2 - r^2) ((rho + R)^2 + z^2 - r^2),
+    // with rho the distance from the axis. the second factor is never zero,
+    // so the first has the same roots without the expanded form's
+    // cancellation. returns it at o + t d, its derivative by t, and a bound
+    // on its rounding error
+    fn surface(&self, o: &Vec3, d: &Vec3, t: Float) -> (Float, Float, Float) {
+        let (big_r, r) = (self.major_radius, self.minor_radius);
+        let p = o + d * t;
+        let rho = (p.0 * p.0 + p.1 * p.1).sqrt();
+        let drho = match rho > 0. {
+            true => (p.0 * d.0 + p.1 * d.1) / rho,
+            false => 0.,
+        };
+        let (radial, z) = (rho - big_r, p.2);
+        let f = radial * radial + z * z - r * r;
+        let df = 2. * (radial * drho + z * d.2);
+        // p, and so rho, are off by about the size of the numbers that went
+        // into them, which the squares scale by their distance from the tube
+        let size = o.length() + d.length() * t.abs() + big_r;
+        let error =
+            gamma(8) * (2. * (radial.abs() + z.abs()) * size + radial * radial + z * z + r * r);
+        (f, df, error)
+    }
+
+    // a few newton steps, as long as they stay small enough not to wander
+    // off to another root
+    fn polish(&self, o: &Vec3, d: &Vec3, mut t: Float) -> Float {
+        let max_step = self.minor_radius / d.length();
+        for _ in 0..8 {
+            let (f, df, _) = self.surface(o, d, t);
+            let step = f / df;
+            if f == 0. || !step.is_finite() || step.abs() > max_step {
+                break;
+            }
+            let next = t - step;
+            if next == t {
+                break;
+            }
+            t = next;
+        }
+        t
+    }
+
+    // with single precision floats the quartic can lose the two close roots
+    // of a ray grazing a thin tube, and the ray seems to leave the tube
+    // without having gone in. if it started outside, the way in is between
+    // the start and t, where the surface function changes sign
+    fn missed_entry(&self, o: &Vec3, d: &Vec3, start: Float, t: Float) -> Option<Float> {
+        let (f_start, _, _) = self.surface(o, d, start);
+        let (_, df, _) = self.surface(o, d, t);
+        if f_start <= 0. || df <= 0. {
+            return None;
+        }
+        // a point just before t that's inside, the chord can be shorter
+        // than t's error
+        let mut back = self.root_error(o, d, t);
+        let mut inside = t - back;
+        while !(start < inside && self.surface(o, d, inside).0 < 0.) {
+            back *= 0.5;
+            inside = t - back;
+            if inside >= t {
+                return None;
+            }
+        }
+        let mut outside = start;
+        for _ in 0..64 {
+            let mid = 0.5 * (outside + inside);
+            if mid <= outside || mid >= inside {
+                break;
+            }
+            match self.surface(o, d, mid).0 < 0. {
+                true => inside = mid,
+                false => outside = mid,
+            }
+        }
+        Some(inside)
+    }
+
+    // how far off a polished root can be: the surface function's error
+    // over how steeply the ray crosses it. rays leaving the surface at a
+    // glancing angle get a wide margin, as they should
+    fn root_error(&self, o: &Vec3, d: &Vec3, t: Float) -> Float {
+        let (_, df, error) = self.surface(o, d, t);
+        2. * error / df.abs()
+    }
+}
+
+impl Hit for Torus<'_> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let (o, d) = self.frame.ray_to_local(ray);
+        let (big_r, r) = (self.major_radius, self.minor_radius);
+
+        // only the part of the ray inside the bounding sphere is searched.
+        // moving the start there and using a unit direction keeps the
+        // numbers in the quartic small
+        let (t_in, t_out) = centered_quadratic(&o, &d, big_r + r)?;
+        let (t_start, t_end) = (
+            t_in.max(ray_t_interval.start),
+            t_out.min(ray_t_interval.end),
+        );
+        if t_start >= t_end {
+            return None;
+        }
+        let len = d.length();
+        let du = d / len;
+        let start = o + d * t_start;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2), with p = start + s du
+        let alpha = start.dot(&du);
+        let beta = start.length_squared() + big_r * big_r - r * r;
+        let four_r2 = 4. * big_r * big_r;
+        let coeffs = [
+            beta * beta - four_r2 * (start.0 * start.0 + start.1 * start.1),
+            4. * alpha * beta - 2. * four_r2 * (start.0 * du.0 + start.1 * du.1),
+            4. * alpha * alpha + 2. * beta - four_r2 * (du.0 * du.0 + du.1 * du.1),
+            4. * alpha,
+            1.,
+        ];
+        let mut roots = [0.; 4];
+        let n = polynomial_roots(&coeffs, 0., (t_end - t_start) * len, &mut roots);
+
+        // the expanded quartic loses about (R / r)^2 of its precision to
+        // cancellation, enough for rays leaving thin tori to find the surface
+        // they start on again. the roots are polished on the factored form,
+        // and only count if they really are roots of it and are past the
+        // start by more than what's left of their error
+        let t = roots[..n]
+            .iter()
+            .map(|s| self.polish(&o, &d, t_start + s / len))
+            .find(|&t| {
+                let (f, _, error) = self.surface(&o, &d, t);
+                f.abs() <= 2. * error
+                    && ray_t_interval.start < t - self.root_error(&o, &d, t)
+                    && t < ray_t_interval.end
+            })?;
+        let t = self.missed_entry(&o, &d, t_start, t).unwrap_or(t);
+
+        // pushed back onto the surface: the nearest point on the center
+        // circle, then out by the minor radius
+        let p = o + d * t;
+        let xy = Vec3(p.0, p.1, 0.);
+        let ring = xy * (big_r / xy.length());
+        let out = p - ring;
+        let out = out * (r / out.length());
+        let p = ring + out;
+
+        let tube_angle = out.2.atan2(out.dot(&xy.unit_vec()));
+        let hit = LocalHit {
+            t,
+            p,
+            p_error: (ring.abs() + out.abs()) * gamma(6),
+            normal: out / r,
+            u: angle_u(&p),
+            v: (tube_angle / (2. * consts::PI)).rem_euclid(1.),
+            // around the tube
+            dpdv: {
+                let radial = xy.unit_vec();
+                Vec3(-out.2 * radial.0, -out.2 * radial.1, out.dot(&radial)) * (2. * consts::PI)
+            },
+        };
+        Some(self.frame.hit_info(ray, hit, self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let (big_r, r) = (self.major_radius, self.minor_radius);
+        let reach = big_r + r;
+        self.frame
+            .bounds(Vec3(-reach, -reach, -r), Vec3(reach, reach, r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, float::random, materials::Lambertian};
+
+    const GRAY: Lambertian = Lambertian {
+        albedo: Color(0.5, 0.5, 0.5),
+    };
+
+    // hits the torus from all around, aiming near its surface, then sends a
+    // ray off every hit into the hemisphere it was hit from. returns how
+    // many hits there were, and how many of the rays leaving came back into
+    // the surface they left, meaning they hit it from the inside right away
+    fn rehits(torus: &Torus, count: usize) -> (usize, usize) {
+        let (big_r, r) = (torus.major_radius, torus.minor_radius);
+        let (mut hits, mut rehits) = (0, 0);
+        for _ in 0..count {
+            let origin = Pos::ORIGIN + Vec3::rand_unit_vec() * (3. * (big_r + r));
+            // a point on the surface, give or take a little
+            let (a, b) = (random() * 2. * consts::PI, random() * 2. * consts::PI);
+            let radial = Vec3(a.cos(), a.sin(), 0.);
+            let target = radial * (big_r + r * b.cos()) + Vec3(0., 0., r * b.sin());
+            let target = target + Vec3::rand_unit_vec() * (0.5 * r * random());
+            let ray = Ray {
+                origin,
+                dir: (Pos::ORIGIN + target) - origin,
+                time: 0.,
+            };
+            // single precision can lose both ends of a chord grazing the
+            // tube, and hit the far wall from inside. a ray bouncing back
+            // in from there is right to hit the tube again
+            let Some(hit_info) = torus.hit(&ray, 0.0..Float::INFINITY) else {
+                continue;
+            };
+            if !hit_info.front_face {
+                continue;
+            }
+            hits += 1;
+            let bounce = hit_info.spawn_ray(Vec3::random_on_hemisphere(&hit_info.normal), 0.);
+            if let Some(again) = torus.hit(&bounce, 0.0..Float::INFINITY) {
+                let dist = again.t * bounce.dir.length();
+                if !again.front_face && dist < r {
+                    rehits += 1;
+                }
+            }
+        }
+        (hits, rehits)
+    }
+
+    #[test]
+    fn rays_leaving_thin_tori_dont_hit_them_again() {
+        fastrand::seed(3);
+        for (big_r, r) in [(1., 0.3), (1., 0.01), (100., 0.5), (1., 0.001), (5., 0.2)] {
+            let torus = Torus::new(Pos::ORIGIN, Vec3(0., 0., 1.), big_r, r, &GRAY);
+            let (hits, rehits) = rehits(&torus, 20_000);
+            assert!(hits > 1000, "{big_r} {r}");
+            assert_eq!(rehits, 0, "{big_r} {r}");
+        }
+    }
+}
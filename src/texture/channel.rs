@@ -0,0 +1,26 @@
+use super::Texture;
+use crate::{color::Color, float::Float, hit::HitInfo};
+
+/// One channel of a color texture as a number, for masks and the packed
+/// parameter images of glTF. 0 is red, 1 green and 2 blue.
+pub struct ColorChannel<T: Texture<Color>> {
+    pub texture: T,
+    pub channel: usize,
+}
+
+impl<T: Texture<Color>> ColorChannel<T> {
+    pub fn new(texture: T, channel: usize) -> Self {
+        Self { texture, channel }
+    }
+}
+
+impl<T: Texture<Color>> Texture<Float> for ColorChannel<T> {
+    fn value(&self, hit_info: &HitInfo) -> Float {
+        let color = self.texture.value(hit_info);
+        match self.channel {
+            0 => color.r(),
+            1 => color.g(),
+            _ => color.b(),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use super::Texture;
+use crate::{float::Float, hit::HitInfo};
+
+/// A 3d checkerboard in world space, alternating between two textures every
+/// `scale` units along each axis
+pub struct Checker<T> {
+    pub inv_scale: Float,
+    pub even: Box<dyn Texture<T> + Sync>,
+    pub odd: Box<dyn Texture<T> + Sync>,
+}
+
+impl<T> Checker<T> {
+    pub fn new(
+        scale: Float,
+        even: impl Texture<T> + Sync + 'static,
+        odd: impl Texture<T> + Sync + 'static,
+    ) -> Self {
+        Self {
+            inv_scale: 1. / scale,
+            even: Box::new(even),
+            odd: Box::new(odd),
+        }
+    }
+}
+
+impl<T> Texture<T> for Checker<T> {
+    fn value(&self, hit_info: &HitInfo) -> T {
+        let p = hit_info.pos;
+        let cell = |x: Float| (x * self.inv_scale).floor() as i64;
+        match (cell(p.0) + cell(p.1) + cell(p.2)).rem_euclid(2) {
+            0 => self.even.value(hit_info),
+            _ => self.odd.value(hit_info),
+        }
+    }
+}
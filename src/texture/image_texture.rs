@@ -0,0 +1,44 @@
+use super::Texture;
+use crate::{color::Color, float::Float, hit::HitInfo, image::Image};
+
+/// An image wrapped over the surface's uv coordinates, repeating outside
+/// [0, 1]. v = 0 is the bottom row of the image.
+pub struct ImageTexture {
+    pub image: Image,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> Self {
+        Self { image }
+    }
+
+    /// Bilinearly filtered color at the uv coordinates
+    pub fn lookup(&self, u: Float, v: Float) -> Color {
+        let (width, height) = (self.image.width(), self.image.height());
+        if width == 0 || height == 0 {
+            // the classic cyan of a missing texture
+            return Color(0., 1., 1.);
+        }
+
+        // pixel centers sit at half integers
+        let x = u.rem_euclid(1.) * width as Float - 0.5;
+        let y = (1. - v.rem_euclid(1.)) * height as Float - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let texel = |x: Float, y: Float| {
+            let x = (x as i64).rem_euclid(width as i64) as usize;
+            let y = (y as i64).rem_euclid(height as i64) as usize;
+            self.image.get(x, y)
+        };
+        let top = texel(x0, y0) * (1. - fx) + texel(x0 + 1., y0) * fx;
+        let bottom = texel(x0, y0 + 1.) * (1. - fx) + texel(x0 + 1., y0 + 1.) * fx;
+        top * (1. - fy) + bottom * fy
+    }
+}
+
+impl Texture<Color> for ImageTexture {
+    fn value(&self, hit_info: &HitInfo) -> Color {
+        self.lookup(hit_info.u, hit_info.v)
+    }
+}
@@ -0,0 +1,27 @@
+use super::Texture;
+use crate::{color::Color, float::Float, hit::HitInfo, medium::Perlin};
+
+/// Gray marble veins from Perlin turbulence in world space, stripes along z
+/// about every `1 / scale` units
+pub struct Marble {
+    pub noise: Perlin,
+    pub scale: Float,
+}
+
+impl Marble {
+    pub fn new(scale: Float) -> Self {
+        Self {
+            noise: Perlin::new(),
+            scale,
+        }
+    }
+}
+
+impl Texture<Color> for Marble {
+    fn value(&self, hit_info: &HitInfo) -> Color {
+        let p = hit_info.pos.to_vec();
+        let turbulence = self.noise.turbulence(&p, 7);
+        let gray = 0.5 * (1. + (self.scale * p.z() + 10. * turbulence).sin());
+        Color(gray, gray, gray)
+    }
+}
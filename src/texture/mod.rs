@@ -0,0 +1,42 @@
+pub mod channel;
+pub mod checker;
+pub mod image_texture;
+pub mod marble;
+pub mod vertex_color;
+
+pub use channel::*;
+pub use checker::*;
+pub use image_texture::*;
+pub use marble::*;
+pub use vertex_color::*;
+
+use crate::{color::Color, float::Float, hit::HitInfo};
+
+/// A value that varies over a surface, looked up at a hit
+pub trait Texture<T> {
+    fn value(&self, hit_info: &HitInfo) -> T;
+}
+
+/// Boxed textures, for materials where every parameter may be textured
+pub type ColorTexture = Box<dyn Texture<Color> + Sync>;
+pub type FloatTexture = Box<dyn Texture<Float> + Sync>;
+
+// a texture shared between several users looks the same to each
+impl<T, X: Texture<T> + ?Sized> Texture<T> for std::sync::Arc<X> {
+    fn value(&self, hit_info: &HitInfo) -> T {
+        (**self).value(hit_info)
+    }
+}
+
+// plain values are textures that are the same everywhere
+impl Texture<Color> for Color {
+    fn value(&self, _hit_info: &HitInfo) -> Color {
+        *self
+    }
+}
+
+impl Texture<Float> for Float {
+    fn value(&self, _hit_info: &HitInfo) -> Float {
+        *self
+    }
+}
@@ -0,0 +1,13 @@
+use super::Texture;
+use crate::{color::Color, hit::HitInfo};
+
+/// The colors painted on the vertices of a mesh, as scanners and some
+/// modelling tools export them. White on shapes without any.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VertexColor;
+
+impl Texture<Color> for VertexColor {
+    fn value(&self, hit_info: &HitInfo) -> Color {
+        hit_info.vertex_color
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{color::Color, float::Float, vec3::Mat3};
+
+/// Curve that compresses linear scene colors into the displayable [0, 1] range
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// No compression, anything above 1 is clipped
+    #[default]
+    Clamp,
+    /// c / (1 + c) per channel
+    Reinhard,
+    /// Stephen Hill's fit of the ACES reference rendering and sRGB output transforms
+    AcesFilmic,
+    /// Troy Sobotka's AgX, using Benjamin Wrensch's polynomial fit of the default look
+    Agx,
+}
+
+/// How the linear radiance of a render becomes 8 bit sRGB pixels:
+/// exposure, then a tone curve, then the sRGB transfer function
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ColorPipeline {
+    /// Exposure adjustment in stops, each stop doubles the brightness
+    pub exposure: Float,
+    pub tone_map: ToneMap,
+}
+
+impl ColorPipeline {
+    /// Linear scene color to 8 bit sRGB
+    pub fn encode(&self, color: Color) -> [u8; 3] {
+        let color = self.tone_map.apply(color * Float::powf(2., self.exposure));
+        [color.r(), color.g(), color.b()].map(|c| (srgb_oetf(c.clamp(0., 1.)) * 255.).round() as u8)
+    }
+}
+
+impl ToneMap {
+    pub const ALL: [ToneMap; 4] = [
+        ToneMap::Clamp,
+        ToneMap::Reinhard,
+        ToneMap::AcesFilmic,
+        ToneMap::Agx,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneMap::Clamp => "clamp",
+            ToneMap::Reinhard => "reinhard",
+            ToneMap::AcesFilmic => "aces",
+            ToneMap::Agx => "agx",
+        }
+    }
+
+    /// Maps a linear color to a linear color in [0, 1]
+    pub fn apply(&self, color: Color) -> Color {
+        let color = Color(color.r().max(0.), color.g().max(0.), color.b().max(0.));
+
+        match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => color / (color + 1.),
+            ToneMap::AcesFilmic => aces_filmic(color),
+            ToneMap::Agx => agx(color),
+        }
+    }
+}
+
+impl std::str::FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ToneMap::ALL
+            .into_iter()
+            .find(|tone_map| tone_map.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = ToneMap::ALL.iter().map(ToneMap::name).collect();
+                format!(
+                    "unknown tone map '{s}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The exact piecewise sRGB encoding of a linear value in [0, 1]
+pub fn srgb_oetf(x: Float) -> Float {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// The inverse of `srgb_oetf`, from an encoded value in [0, 1] to linear
+pub fn srgb_eotf(x: Float) -> Float {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn aces_filmic(color: Color) -> Color {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    const INPUT: Mat3 = Mat3([
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ]);
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    const OUTPUT: Mat3 = Mat3([
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ]);
+
+    let v = INPUT * color;
+    let a = v * (v + 0.0245786) - 0.000090537;
+    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    OUTPUT * (a / b)
+}
+
+fn agx(color: Color) -> Color {
+    const INSET: Mat3 = Mat3([
+        [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+        [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+        [0.0423756549057051, 0.0784336, 0.879142973793104],
+    ]);
+    const OUTSET: Mat3 = Mat3([
+        [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+        [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+        [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+    ]);
+    const MIN_EV: Float = -12.47393;
+    const MAX_EV: Float = 4.026069;
+
+    // log encode into [0, 1]
+    let log = |x: Float| (x.max(1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+    // sigmoid approximating the default AgX contrast curve
+    let contrast = |x: Float| {
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    };
+
+    let v = INSET * color;
+    let v = Color(
+        contrast(log(v.r())),
+        contrast(log(v.g())),
+        contrast(log(v.b())),
+    );
+    // the curve outputs display encoded values, undo the 2.2 gamma to get back to linear
+    let v = OUTSET * v;
+    Color(
+        v.r().max(0.).powf(2.2),
+        v.g().max(0.).powf(2.2),
+        v.b().max(0.).powf(2.2),
+    )
+}
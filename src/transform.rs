@@ -0,0 +1,239 @@
+use crate::{
+    aabb::Aabb,
+    float::{gamma, Float},
+    hit::*,
+    ray::*,
+    vec3::*,
+};
+use std::ops::Range;
+
+/// Wraps an object and moves it over time: a rotation around an axis through
+/// the object's origin, followed by a translation. Both are interpolated
+/// linearly from their values at time0 to their values at time1, 0 and 1
+/// unless set, and hold before and after.
+pub struct Animated<H: Hit> {
+    pub object: H,
+    pub translation_start: Vec3,
+    pub translation_end: Vec3,
+    // unit length
+    pub axis: Vec3,
+    // radians
+    pub angle_start: Float,
+    pub angle_end: Float,
+    pub time0: Float,
+    pub time1: Float,
+}
+
+impl<H: Hit> Animated<H> {
+    /// Doesn't move until given a translation or rotation
+    pub fn new(object: H) -> Self {
+        Self {
+            object,
+            translation_start: Vec3(0., 0., 0.),
+            translation_end: Vec3(0., 0., 0.),
+            axis: Vec3(0., 1., 0.),
+            angle_start: 0.,
+            angle_end: 0.,
+            time0: 0.,
+            time1: 1.,
+        }
+    }
+
+    pub fn with_translation(self, start: Vec3, end: Vec3) -> Self {
+        Self {
+            translation_start: start,
+            translation_end: end,
+            ..self
+        }
+    }
+
+    pub fn with_rotation_degrees(self, axis: Vec3, start: Float, end: Float) -> Self {
+        Self {
+            axis: axis.unit_vec(),
+            angle_start: start.to_radians(),
+            angle_end: end.to_radians(),
+            ..self
+        }
+    }
+
+    /// Moves between time0 and time1 instead of 0 and 1
+    pub fn with_motion_times(self, time0: Float, time1: Float) -> Self {
+        assert!(time0 < time1, "motion has to end after it starts");
+        Self {
+            time0,
+            time1,
+            ..self
+        }
+    }
+
+    fn translation_at(&self, time: Float) -> Vec3 {
+        let progress = motion_progress(time, self.time0, self.time1);
+        self.translation_start + (self.translation_end - self.translation_start) * progress
+    }
+
+    fn angle_at(&self, time: Float) -> Float {
+        let progress = motion_progress(time, self.time0, self.time1);
+        self.angle_start + (self.angle_end - self.angle_start) * progress
+    }
+}
+
+impl<H: Hit> Hit for Animated<H> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        let translation = self.translation_at(ray.time);
+        let angle = self.angle_at(ray.time);
+
+        // move the ray into object space, t stays the same since the
+        // direction isn't rescaled
+        let object_ray = Ray {
+            origin: (ray.origin - translation).rotate_around(&self.axis, -angle),
+            dir: ray.dir.rotate_around(&self.axis, -angle),
+            time: ray.time,
+        };
+
+        let hit_info = self.object.hit(&object_ray, ray_t_interval)?;
+
+        // and the hit back into world space.
+        // rotating keeps the length of the error but can turn it towards any axis
+        let pos = hit_info.pos.rotate_around(&self.axis, angle) + translation;
+        let error = hit_info.pos_error.length();
+        let pos_error = Vec3(error, error, error) * (1. + gamma(6))
+            + (pos.to_vec().abs() + translation.abs()) * gamma(6);
+        Some(HitInfo {
+            pos,
+            pos_error,
+            normal: hit_info.normal.rotate_around(&self.axis, angle),
+            geometric_normal: hit_info.geometric_normal.rotate_around(&self.axis, angle),
+            dpdu: hit_info.dpdu.rotate_around(&self.axis, angle),
+            dpdv: hit_info.dpdv.rotate_around(&self.axis, angle),
+            ..hit_info
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let object_box = self.object.bounding_box();
+
+        if self.angle_start == self.angle_end {
+            let rotated = object_box
+                .corners()
+                .iter()
+                .map(|corner| corner.rotate_around(&self.axis, self.angle_start))
+                .fold(Aabb::EMPTY, |acc, corner| {
+                    acc.union(&Aabb::new(corner, corner))
+                });
+            return rotated
+                .translate(&self.translation_start)
+                .union(&rotated.translate(&self.translation_end));
+        }
+
+        // while rotating the object stays within the sphere around its origin
+        // that reaches its furthest corner
+        let radius = object_box
+            .corners()
+            .iter()
+            .map(|corner| corner.to_vec().length())
+            .fold(0., Float::max);
+        let r = Vec3(radius, radius, radius);
+        let (start, end) = (
+            Pos::ORIGIN + self.translation_start,
+            Pos::ORIGIN + self.translation_end,
+        );
+        Aabb::new(start - r, start + r).union(&Aabb::new(end - r, end + r))
+    }
+}
+
+/// Wraps an object with an affine transform from object space to world space,
+/// for placing, rotating and (non-uniformly) scaling it.
+/// Wrap a reference to place the same object several times.
+pub struct Transformed<H: Hit> {
+    pub object: H,
+    object_to_world: Mat4,
+    world_to_object: Mat4,
+}
+
+impl<H: Hit> Transformed<H> {
+    /// Panics if the transform can't be inverted
+    pub fn new(object: H, object_to_world: Mat4) -> Self {
+        let world_to_object = object_to_world
+            .inverse()
+            .expect("object transforms must be invertible");
+        Self {
+            object,
+            object_to_world,
+            world_to_object,
+        }
+    }
+
+    pub fn transform(&self) -> &Mat4 {
+        &self.object_to_world
+    }
+}
+
+impl<H: Hit> Hit for Transformed<H> {
+    fn hit(&self, ray: &Ray, ray_t_interval: Range<Float>) -> Option<HitInfo<'_>> {
+        // the direction isn't normalized, so t means the same in both spaces
+        let object_ray = Ray {
+            origin: self.world_to_object.transform_point(&ray.origin),
+            dir: self.world_to_object.transform_vector(&ray.dir),
+            time: ray.time,
+        };
+
+        let hit_info = self.object.hit(&object_ray, ray_t_interval)?;
+
+        // front_face stays valid, the inverse transpose keeps the sign of normal . dir
+        Some(HitInfo {
+            pos: self.object_to_world.transform_point(&hit_info.pos),
+            pos_error: self
+                .object_to_world
+                .transform_point_error(&hit_info.pos, &hit_info.pos_error),
+            normal: self.world_to_object.transform_normal(&hit_info.normal),
+            geometric_normal: self
+                .world_to_object
+                .transform_normal(&hit_info.geometric_normal),
+            dpdu: self.object_to_world.transform_vector(&hit_info.dpdu),
+            dpdv: self.object_to_world.transform_vector(&hit_info.dpdv),
+            ..hit_info
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object
+            .bounding_box()
+            .corners()
+            .iter()
+            .map(|corner| self.object_to_world.transform_point(corner))
+            .fold(Aabb::EMPTY, |acc, corner| {
+                acc.union(&Aabb::new(corner, corner))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, materials::Lambertian, shapes::Sphere};
+
+    #[test]
+    fn animated_motion_times() {
+        let gray = Lambertian {
+            albedo: Color(0.5, 0.5, 0.5),
+        };
+        let moving = Animated::new(Sphere::new(Pos::ORIGIN, 0.5, &gray))
+            .with_translation(Vec3(-4., 0., 0.), Vec3(4., 0., 0.))
+            .with_motion_times(3., 7.);
+        let hits_origin = |time| {
+            let ray = Ray {
+                origin: Pos(0., 10., 0.),
+                dir: Vec3(0., -1., 0.),
+                time,
+            };
+            moving.hit(&ray, 0.0..Float::INFINITY).is_some()
+        };
+        assert!(hits_origin(5.));
+        assert!(!hits_origin(0.5));
+        assert!(!hits_origin(3.));
+        assert!(!hits_origin(100.));
+
+        let bounds = moving.bounding_box();
+        assert_eq!((bounds.min.x(), bounds.max.x()), (-4.5, 4.5));
+    }
+}
@@ -1,67 +1,16 @@
 // traits have to be implemented for (Vec, Vec), (&Vec, Vec), (Vec, &Vec), and
 // (&Vec, &Vec), and i have no idea what the best way to do that is
 
+// every op is componentwise, and the types say which combinations make sense:
+// impl_vec3_binop!(Sub, sub, Pos, Pos => Vec3) gives point - point = vector
 macro_rules! impl_vec3_binop {
-    ($trait_name:ident, $method_name:ident, Vec3) => {
-        impl std::ops::$trait_name<Vec3> for Vec3 {
-            type Output = Vec3;
+    ($trait_name:ident, $method_name:ident, $lhs:ident, Float => $out:ident) => {
+        impl std::ops::$trait_name<$crate::float::Float> for $lhs {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: Vec3) -> Self::Output {
-                Self(
-                    self.0.$method_name(rhs.0),
-                    self.1.$method_name(rhs.1),
-                    self.2.$method_name(rhs.2),
-                )
-            }
-        }
-
-        impl std::ops::$trait_name<&Vec3> for Vec3 {
-            type Output = Vec3;
-
-            // #[inline(always)]
-            fn $method_name(self, rhs: &Vec3) -> Self::Output {
-                Self(
-                    self.0.$method_name(rhs.0),
-                    self.1.$method_name(rhs.1),
-                    self.2.$method_name(rhs.2),
-                )
-            }
-        }
-
-        impl std::ops::$trait_name<Vec3> for &Vec3 {
-            type Output = Vec3;
-
-            // #[inline(always)]
-            fn $method_name(self, rhs: Vec3) -> Self::Output {
-                Vec3(
-                    self.0.$method_name(rhs.0),
-                    self.1.$method_name(rhs.1),
-                    self.2.$method_name(rhs.2),
-                )
-            }
-        }
-
-        impl std::ops::$trait_name<&Vec3> for &Vec3 {
-            type Output = Vec3;
-
-            // #[inline(always)]
-            fn $method_name(self, rhs: &Vec3) -> Self::Output {
-                Vec3(
-                    self.0.$method_name(rhs.0),
-                    self.1.$method_name(rhs.1),
-                    self.2.$method_name(rhs.2),
-                )
-            }
-        }
-    };
-    ($trait_name:ident, $method_name:ident, f64) => {
-        impl std::ops::$trait_name<f64> for Vec3 {
-            type Output = Vec3;
-
-            // #[inline(always)]
-            fn $method_name(self, rhs: f64) -> Self::Output {
-                Self(
+            fn $method_name(self, rhs: $crate::float::Float) -> Self::Output {
+                $out(
                     self.0.$method_name(rhs),
                     self.1.$method_name(rhs),
                     self.2.$method_name(rhs),
@@ -69,12 +18,12 @@ macro_rules! impl_vec3_binop {
             }
         }
 
-        impl std::ops::$trait_name<&f64> for Vec3 {
-            type Output = Vec3;
+        impl std::ops::$trait_name<&$crate::float::Float> for $lhs {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: &f64) -> Self::Output {
-                Self(
+            fn $method_name(self, rhs: &$crate::float::Float) -> Self::Output {
+                $out(
                     self.0.$method_name(rhs),
                     self.1.$method_name(rhs),
                     self.2.$method_name(rhs),
@@ -82,12 +31,12 @@ macro_rules! impl_vec3_binop {
             }
         }
 
-        impl std::ops::$trait_name<f64> for &Vec3 {
-            type Output = Vec3;
+        impl std::ops::$trait_name<$crate::float::Float> for &$lhs {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: f64) -> Self::Output {
-                Vec3(
+            fn $method_name(self, rhs: $crate::float::Float) -> Self::Output {
+                $out(
                     self.0.$method_name(rhs),
                     self.1.$method_name(rhs),
                     self.2.$method_name(rhs),
@@ -95,25 +44,26 @@ macro_rules! impl_vec3_binop {
             }
         }
 
-        impl std::ops::$trait_name<&f64> for &Vec3 {
-            type Output = Vec3;
+        impl std::ops::$trait_name<&$crate::float::Float> for &$lhs {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: &f64) -> Self::Output {
-                Vec3(
+            fn $method_name(self, rhs: &$crate::float::Float) -> Self::Output {
+                $out(
                     self.0.$method_name(rhs),
                     self.1.$method_name(rhs),
                     self.2.$method_name(rhs),
                 )
             }
         }
-
-        impl std::ops::$trait_name<Vec3> for f64 {
-            type Output = Vec3;
+    };
+    ($trait_name:ident, $method_name:ident, Float, $rhs:ident => $out:ident) => {
+        impl std::ops::$trait_name<$rhs> for $crate::float::Float {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: Vec3) -> Self::Output {
-                Vec3(
+            fn $method_name(self, rhs: $rhs) -> Self::Output {
+                $out(
                     self.$method_name(rhs.0),
                     self.$method_name(rhs.1),
                     self.$method_name(rhs.2),
@@ -121,12 +71,12 @@ macro_rules! impl_vec3_binop {
             }
         }
 
-        impl std::ops::$trait_name<&Vec3> for f64 {
-            type Output = Vec3;
+        impl std::ops::$trait_name<&$rhs> for $crate::float::Float {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: &Vec3) -> Self::Output {
-                Vec3(
+            fn $method_name(self, rhs: &$rhs) -> Self::Output {
+                $out(
                     self.$method_name(rhs.0),
                     self.$method_name(rhs.1),
                     self.$method_name(rhs.2),
@@ -134,12 +84,12 @@ macro_rules! impl_vec3_binop {
             }
         }
 
-        impl std::ops::$trait_name<Vec3> for &f64 {
-            type Output = Vec3;
+        impl std::ops::$trait_name<$rhs> for &$crate::float::Float {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: Vec3) -> Self::Output {
-                Vec3(
+            fn $method_name(self, rhs: $rhs) -> Self::Output {
+                $out(
                     self.$method_name(rhs.0),
                     self.$method_name(rhs.1),
                     self.$method_name(rhs.2),
@@ -147,12 +97,12 @@ macro_rules! impl_vec3_binop {
             }
         }
 
-        impl std::ops::$trait_name<&Vec3> for &f64 {
-            type Output = Vec3;
+        impl std::ops::$trait_name<&$rhs> for &$crate::float::Float {
+            type Output = $out;
 
             // #[inline(always)]
-            fn $method_name(self, rhs: &Vec3) -> Self::Output {
-                Vec3(
+            fn $method_name(self, rhs: &$rhs) -> Self::Output {
+                $out(
                     self.$method_name(rhs.0),
                     self.$method_name(rhs.1),
                     self.$method_name(rhs.2),
@@ -160,60 +110,97 @@ macro_rules! impl_vec3_binop {
             }
         }
     };
-    ($trait_name:ident, $method_name:ident, Vec3, assign) => {
-        impl std::ops::$trait_name<&Vec3> for Vec3 {
+    ($trait_name:ident, $method_name:ident, $lhs:ident, $rhs:ident => $out:ident) => {
+        impl std::ops::$trait_name<$rhs> for $lhs {
+            type Output = $out;
+
             // #[inline(always)]
-            fn $method_name(&mut self, rhs: &Vec3) {
-                self.0.$method_name(rhs.0);
-                self.1.$method_name(rhs.1);
-                self.2.$method_name(rhs.2);
+            fn $method_name(self, rhs: $rhs) -> Self::Output {
+                $out(
+                    self.0.$method_name(rhs.0),
+                    self.1.$method_name(rhs.1),
+                    self.2.$method_name(rhs.2),
+                )
             }
         }
 
-        impl std::ops::$trait_name<Vec3> for Vec3 {
+        impl std::ops::$trait_name<&$rhs> for $lhs {
+            type Output = $out;
+
             // #[inline(always)]
-            fn $method_name(&mut self, rhs: Vec3) {
-                self.0.$method_name(rhs.0);
-                self.1.$method_name(rhs.1);
-                self.2.$method_name(rhs.2);
+            fn $method_name(self, rhs: &$rhs) -> Self::Output {
+                $out(
+                    self.0.$method_name(rhs.0),
+                    self.1.$method_name(rhs.1),
+                    self.2.$method_name(rhs.2),
+                )
+            }
+        }
+
+        impl std::ops::$trait_name<$rhs> for &$lhs {
+            type Output = $out;
+
+            // #[inline(always)]
+            fn $method_name(self, rhs: $rhs) -> Self::Output {
+                $out(
+                    self.0.$method_name(rhs.0),
+                    self.1.$method_name(rhs.1),
+                    self.2.$method_name(rhs.2),
+                )
+            }
+        }
+
+        impl std::ops::$trait_name<&$rhs> for &$lhs {
+            type Output = $out;
+
+            // #[inline(always)]
+            fn $method_name(self, rhs: &$rhs) -> Self::Output {
+                $out(
+                    self.0.$method_name(rhs.0),
+                    self.1.$method_name(rhs.1),
+                    self.2.$method_name(rhs.2),
+                )
             }
         }
     };
-    ($trait_name:ident, $method_name:ident, f64, assign) => {
-        impl std::ops::$trait_name<f64> for Vec3 {
+    ($trait_name:ident, $method_name:ident, $lhs:ident, Float, assign) => {
+        impl std::ops::$trait_name<$crate::float::Float> for $lhs {
             // #[inline(always)]
-            fn $method_name(&mut self, rhs: f64) {
+            fn $method_name(&mut self, rhs: $crate::float::Float) {
                 self.0.$method_name(rhs);
                 self.1.$method_name(rhs);
                 self.2.$method_name(rhs);
             }
         }
 
-        impl std::ops::$trait_name<&f64> for Vec3 {
+        impl std::ops::$trait_name<&$crate::float::Float> for $lhs {
             // #[inline(always)]
-            fn $method_name(&mut self, rhs: &f64) {
+            fn $method_name(&mut self, rhs: &$crate::float::Float) {
                 self.0.$method_name(rhs);
                 self.1.$method_name(rhs);
                 self.2.$method_name(rhs);
             }
         }
-
-        impl std::ops::$trait_name<Vec3> for f64 {
+    };
+    ($trait_name:ident, $method_name:ident, $lhs:ident, $rhs:ident, assign) => {
+        impl std::ops::$trait_name<&$rhs> for $lhs {
             // #[inline(always)]
-            fn $method_name(&mut self, rhs: Vec3) {
-                self.$method_name(rhs.0);
-                self.$method_name(rhs.1);
-                self.$method_name(rhs.2);
+            fn $method_name(&mut self, rhs: &$rhs) {
+                self.0.$method_name(rhs.0);
+                self.1.$method_name(rhs.1);
+                self.2.$method_name(rhs.2);
             }
         }
 
-        impl std::ops::$trait_name<&Vec3> for f64 {
+        impl std::ops::$trait_name<$rhs> for $lhs {
             // #[inline(always)]
-            fn $method_name(&mut self, rhs: &Vec3) {
-                self.$method_name(rhs.0);
-                self.$method_name(rhs.1);
-                self.$method_name(rhs.2);
+            fn $method_name(&mut self, rhs: $rhs) {
+                self.0.$method_name(rhs.0);
+                self.1.$method_name(rhs.1);
+                self.2.$method_name(rhs.2);
             }
         }
     };
 }
+
+pub(crate) use impl_vec3_binop;
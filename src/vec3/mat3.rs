@@ -0,0 +1,97 @@
+use super::*;
+use crate::float::Float;
+
+/// 3x3 matrix for linear transforms of vectors, row major
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat3(pub [[Float; 3]; 3]);
+
+impl Mat3 {
+    pub const IDENTITY: Mat3 = Mat3([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]);
+
+    /// Matrix with the given vectors as its columns, mapping the x, y and z
+    /// axes onto them
+    pub fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self {
+        Mat3([[x.0, y.0, z.0], [x.1, y.1, z.1], [x.2, y.2, z.2]])
+    }
+
+    pub fn col(&self, i: usize) -> Vec3 {
+        let m = &self.0;
+        Vec3(m[0][i], m[1][i], m[2][i])
+    }
+
+    /// Counterclockwise rotation around a unit-length axis
+    pub fn rotation(axis: Vec3, angle: Float) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let Vec3(x, y, z) = axis;
+        let t = 1. - cos;
+        Mat3([
+            [t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y],
+            [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x],
+            [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos],
+        ])
+    }
+
+    /// Orthonormal camera basis looking from `from` towards `at`, with columns
+    /// u (right), v (up) and w (backwards, pointing from `at` to `from`)
+    pub fn look_at(from: Pos, at: Pos, up: Vec3) -> Self {
+        let w = (from - at).unit_vec();
+        let u = up.cross(&w).unit_vec();
+        let v = w.cross(&u);
+        Mat3::from_cols(u, v, w)
+    }
+
+    pub fn transpose(&self) -> Mat3 {
+        let m = &self.0;
+        Mat3(std::array::from_fn(|i| std::array::from_fn(|j| m[j][i])))
+    }
+
+    pub fn determinant(&self) -> Float {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// None if the matrix is singular
+    pub fn inverse(&self) -> Option<Mat3> {
+        // like Mat4, a tiny determinant can be a valid small scale
+        let det = self.determinant();
+        if !det.is_finite() || !(1. / det).is_finite() {
+            return None;
+        }
+
+        // the inverse is the transposed cofactor matrix over the determinant,
+        // and the cofactors of the rows come from crossing the other two rows
+        let [r0, r1, r2] = self.0.map(|row| Vec3(row[0], row[1], row[2]));
+        Some(Mat3::from_cols(r1.cross(&r2), r2.cross(&r0), r0.cross(&r1)) * (1. / det))
+    }
+}
+
+impl std::ops::Mul for Mat3 {
+    type Output = Mat3;
+
+    /// Applies rhs first, then self
+    fn mul(self, rhs: Mat3) -> Self::Output {
+        let (a, b) = (&self.0, &rhs.0);
+        Mat3(std::array::from_fn(|i| {
+            std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum())
+        }))
+    }
+}
+
+impl std::ops::Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, v: Vec3) -> Self::Output {
+        let row = |r: &[Float; 3]| r[0] * v.0 + r[1] * v.1 + r[2] * v.2;
+        Vec3(row(&self.0[0]), row(&self.0[1]), row(&self.0[2]))
+    }
+}
+
+impl std::ops::Mul<Float> for Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, rhs: Float) -> Self::Output {
+        Mat3(self.0.map(|row| row.map(|x| x * rhs)))
+    }
+}
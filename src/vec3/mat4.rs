@@ -0,0 +1,185 @@
+use super::*;
+use crate::float::{gamma, Float};
+
+/// 4x4 matrix for affine transforms, row major.
+/// Points are transformed as column vectors with w = 1, vectors with w = 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4(pub [[Float; 4]; 4]);
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4([
+        [1., 0., 0., 0.],
+        [0., 1., 0., 0.],
+        [0., 0., 1., 0.],
+        [0., 0., 0., 1.],
+    ]);
+
+    pub fn translation(offset: Vec3) -> Self {
+        Mat4([
+            [1., 0., 0., offset.0],
+            [0., 1., 0., offset.1],
+            [0., 0., 1., offset.2],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Scales each axis separately, non-uniform scales are fine
+    pub fn scale(factors: Vec3) -> Self {
+        Mat4([
+            [factors.0, 0., 0., 0.],
+            [0., factors.1, 0., 0.],
+            [0., 0., factors.2, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Linear transform followed by a translation
+    pub fn from_mat3(linear: &Mat3, translation: Vec3) -> Self {
+        let m = &linear.0;
+        Mat4([
+            [m[0][0], m[0][1], m[0][2], translation.0],
+            [m[1][0], m[1][1], m[1][2], translation.1],
+            [m[2][0], m[2][1], m[2][2], translation.2],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Counterclockwise rotation around a unit-length axis through the origin
+    pub fn rotation(axis: Vec3, angle: Float) -> Self {
+        Mat4::from_mat3(&Mat3::rotation(axis, angle), Vec3(0., 0., 0.))
+    }
+
+    pub fn rotation_degrees(axis: Vec3, angle: Float) -> Self {
+        Mat4::rotation(axis, angle.to_radians())
+    }
+
+    pub fn from_quat(rotation: &Quat) -> Self {
+        Mat4::from_mat3(&rotation.to_mat3(), Vec3(0., 0., 0.))
+    }
+
+    /// Places an object at `from`, with its -z axis pointing at `at` and its
+    /// y axis as close to `up` as possible
+    pub fn look_at(from: Pos, at: Pos, up: Vec3) -> Self {
+        Mat4::from_mat3(&Mat3::look_at(from, at, up), from.to_vec())
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let m = &self.0;
+        Mat4(std::array::from_fn(|i| std::array::from_fn(|j| m[j][i])))
+    }
+
+    /// None if the matrix is singular
+    pub fn inverse(&self) -> Option<Mat4> {
+        // cofactor expansion using the 2x2 sub-determinants of the
+        // top two and bottom two rows
+        let m = &self.0;
+        let s0 = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+        let s1 = m[0][0] * m[1][2] - m[1][0] * m[0][2];
+        let s2 = m[0][0] * m[1][3] - m[1][0] * m[0][3];
+        let s3 = m[0][1] * m[1][2] - m[1][1] * m[0][2];
+        let s4 = m[0][1] * m[1][3] - m[1][1] * m[0][3];
+        let s5 = m[0][2] * m[1][3] - m[1][2] * m[0][3];
+
+        let c5 = m[2][2] * m[3][3] - m[3][2] * m[2][3];
+        let c4 = m[2][1] * m[3][3] - m[3][1] * m[2][3];
+        let c3 = m[2][1] * m[3][2] - m[3][1] * m[2][2];
+        let c2 = m[2][0] * m[3][3] - m[3][0] * m[2][3];
+        let c1 = m[2][0] * m[3][2] - m[3][0] * m[2][2];
+        let c0 = m[2][0] * m[3][1] - m[3][0] * m[2][1];
+
+        let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        // no threshold on the size of det, a tiny one can be a valid small
+        // scale, only values that leave no finite inverse are rejected
+        let inv_det = 1. / det;
+        if !det.is_finite() || !inv_det.is_finite() {
+            return None;
+        }
+
+        Some(Mat4([
+            [
+                (m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3) * inv_det,
+                (-m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3) * inv_det,
+                (m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3) * inv_det,
+                (-m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3) * inv_det,
+            ],
+            [
+                (-m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1) * inv_det,
+                (m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1) * inv_det,
+                (-m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1) * inv_det,
+                (m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1) * inv_det,
+            ],
+            [
+                (m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0) * inv_det,
+                (-m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0) * inv_det,
+                (m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0) * inv_det,
+                (-m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0) * inv_det,
+            ],
+            [
+                (-m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0) * inv_det,
+                (m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0) * inv_det,
+                (-m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0) * inv_det,
+                (m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0) * inv_det,
+            ],
+        ]))
+    }
+
+    pub fn transform_point(&self, p: &Pos) -> Pos {
+        let m = &self.0;
+        Pos(
+            m[0][0] * p.0 + m[0][1] * p.1 + m[0][2] * p.2 + m[0][3],
+            m[1][0] * p.0 + m[1][1] * p.1 + m[1][2] * p.2 + m[1][3],
+            m[2][0] * p.0 + m[2][1] * p.1 + m[2][2] * p.2 + m[2][3],
+        )
+    }
+
+    /// Bound on the error of transform_point(p), when p itself was already off
+    /// by up to p_error in each axis (pbrt 6.8.4)
+    pub fn transform_point_error(&self, p: &Pos, p_error: &Vec3) -> Vec3 {
+        let m = &self.0;
+        let row = |i: usize| {
+            let propagated = (m[i][0] * p_error.0).abs()
+                + (m[i][1] * p_error.1).abs()
+                + (m[i][2] * p_error.2).abs();
+            let rounding = (m[i][0] * p.0).abs()
+                + (m[i][1] * p.1).abs()
+                + (m[i][2] * p.2).abs()
+                + m[i][3].abs();
+            (1. + gamma(3)) * propagated + gamma(3) * rounding
+        };
+        Vec3(row(0), row(1), row(2))
+    }
+
+    /// Ignores the translation
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.0;
+        Vec3(
+            m[0][0] * v.0 + m[0][1] * v.1 + m[0][2] * v.2,
+            m[1][0] * v.0 + m[1][1] * v.1 + m[1][2] * v.2,
+            m[2][0] * v.0 + m[2][1] * v.1 + m[2][2] * v.2,
+        )
+    }
+
+    /// Normals go by the inverse transpose to stay perpendicular to the
+    /// surface, so this must be called on the *inverse* of the matrix the
+    /// points went through. Multiplies by the transpose and renormalizes.
+    pub fn transform_normal(&self, n: &Normal) -> Normal {
+        let (m, n) = (&self.0, n.to_vec());
+        Normal::new(Vec3(
+            m[0][0] * n.0 + m[1][0] * n.1 + m[2][0] * n.2,
+            m[0][1] * n.0 + m[1][1] * n.1 + m[2][1] * n.2,
+            m[0][2] * n.0 + m[1][2] * n.1 + m[2][2] * n.2,
+        ))
+    }
+}
+
+impl std::ops::Mul for Mat4 {
+    type Output = Mat4;
+
+    /// Applies rhs first, then self
+    fn mul(self, rhs: Mat4) -> Self::Output {
+        let (a, b) = (&self.0, &rhs.0);
+        Mat4(std::array::from_fn(|i| {
+            std::array::from_fn(|j| (0..4).map(|k| a[i][k] * b[k][j]).sum())
+        }))
+    }
+}
@@ -1,26 +1,41 @@
 #[macro_use]
 mod macros;
-
-pub use Vec3 as Pos;
-
-#[derive(Clone, Copy, Debug)]
-pub struct Vec3(pub f64, pub f64, pub f64);
+mod mat3;
+mod mat4;
+mod normal;
+mod pos;
+mod quat;
+mod wide;
+
+pub(crate) use macros::impl_vec3_binop;
+pub use mat3::*;
+pub use mat4::*;
+pub use normal::*;
+pub use pos::*;
+pub use quat::*;
+pub use wide::*;
+
+use crate::float::{consts, random, Float};
+
+/// A direction or offset in space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vec3(pub Float, pub Float, pub Float);
 
 impl Vec3 {
     // vector math
 
     // #[inline(always)]
-    pub fn dot(&self, rhs: &Vec3) -> f64 {
+    pub fn dot(&self, rhs: &Vec3) -> Float {
         self.0 * rhs.0 + self.1 * rhs.1 + self.2 * rhs.2
     }
 
     // #[inline(always)]
-    pub fn length_squared(&self) -> f64 {
+    pub fn length_squared(&self) -> Float {
         self.dot(self)
     }
 
     // #[inline(always)]
-    pub fn length(&self) -> f64 {
+    pub fn length(&self) -> Float {
         self.length_squared().sqrt()
     }
 
@@ -38,13 +53,17 @@ impl Vec3 {
         self / self.length()
     }
 
-    pub fn rand(min: f64, max: f64) -> Self {
-        Self(randf64(min, max), randf64(min, max), randf64(min, max))
+    pub fn rand(min: Float, max: Float) -> Self {
+        Self(
+            rand_float(min, max),
+            rand_float(min, max),
+            rand_float(min, max),
+        )
     }
 
-    pub fn random_on_hemisphere(norm: &Vec3) -> Vec3 {
+    pub fn random_on_hemisphere(norm: &Normal) -> Vec3 {
         let dir = Vec3::rand_unit_vec();
-        if dir.dot(norm) > 0. {
+        if norm.dot(&dir) > 0. {
             dir
         } else {
             -dir
@@ -64,13 +83,62 @@ impl Vec3 {
 
     pub fn rand_in_unit_disk() -> Vec3 {
         loop {
-            let vec = Vec3(randf64(-1., 1.), randf64(-1., 1.), 0.);
+            let vec = Vec3(rand_float(-1., 1.), rand_float(-1., 1.), 0.);
             if vec.length_squared() < 1. {
                 return vec;
             }
         }
     }
 
+    /// Maps a uniform point in [0, 1)^2 to a uniform point on the unit sphere
+    pub fn unit_vec_from_sample((u, v): (Float, Float)) -> Vec3 {
+        let z = 1. - 2. * u;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * consts::PI * v;
+        Vec3(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    /// Maps a uniform point in [0, 1)^2 to a uniform point in the unit disk (z = 0).
+    /// Uses the concentric mapping, which keeps stratified points stratified.
+    pub fn in_unit_disk_from_sample((u, v): (Float, Float)) -> Vec3 {
+        use consts::FRAC_PI_4;
+
+        let (a, b) = (2. * u - 1., 2. * v - 1.);
+        if a == 0. && b == 0. {
+            return Vec3(0., 0., 0.);
+        }
+
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, FRAC_PI_4 * (b / a))
+        } else {
+            (b, 2. * FRAC_PI_4 - FRAC_PI_4 * (a / b))
+        };
+        Vec3(r * theta.cos(), r * theta.sin(), 0.)
+    }
+
+    /// Rotates the vector around a unit-length axis, counterclockwise looking
+    /// down the axis (Rodrigues' rotation formula)
+    pub fn rotate_around(&self, axis: &Vec3, angle: Float) -> Vec3 {
+        let (sin, cos) = angle.sin_cos();
+        self * cos + axis.cross(self) * sin + axis * axis.dot(self) * (1. - cos)
+    }
+
+    /// Two unit vectors that form an orthonormal basis together with this
+    /// unit-length vector (Duff et al., "Building an Orthonormal Basis, Revisited")
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let sign = Float::copysign(1., self.2);
+        let a = -1. / (sign + self.2);
+        let b = self.0 * self.1 * a;
+        (
+            Vec3(1. + sign * self.0 * self.0 * a, sign * b, -sign * self.0),
+            Vec3(b, sign + self.1 * self.1 * a, -self.1),
+        )
+    }
+
+    pub fn abs(&self) -> Vec3 {
+        Vec3(self.0.abs(), self.1.abs(), self.2.abs())
+    }
+
     /// Checks whether the vector is near zero
     pub fn near_zero(&self) -> bool {
         let margin = 1e-8;
@@ -78,21 +146,21 @@ impl Vec3 {
     }
 
     /// Reflect a vector around a unit-length direction
-    pub fn reflect(&self, norm: &Vec3) -> Vec3 {
-        self - 2. * self.dot(norm) * norm
+    pub fn reflect(&self, norm: &Normal) -> Vec3 {
+        self - 2. * norm.dot(self) * norm
     }
 
-    pub fn refract(&self, norm: &Vec3, rel_refract_index: f64) -> Vec3 {
-        let cos_theta = (-self).dot(norm).min(1.);
+    pub fn refract(&self, norm: &Normal, rel_refract_index: Float) -> Vec3 {
+        let cos_theta = norm.dot(&-self).min(1.);
         let r_prime_perp = rel_refract_index * (self + cos_theta * norm);
         let r_prime_parallel = -((1.0 - r_prime_perp.length_squared()).abs().sqrt()) * norm;
         r_prime_perp + r_prime_parallel
     }
 }
 
-fn randf64(min: f64, max: f64) -> f64 {
+fn rand_float(min: Float, max: Float) -> Float {
     let scale = max - min;
-    let bruh = fastrand::f64();
+    let bruh = random();
     // FMA??? join the cargo cult
     bruh * scale + min
 }
@@ -119,39 +187,32 @@ impl std::iter::Sum for Vec3 {
     }
 }
 
-impl_vec3_binop!(Add, add, Vec3);
-impl_vec3_binop!(Add, add, f64);
-impl_vec3_binop!(AddAssign, add_assign, Vec3, assign);
-impl_vec3_binop!(AddAssign, add_assign, f64, assign);
+impl_vec3_binop!(Add, add, Vec3, Vec3 => Vec3);
+impl_vec3_binop!(AddAssign, add_assign, Vec3, Vec3, assign);
 
-impl_vec3_binop!(Sub, sub, Vec3);
-impl_vec3_binop!(Sub, sub, f64);
-impl_vec3_binop!(SubAssign, sub_assign, Vec3, assign);
-impl_vec3_binop!(SubAssign, sub_assign, f64, assign);
+impl_vec3_binop!(Sub, sub, Vec3, Vec3 => Vec3);
+impl_vec3_binop!(SubAssign, sub_assign, Vec3, Vec3, assign);
 
-impl_vec3_binop!(Mul, mul, Vec3);
-impl_vec3_binop!(Mul, mul, f64);
-impl_vec3_binop!(MulAssign, mul_assign, f64, assign);
-impl_vec3_binop!(MulAssign, mul_assign, Vec3, assign);
+impl_vec3_binop!(Mul, mul, Vec3, Float => Vec3);
+impl_vec3_binop!(Mul, mul, Float, Vec3 => Vec3);
+impl_vec3_binop!(MulAssign, mul_assign, Vec3, Float, assign);
 
-impl_vec3_binop!(Div, div, Vec3);
-impl_vec3_binop!(Div, div, f64);
-impl_vec3_binop!(DivAssign, div_assign, f64, assign);
-impl_vec3_binop!(DivAssign, div_assign, Vec3, assign);
+impl_vec3_binop!(Div, div, Vec3, Float => Vec3);
+impl_vec3_binop!(DivAssign, div_assign, Vec3, Float, assign);
 
-impl Pos {
+impl Vec3 {
     #[inline(always)]
-    pub fn x(&self) -> f64 {
+    pub fn x(&self) -> Float {
         self.0
     }
 
     #[inline(always)]
-    pub fn y(&self) -> f64 {
+    pub fn y(&self) -> Float {
         self.1
     }
 
     #[inline(always)]
-    pub fn z(&self) -> f64 {
+    pub fn z(&self) -> Float {
         self.2
     }
 }
@@ -0,0 +1,69 @@
+use super::*;
+use crate::float::Float;
+
+/// A unit-length surface normal.
+/// Unlike vectors, normals go through transforms by the inverse transpose
+/// (see `Mat4::transform_normal`), so they are kept as their own type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Normal(Vec3);
+
+impl Normal {
+    /// Normalizes the vector
+    pub fn new(v: Vec3) -> Self {
+        Normal(v.unit_vec())
+    }
+
+    pub fn to_vec(&self) -> Vec3 {
+        self.0
+    }
+
+    pub fn dot(&self, rhs: &Vec3) -> Float {
+        self.0.dot(rhs)
+    }
+
+    /// Rotates the normal around a unit-length axis
+    pub fn rotate_around(&self, axis: &Vec3, angle: Float) -> Normal {
+        Normal(self.0.rotate_around(axis, angle))
+    }
+}
+
+impl std::ops::Neg for Normal {
+    type Output = Normal;
+
+    fn neg(self) -> Self::Output {
+        Normal(-self.0)
+    }
+}
+
+impl std::ops::Neg for &Normal {
+    type Output = Normal;
+
+    fn neg(self) -> Self::Output {
+        Normal(-self.0)
+    }
+}
+
+// scaling a normal gives a plain vector
+impl std::ops::Mul<Float> for Normal {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Float) -> Self::Output {
+        self.0 * rhs
+    }
+}
+
+impl std::ops::Mul<Normal> for Float {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Normal) -> Self::Output {
+        self * rhs.0
+    }
+}
+
+impl std::ops::Mul<&Normal> for Float {
+    type Output = Vec3;
+
+    fn mul(self, rhs: &Normal) -> Self::Output {
+        self * rhs.0
+    }
+}
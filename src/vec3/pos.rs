@@ -0,0 +1,43 @@
+use super::*;
+use crate::float::Float;
+
+/// A point in space. Points can be moved by vectors, and the difference of
+/// two points is a vector, but adding or scaling points is meaningless.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pos(pub Float, pub Float, pub Float);
+
+impl Pos {
+    pub const ORIGIN: Pos = Pos(0., 0., 0.);
+
+    #[inline(always)]
+    pub fn x(&self) -> Float {
+        self.0
+    }
+
+    #[inline(always)]
+    pub fn y(&self) -> Float {
+        self.1
+    }
+
+    #[inline(always)]
+    pub fn z(&self) -> Float {
+        self.2
+    }
+
+    /// The vector from the origin to this point
+    pub fn to_vec(&self) -> Vec3 {
+        Vec3(self.0, self.1, self.2)
+    }
+
+    /// Rotates the point around a unit-length axis through the origin
+    pub fn rotate_around(&self, axis: &Vec3, angle: Float) -> Pos {
+        Pos::ORIGIN + self.to_vec().rotate_around(axis, angle)
+    }
+}
+
+impl_vec3_binop!(Add, add, Pos, Vec3 => Pos);
+impl_vec3_binop!(AddAssign, add_assign, Pos, Vec3, assign);
+
+impl_vec3_binop!(Sub, sub, Pos, Vec3 => Pos);
+impl_vec3_binop!(Sub, sub, Pos, Pos => Vec3);
+impl_vec3_binop!(SubAssign, sub_assign, Pos, Vec3, assign);
@@ -0,0 +1,138 @@
+use super::*;
+use crate::float::Float;
+
+/// Unit quaternion for rotations, w + xi + yj + zk
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+    pub w: Float,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
+}
+
+impl Quat {
+    pub const IDENTITY: Quat = Quat {
+        w: 1.,
+        x: 0.,
+        y: 0.,
+        z: 0.,
+    };
+
+    /// Counterclockwise rotation around a unit-length axis
+    pub fn from_axis_angle(axis: Vec3, angle: Float) -> Self {
+        let (sin, cos) = (angle / 2.).sin_cos();
+        Quat {
+            w: cos,
+            x: axis.0 * sin,
+            y: axis.1 * sin,
+            z: axis.2 * sin,
+        }
+    }
+
+    fn vector(&self) -> Vec3 {
+        Vec3(self.x, self.y, self.z)
+    }
+
+    pub fn dot(&self, rhs: &Quat) -> Float {
+        self.w * rhs.w + self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn normalize(&self) -> Quat {
+        let len = self.dot(self).sqrt();
+        Quat {
+            w: self.w / len,
+            x: self.x / len,
+            y: self.y / len,
+            z: self.z / len,
+        }
+    }
+
+    /// The opposite rotation
+    pub fn conjugate(&self) -> Quat {
+        Quat {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        // v + 2w(q x v) + 2q x (q x v), with q the vector part
+        let q = self.vector();
+        let t = 2. * q.cross(v);
+        v + self.w * t + q.cross(&t)
+    }
+
+    /// Spherical linear interpolation, constant angular speed along the
+    /// shortest arc from self (t = 0) to other (t = 1)
+    pub fn slerp(&self, other: &Quat, t: Float) -> Quat {
+        // q and -q are the same rotation, take the one on the near side
+        let mut cos = self.dot(other);
+        let other = if cos < 0. {
+            cos = -cos;
+            Quat {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            }
+        } else {
+            *other
+        };
+
+        // nearly parallel, sin(theta) is too small to divide by
+        let (a, b) = if cos > 0.9995 {
+            (1. - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+
+        Quat {
+            w: a * self.w + b * other.w,
+            x: a * self.x + b * other.x,
+            y: a * self.y + b * other.y,
+            z: a * self.z + b * other.z,
+        }
+        .normalize()
+    }
+
+    pub fn to_mat3(&self) -> Mat3 {
+        let Quat { w, x, y, z } = *self;
+        Mat3([
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - w * z),
+                2. * (x * z + w * y),
+            ],
+            [
+                2. * (x * y + w * z),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - w * x),
+            ],
+            [
+                2. * (x * z - w * y),
+                2. * (y * z + w * x),
+                1. - 2. * (x * x + y * y),
+            ],
+        ])
+    }
+}
+
+impl std::ops::Mul for Quat {
+    type Output = Quat;
+
+    /// Hamilton product, rotates by rhs first, then self
+    fn mul(self, rhs: Quat) -> Self::Output {
+        let (a, b) = (self.vector(), rhs.vector());
+        let v = self.w * b + rhs.w * a + a.cross(&b);
+        Quat {
+            w: self.w * rhs.w - a.dot(&b),
+            x: v.0,
+            y: v.1,
+            z: v.2,
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! 4-wide versions of Float and Vec3 for testing several objects at once.
+//!
+//! With the `simd` feature on x86_64 the lanes live in SSE2 registers, or AVX
+//! registers when building with `-C target-feature=+avx`. With the `f32`
+//! feature all four lanes fit in a single SSE register. Everywhere else it's
+//! plain arrays, which the compiler can still often vectorize on its own.
+
+use super::*;
+use crate::float::Float;
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use std::arch::x86_64::*;
+
+/// Four Float lanes
+#[derive(Clone, Copy, Debug)]
+pub struct Floatx4(Lanes);
+
+/// Result of a lane-wise comparison, true lanes are all ones
+#[derive(Clone, Copy, Debug)]
+pub struct Mask4(Lanes);
+
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+type Lanes = [Float; 4];
+#[cfg(all(
+    feature = "simd",
+    target_arch = "x86_64",
+    not(feature = "f32"),
+    not(target_feature = "avx")
+))]
+type Lanes = [__m128d; 2];
+#[cfg(all(
+    feature = "simd",
+    target_arch = "x86_64",
+    not(feature = "f32"),
+    target_feature = "avx"
+))]
+type Lanes = __m256d;
+#[cfg(all(feature = "simd", target_arch = "x86_64", feature = "f32"))]
+type Lanes = __m128;
+
+// scalar fallback
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+impl Floatx4 {
+    pub fn new(lanes: [Float; 4]) -> Self {
+        Floatx4(lanes)
+    }
+
+    pub fn to_array(self) -> [Float; 4] {
+        self.0
+    }
+
+    fn zip(self, rhs: Floatx4, f: impl Fn(Float, Float) -> Float) -> Floatx4 {
+        Floatx4(std::array::from_fn(|i| f(self.0[i], rhs.0[i])))
+    }
+
+    pub fn sqrt(self) -> Floatx4 {
+        Floatx4(self.0.map(Float::sqrt))
+    }
+
+    pub fn lt(self, rhs: Floatx4) -> Mask4 {
+        let lane = |i: usize| match self.0[i] < rhs.0[i] {
+            true => Float::from_bits(!0),
+            false => 0.,
+        };
+        Mask4(std::array::from_fn(lane))
+    }
+
+    pub fn le(self, rhs: Floatx4) -> Mask4 {
+        let lane = |i: usize| match self.0[i] <= rhs.0[i] {
+            true => Float::from_bits(!0),
+            false => 0.,
+        };
+        Mask4(std::array::from_fn(lane))
+    }
+
+    /// Lanes of a where the mask is set, b elsewhere
+    pub fn select(mask: Mask4, a: Floatx4, b: Floatx4) -> Floatx4 {
+        Floatx4(std::array::from_fn(|i| match mask.0[i].to_bits() {
+            0 => b.0[i],
+            _ => a.0[i],
+        }))
+    }
+}
+
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+impl Mask4 {
+    pub fn and(self, rhs: Mask4) -> Mask4 {
+        Mask4(std::array::from_fn(|i| {
+            Float::from_bits(self.0[i].to_bits() & rhs.0[i].to_bits())
+        }))
+    }
+
+    /// Bit i is set if lane i is
+    pub fn bits(self) -> u32 {
+        (0..4).fold(0, |acc, i| acc | ((self.0[i].to_bits() != 0) as u32) << i)
+    }
+}
+
+// sse2 is part of x86_64, so it's always there and the intrinsics are sound
+// to call. the avx ones are only compiled in when avx is enabled
+#[cfg(all(
+    feature = "simd",
+    target_arch = "x86_64",
+    not(feature = "f32"),
+    not(target_feature = "avx")
+))]
+impl Floatx4 {
+    pub fn new(lanes: [Float; 4]) -> Self {
+        // SAFETY: both loads read two f64s from inside the array
+        unsafe {
+            Floatx4([
+                _mm_loadu_pd(lanes.as_ptr()),
+                _mm_loadu_pd(lanes.as_ptr().add(2)),
+            ])
+        }
+    }
+
+    pub fn to_array(self) -> [Float; 4] {
+        let mut lanes = [0.; 4];
+        // SAFETY: both stores write two f64s to inside the array
+        unsafe {
+            _mm_storeu_pd(lanes.as_mut_ptr(), self.0[0]);
+            _mm_storeu_pd(lanes.as_mut_ptr().add(2), self.0[1]);
+        }
+        lanes
+    }
+
+    fn zip(self, rhs: Floatx4, f: impl Fn(__m128d, __m128d) -> __m128d) -> Floatx4 {
+        Floatx4([f(self.0[0], rhs.0[0]), f(self.0[1], rhs.0[1])])
+    }
+
+    pub fn sqrt(self) -> Floatx4 {
+        Floatx4(self.0.map(|x| unsafe { _mm_sqrt_pd(x) }))
+    }
+
+    pub fn lt(self, rhs: Floatx4) -> Mask4 {
+        unsafe {
+            Mask4([
+                _mm_cmplt_pd(self.0[0], rhs.0[0]),
+                _mm_cmplt_pd(self.0[1], rhs.0[1]),
+            ])
+        }
+    }
+
+    pub fn le(self, rhs: Floatx4) -> Mask4 {
+        unsafe {
+            Mask4([
+                _mm_cmple_pd(self.0[0], rhs.0[0]),
+                _mm_cmple_pd(self.0[1], rhs.0[1]),
+            ])
+        }
+    }
+
+    /// Lanes of a where the mask is set, b elsewhere
+    pub fn select(mask: Mask4, a: Floatx4, b: Floatx4) -> Floatx4 {
+        let blend = |m, a, b| unsafe { _mm_or_pd(_mm_and_pd(m, a), _mm_andnot_pd(m, b)) };
+        Floatx4([
+            blend(mask.0[0], a.0[0], b.0[0]),
+            blend(mask.0[1], a.0[1], b.0[1]),
+        ])
+    }
+}
+
+#[cfg(all(
+    feature = "simd",
+    target_arch = "x86_64",
+    not(feature = "f32"),
+    not(target_feature = "avx")
+))]
+impl Mask4 {
+    pub fn and(self, rhs: Mask4) -> Mask4 {
+        unsafe {
+            Mask4([
+                _mm_and_pd(self.0[0], rhs.0[0]),
+                _mm_and_pd(self.0[1], rhs.0[1]),
+            ])
+        }
+    }
+
+    /// Bit i is set if lane i is
+    pub fn bits(self) -> u32 {
+        unsafe { (_mm_movemask_pd(self.0[0]) | (_mm_movemask_pd(self.0[1]) << 2)) as u32 }
+    }
+}
+
+#[cfg(all(
+    feature = "simd",
+    target_arch = "x86_64",
+    not(feature = "f32"),
+    target_feature = "avx"
+))]
+impl Floatx4 {
+    pub fn new(lanes: [Float; 4]) -> Self {
+        // SAFETY: reads four f64s from inside the array
+        unsafe { Floatx4(_mm256_loadu_pd(lanes.as_ptr())) }
+    }
+
+    pub fn to_array(self) -> [Float; 4] {
+        let mut lanes = [0.; 4];
+        // SAFETY: writes four f64s to inside the array
+        unsafe { _mm256_storeu_pd(lanes.as_mut_ptr(), self.0) };
+        lanes
+    }
+
+    fn zip(self, rhs: Floatx4, f: impl Fn(__m256d, __m256d) -> __m256d) -> Floatx4 {
+        Floatx4(f(self.0, rhs.0))
+    }
+
+    pub fn sqrt(self) -> Floatx4 {
+        Floatx4(unsafe { _mm256_sqrt_pd(self.0) })
+    }
+
+    pub fn lt(self, rhs: Floatx4) -> Mask4 {
+        Mask4(unsafe { _mm256_cmp_pd::<_CMP_LT_OQ>(self.0, rhs.0) })
+    }
+
+    pub fn le(self, rhs: Floatx4) -> Mask4 {
+        Mask4(unsafe { _mm256_cmp_pd::<_CMP_LE_OQ>(self.0, rhs.0) })
+    }
+
+    /// Lanes of a where the mask is set, b elsewhere
+    pub fn select(mask: Mask4, a: Floatx4, b: Floatx4) -> Floatx4 {
+        Floatx4(unsafe { _mm256_blendv_pd(b.0, a.0, mask.0) })
+    }
+}
+
+#[cfg(all(
+    feature = "simd",
+    target_arch = "x86_64",
+    not(feature = "f32"),
+    target_feature = "avx"
+))]
+impl Mask4 {
+    pub fn and(self, rhs: Mask4) -> Mask4 {
+        Mask4(unsafe { _mm256_and_pd(self.0, rhs.0) })
+    }
+
+    /// Bit i is set if lane i is
+    pub fn bits(self) -> u32 {
+        unsafe { _mm256_movemask_pd(self.0) as u32 }
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64", feature = "f32"))]
+impl Floatx4 {
+    pub fn new(lanes: [Float; 4]) -> Self {
+        // SAFETY: reads four f32s from inside the array
+        unsafe { Floatx4(_mm_loadu_ps(lanes.as_ptr())) }
+    }
+
+    pub fn to_array(self) -> [Float; 4] {
+        let mut lanes = [0.; 4];
+        // SAFETY: writes four f32s to inside the array
+        unsafe { _mm_storeu_ps(lanes.as_mut_ptr(), self.0) };
+        lanes
+    }
+
+    fn zip(self, rhs: Floatx4, f: impl Fn(__m128, __m128) -> __m128) -> Floatx4 {
+        Floatx4(f(self.0, rhs.0))
+    }
+
+    pub fn sqrt(self) -> Floatx4 {
+        Floatx4(unsafe { _mm_sqrt_ps(self.0) })
+    }
+
+    pub fn lt(self, rhs: Floatx4) -> Mask4 {
+        Mask4(unsafe { _mm_cmplt_ps(self.0, rhs.0) })
+    }
+
+    pub fn le(self, rhs: Floatx4) -> Mask4 {
+        Mask4(unsafe { _mm_cmple_ps(self.0, rhs.0) })
+    }
+
+    /// Lanes of a where the mask is set, b elsewhere
+    pub fn select(mask: Mask4, a: Floatx4, b: Floatx4) -> Floatx4 {
+        let m = mask.0;
+        Floatx4(unsafe { _mm_or_ps(_mm_and_ps(m, a.0), _mm_andnot_ps(m, b.0)) })
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64", feature = "f32"))]
+impl Mask4 {
+    pub fn and(self, rhs: Mask4) -> Mask4 {
+        Mask4(unsafe { _mm_and_ps(self.0, rhs.0) })
+    }
+
+    /// Bit i is set if lane i is
+    pub fn bits(self) -> u32 {
+        unsafe { _mm_movemask_ps(self.0) as u32 }
+    }
+}
+
+impl Floatx4 {
+    pub fn splat(x: Float) -> Self {
+        Floatx4::new([x; 4])
+    }
+}
+
+// the backends only differ in how a single lane-wise op is done
+macro_rules! impl_floatx4_binop {
+    ($trait_name:ident, $method_name:ident, $op:tt, $sse2:ident, $avx:ident, $sse_f32:ident) => {
+        impl std::ops::$trait_name for Floatx4 {
+            type Output = Floatx4;
+
+            #[inline(always)]
+            fn $method_name(self, rhs: Floatx4) -> Self::Output {
+                #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+                return self.zip(rhs, |a, b| a $op b);
+                #[cfg(all(
+                    feature = "simd",
+                    target_arch = "x86_64",
+                    not(feature = "f32"),
+                    not(target_feature = "avx")
+                ))]
+                return self.zip(rhs, |a, b| unsafe { $sse2(a, b) });
+                #[cfg(all(
+                    feature = "simd",
+                    target_arch = "x86_64",
+                    not(feature = "f32"),
+                    target_feature = "avx"
+                ))]
+                return self.zip(rhs, |a, b| unsafe { $avx(a, b) });
+                #[cfg(all(feature = "simd", target_arch = "x86_64", feature = "f32"))]
+                return self.zip(rhs, |a, b| unsafe { $sse_f32(a, b) });
+            }
+        }
+    };
+}
+
+impl_floatx4_binop!(Add, add, +, _mm_add_pd, _mm256_add_pd, _mm_add_ps);
+impl_floatx4_binop!(Sub, sub, -, _mm_sub_pd, _mm256_sub_pd, _mm_sub_ps);
+impl_floatx4_binop!(Mul, mul, *, _mm_mul_pd, _mm256_mul_pd, _mm_mul_ps);
+impl_floatx4_binop!(Div, div, /, _mm_div_pd, _mm256_div_pd, _mm_div_ps);
+
+/// Four vectors stored by component, so each op works on all four at once
+#[derive(Clone, Copy, Debug)]
+pub struct Vec3x4 {
+    pub x: Floatx4,
+    pub y: Floatx4,
+    pub z: Floatx4,
+}
+
+impl Vec3x4 {
+    pub fn new(v: [Vec3; 4]) -> Self {
+        Vec3x4 {
+            x: Floatx4::new(v.map(|v| v.0)),
+            y: Floatx4::new(v.map(|v| v.1)),
+            z: Floatx4::new(v.map(|v| v.2)),
+        }
+    }
+
+    pub fn splat(v: Vec3) -> Self {
+        Vec3x4 {
+            x: Floatx4::splat(v.0),
+            y: Floatx4::splat(v.1),
+            z: Floatx4::splat(v.2),
+        }
+    }
+
+    pub fn dot(&self, rhs: &Vec3x4) -> Floatx4 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn length_squared(&self) -> Floatx4 {
+        self.dot(self)
+    }
+}
+
+impl std::ops::Add for Vec3x4 {
+    type Output = Vec3x4;
+
+    fn add(self, rhs: Vec3x4) -> Self::Output {
+        Vec3x4 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl std::ops::Sub for Vec3x4 {
+    type Output = Vec3x4;
+
+    fn sub(self, rhs: Vec3x4) -> Self::Output {
+        Vec3x4 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl std::ops::Mul<Floatx4> for Vec3x4 {
+    type Output = Vec3x4;
+
+    fn mul(self, rhs: Floatx4) -> Self::Output {
+        Vec3x4 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}